@@ -19,8 +19,8 @@ impl ApiV3Client {
     }
 
     /// List downloading tasks
-    pub async fn list_downloading(&self) -> Result<Vec<Aria2Task>, Error> {
-        let response: ApiResponse<Vec<Aria2Task>> = self.get("/aria2/downloading").await?;
+    pub async fn list_downloading(&self) -> Result<Vec<Aria2Downloading>, Error> {
+        let response: ApiResponse<Vec<Aria2Downloading>> = self.get("/aria2/downloading").await?;
         match response.data {
             Some(tasks) => Ok(tasks),
             None => Err(Error::Api {
@@ -30,9 +30,10 @@ impl ApiV3Client {
         }
     }
 
-    /// List finished tasks
-    pub async fn list_finished(&self) -> Result<Vec<Aria2Task>, Error> {
-        let response: ApiResponse<Vec<Aria2Task>> = self.get("/aria2/finished").await?;
+    /// List finished tasks, one page at a time
+    pub async fn list_finished(&self, page: u32) -> Result<Vec<Aria2Finished>, Error> {
+        let response: ApiResponse<Vec<Aria2Finished>> =
+            self.get(&format!("/aria2/finished?page={}", page)).await?;
         match response.data {
             Some(tasks) => Ok(tasks),
             None => Err(Error::Api {
@@ -54,4 +55,18 @@ impl ApiV3Client {
             })
         }
     }
+
+    /// Choose which files of a torrent to download
+    pub async fn select_files(&self, gid: &str, request: &Aria2SelectRequest) -> Result<(), Error> {
+        let response: ApiResponse<()> =
+            self.put(&format!("/aria2/select/{}", gid), request).await?;
+        if response.code == 0 {
+            Ok(())
+        } else {
+            Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            })
+        }
+    }
 }
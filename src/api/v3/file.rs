@@ -36,13 +36,26 @@ impl ApiV3Client {
         }
     }
 
+    pub async fn delete_upload_session(&self, session_id: &str) -> Result<(), Error> {
+        let response: ApiResponse<()> =
+            self.delete(&format!("/file/upload/{}", session_id)).await?;
+        if response.code == 0 {
+            Ok(())
+        } else {
+            Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            })
+        }
+    }
+
     pub async fn upload_chunk(
         &self,
         session_id: &str,
-        _chunk_index: u32,
+        chunk_index: u32,
         data: Vec<u8>,
     ) -> Result<(), Error> {
-        let url = self.get_url(&format!("/file/upload/{}/0", session_id));
+        let url = self.get_url(&format!("/file/upload/{}/{}", session_id, chunk_index));
         let mut request = self.http_client.post(&url).body(data);
 
         if let Some(cookie) = &self.session_cookie {
@@ -88,24 +101,43 @@ impl ApiV3Client {
         }
     }
 
-    pub async fn preview_file(&self, id: &str) -> Result<DirectoryList, Error> {
-        let response: ApiResponse<DirectoryList> =
-            self.get(&format!("/file/preview/{}", id)).await?;
-        match response.data {
-            Some(list) => Ok(list),
-            None => Err(Error::Api {
-                code: response.code,
-                message: response.msg,
-            }),
-        }
+    /// Fetch the preview content of a file; the server may redirect to storage
+    pub async fn preview_file(&self, id: &str) -> Result<reqwest::Response, Error> {
+        self.get_raw(&format!("/file/preview/{}", id)).await
     }
 
-    pub async fn get_thumbnail(&self, id: &str) -> Result<DirectoryList, Error> {
-        let response: ApiResponse<DirectoryList> = self.get(&format!("/file/thumb/{}", id)).await?;
-        match response.data {
-            Some(list) => Ok(list),
-            None => Err(Error::Api {
-                code: response.code,
+    /// Fetch the thumbnail image of a file; the server may redirect to storage
+    pub async fn get_thumbnail(&self, id: &str) -> Result<reqwest::Response, Error> {
+        self.get_raw(&format!("/file/thumb/{}", id)).await
+    }
+
+    /// Fetch the raw content of a text file
+    pub async fn get_file_content(&self, id: &str) -> Result<reqwest::Response, Error> {
+        self.get_raw(&format!("/file/content/{}", id)).await
+    }
+
+    /// Replace the content of a file
+    pub async fn update_file_content(&self, id: &str, data: Vec<u8>) -> Result<(), Error> {
+        let url = self.get_url(&format!("/file/update/{}", id));
+        let mut request = self.http_client.put(&url).body(data);
+        if let Some(cookie) = &self.session_cookie {
+            request = request.header("Cookie", format!("cloudreve-session={}", cookie));
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(Error::Api {
+                code: status.as_u16() as i32,
+                message: text.trim().to_string(),
+            });
+        }
+        let response: ApiResponse<serde_json::Value> = serde_json::from_str(&text)?;
+        match response.code {
+            0 => Ok(()),
+            code => Err(Error::Api {
+                code,
                 message: response.msg,
             }),
         }
@@ -122,4 +154,19 @@ impl ApiV3Client {
             })
         }
     }
+
+    /// Search files by keyword
+    pub async fn search_files(&self, keyword: &str) -> Result<DirectoryList, Error> {
+        let encoded = urlencoding::encode(keyword);
+        let response: ApiResponse<DirectoryList> = self
+            .get(&format!("/file/search/keywords/{}", encoded))
+            .await?;
+        match response.data {
+            Some(list) => Ok(list),
+            None => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+        }
+    }
 }
@@ -44,6 +44,25 @@ impl ApiV3Client {
         self.session_cookie = None;
     }
 
+    /// Keep the session cookie from a response's Set-Cookie headers
+    ///
+    /// Format: "cloudreve-session=VALUE; Path=/; HttpOnly" etc.
+    pub(crate) fn store_session_cookie(&mut self, headers: &reqwest::header::HeaderMap) {
+        let session_value = headers
+            .get_all("Set-Cookie")
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|cookie| cookie.split(';'))
+            .find_map(|part| part.trim().strip_prefix("cloudreve-session="));
+        if let Some(session_value) = session_value {
+            debug!(
+                "Extracted V3 session cookie: {}...",
+                &session_value[..session_value.len().min(20)]
+            );
+            self.session_cookie = Some(session_value.to_string());
+        }
+    }
+
     pub fn get_url(&self, endpoint: &str) -> String {
         format!(
             "{}/api/v3/{}",
@@ -153,6 +172,32 @@ impl ApiV3Client {
         }
     }
 
+    /// GET request that returns the response body unparsed, e.g. for images
+    pub async fn get_raw(&self, endpoint: &str) -> Result<reqwest::Response, Error> {
+        let url = self.get_url(endpoint);
+        let mut request = self.http_client.get(&url);
+
+        if let Some(cookie) = &self.session_cookie {
+            request = request.header("Cookie", format!("cloudreve-session={}", cookie));
+            debug!("cookie: {}", cookie);
+        }
+
+        debug!("GET RAW URL: {}", url);
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let raw_text = response.text().await?;
+            return Err(Error::Api {
+                code: status.as_u16() as i32,
+                message: raw_text.trim().to_string(),
+            });
+        }
+
+        Ok(response)
+    }
+
     /// POST request that returns raw text instead of parsing JSON
     pub async fn post_raw(&self, endpoint: &str, body: &impl Serialize) -> Result<String, Error> {
         let url = self.get_url(endpoint);
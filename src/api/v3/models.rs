@@ -103,7 +103,7 @@ pub struct Property {
 }
 
 /// Directory listing response for v3 API
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DirectoryList {
     #[serde(default)]
     pub parent: String,
@@ -156,8 +156,25 @@ pub struct StorageInfo {
     pub total: i64,
 }
 
+/// User settings for v3 API
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct UserSetting {
+    #[serde(default)]
+    pub uid: i64,
+    #[serde(default)]
+    pub qq: bool,
+    #[serde(default)]
+    pub homepage: bool,
+    #[serde(default)]
+    pub two_factor: bool,
+    #[serde(default)]
+    pub prefer_theme: String,
+    #[serde(default)]
+    pub themes: String,
+}
+
 /// Share link information for v3 API
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct Share {
     #[serde(default)]
     pub key: String,
@@ -177,8 +194,46 @@ pub struct Share {
     pub owner_id: String,
     #[serde(default)]
     pub owner_nick: String,
-    #[serde(default)]
+    #[serde(default, alias = "create_date")]
     pub created_at: String,
+    #[serde(default)]
+    pub views: i64,
+    #[serde(default)]
+    pub source: Option<ShareSource>,
+    /// Set in share info while the share's password has not been given
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub creator: Option<ShareCreator>,
+}
+
+/// Owner summary included in v3 share info
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ShareCreator {
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub nick: String,
+    #[serde(default)]
+    pub group_name: String,
+}
+
+/// Shared object summary included in v3 share listings
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ShareSource {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub size: i64,
+}
+
+/// Paginated share list for v3 API
+#[derive(Debug, Deserialize, Default)]
+pub struct ShareList {
+    #[serde(default)]
+    pub items: Vec<Share>,
+    #[serde(default)]
+    pub total: i64,
 }
 
 /// Share request for v3 API
@@ -192,6 +247,14 @@ pub struct ShareRequest {
     pub preview: bool,
 }
 
+/// Share property update request for v3 API
+#[derive(Debug, Serialize)]
+pub struct ShareUpdateRequest<'a> {
+    /// `password` or `preview_enabled`
+    pub prop: &'a str,
+    pub value: String,
+}
+
 /// Site configuration for v3 API
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SiteConfig {
@@ -333,6 +396,102 @@ pub struct Aria2CreateRequest<'a> {
     pub url: Vec<&'a str>,
 }
 
+/// File inside an aria2 download for v3 API
+///
+/// aria2 reports numbers and flags as strings.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Aria2File {
+    #[serde(default)]
+    pub index: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub length: String,
+    #[serde(default, rename = "completedLength")]
+    pub completed_length: String,
+    #[serde(default)]
+    pub selected: String,
+}
+
+/// aria2 status of an active download for v3 API
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Aria2StatusInfo {
+    #[serde(default)]
+    pub gid: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, rename = "totalLength")]
+    pub total_length: String,
+    #[serde(default, rename = "completedLength")]
+    pub completed_length: String,
+    #[serde(default, rename = "downloadSpeed")]
+    pub download_speed: String,
+    #[serde(default)]
+    pub files: Vec<Aria2File>,
+}
+
+/// Active remote download for v3 API
+///
+/// `status` is 0 ready, 1 downloading, 2 paused, 3 error, 4 complete,
+/// 5 canceled, 6 unknown or 7 seeding.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Aria2Downloading {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub status: i32,
+    #[serde(default)]
+    pub dst: String,
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub downloaded: u64,
+    #[serde(default)]
+    pub speed: i64,
+    #[serde(default)]
+    pub info: Aria2StatusInfo,
+    #[serde(default)]
+    pub node: String,
+}
+
+/// Finished remote download for v3 API
+///
+/// `task_status` tracks moving the files into the user's storage: 0 queued,
+/// 1 processing, 2 error, 3 canceled or 4 complete.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Aria2Finished {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub gid: String,
+    #[serde(default)]
+    pub status: i32,
+    #[serde(default)]
+    pub dst: String,
+    #[serde(default)]
+    pub error: String,
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub files: Vec<Aria2File>,
+    #[serde(default)]
+    pub task_status: i32,
+    #[serde(default)]
+    pub task_error: String,
+    #[serde(default)]
+    pub create: String,
+    #[serde(default)]
+    pub update: String,
+    #[serde(default)]
+    pub node: String,
+}
+
+/// Torrent file selection request for v3 API
+#[derive(Debug, Serialize)]
+pub struct Aria2SelectRequest {
+    pub indexes: Vec<i32>,
+}
+
 /// WebDAV account information for v3 API
 #[derive(Debug, Deserialize)]
 pub struct WebdavAccount {
@@ -22,13 +22,178 @@ impl ApiV3Client {
             });
         }
 
+        // Newer v3 servers wrap the share URL in the response
+        if let Ok(api_response) = serde_json::from_str::<ApiResponse<String>>(&raw_text) {
+            return match api_response.data {
+                Some(url) => Ok(Share {
+                    key: share_key_from_url(&url),
+                    ..Default::default()
+                }),
+                None => Err(Error::Api {
+                    code: api_response.code,
+                    message: api_response.msg,
+                }),
+            };
+        }
+
         // If that fails, try to parse as plain string URL
         let url = raw_text.trim();
-        // Extract key from URL (e.g., "https://example.com/s/abc123" -> "abc123")
-        let key = url.split('/').next_back().unwrap_or("").to_string();
         Ok(Share {
-            key,
+            key: share_key_from_url(url),
             ..Default::default()
         })
     }
+
+    /// Change a property of a share link
+    ///
+    /// v3 only allows changing `password` and `preview_enabled`.
+    pub async fn update_share(
+        &self,
+        key: &str,
+        request: &ShareUpdateRequest<'_>,
+    ) -> Result<(), Error> {
+        let response: ApiResponse<serde_json::Value> =
+            self.patch(&format!("/share/{}", key), request).await?;
+        if response.code == 0 {
+            Ok(())
+        } else {
+            Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            })
+        }
+    }
+
+    /// Delete a share link
+    pub async fn delete_share(&self, key: &str) -> Result<(), Error> {
+        let response: ApiResponse<()> = self.delete(&format!("/share/{}", key)).await?;
+        if response.code == 0 {
+            Ok(())
+        } else {
+            Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            })
+        }
+    }
+
+    /// List the current user's share links, one page at a time
+    pub async fn list_shares(&self, page: u32) -> Result<ShareList, Error> {
+        let response: ApiResponse<ShareList> = self
+            .get(&format!(
+                "/share?page={}&order_by=created_at&order=DESC",
+                page
+            ))
+            .await?;
+        match response.data {
+            Some(list) => Ok(list),
+            None => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+        }
+    }
+
+    /// Get the public information of a share
+    ///
+    /// Works without login. A correct password unlocks the share for the
+    /// current session, so the session cookie is updated from the response.
+    pub async fn get_share_info(
+        &mut self,
+        key: &str,
+        password: Option<&str>,
+    ) -> Result<Share, Error> {
+        let mut url = self.get_url(&format!("/share/info/{}", key));
+        if let Some(password) = password {
+            url.push_str(&format!("?password={}", urlencoding::encode(password)));
+        }
+        let mut http_request = self.http_client.get(&url);
+
+        if let Some(cookie) = &self.session_cookie {
+            http_request = http_request.header("Cookie", format!("cloudreve-session={}", cookie));
+        }
+
+        let response = http_request.send().await?;
+        self.store_session_cookie(response.headers());
+
+        let api_response: ApiResponse<Share> = response.json().await?;
+        match api_response.data {
+            Some(share) => Ok(share),
+            None => Err(Error::Api {
+                code: api_response.code,
+                message: api_response.msg,
+            }),
+        }
+    }
+
+    /// List a directory inside a shared folder
+    pub async fn list_shared_directory(
+        &self,
+        key: &str,
+        path: &str,
+    ) -> Result<DirectoryList, Error> {
+        let encoded_path = urlencoding::encode(path);
+        let response: ApiResponse<DirectoryList> = self
+            .get(&format!("/share/list/{}{}", key, encoded_path))
+            .await?;
+        match response.data {
+            Some(list) => Ok(list),
+            None => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+        }
+    }
+
+    /// Get a download URL for a shared file
+    ///
+    /// `path` selects a file inside a shared folder; leave it out for a
+    /// shared file.
+    pub async fn get_shared_download_url(
+        &self,
+        key: &str,
+        path: Option<&str>,
+    ) -> Result<DownloadUrl, Error> {
+        let mut endpoint = format!("/share/download/{}", key);
+        if let Some(path) = path {
+            endpoint.push_str(&format!("?path={}", urlencoding::encode(path)));
+        }
+        let response: ApiResponse<String> = self.put(&endpoint, &serde_json::json!({})).await?;
+        match response.data {
+            Some(url) => Ok(DownloadUrl { url }),
+            None => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+        }
+    }
+
+    /// Create an archive of objects inside a shared folder
+    pub async fn create_shared_archive(
+        &self,
+        key: &str,
+        items: &SourceItems<'_>,
+    ) -> Result<DownloadUrl, Error> {
+        let response: ApiResponse<String> =
+            self.post(&format!("/share/archive/{}", key), items).await?;
+        match response.data {
+            Some(url) => Ok(DownloadUrl { url }),
+            None => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+        }
+    }
+}
+
+/// Extract the share key from a share URL
+///
+/// e.g. "https://example.com/s/abc123" -> "abc123"
+fn share_key_from_url(url: &str) -> String {
+    url.trim()
+        .trim_end_matches('/')
+        .split('/')
+        .next_back()
+        .unwrap_or("")
+        .to_string()
 }
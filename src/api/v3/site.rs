@@ -54,8 +54,8 @@ impl ApiV3Client {
     }
 
     /// Get user settings
-    pub async fn get_user_settings(&self) -> Result<StorageInfo, Error> {
-        let response: ApiResponse<StorageInfo> = self.get("/user/setting").await?;
+    pub async fn get_user_settings(&self) -> Result<UserSetting, Error> {
+        let response: ApiResponse<UserSetting> = self.get("/user/setting").await?;
         match response.data {
             Some(settings) => Ok(settings),
             None => Err(Error::Api {
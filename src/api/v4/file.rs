@@ -294,9 +294,9 @@ impl ApiV4Client {
             url.push_str(&format!("&height={}", h));
         }
 
-        let response: ApiResponse<String> = self.get(&url).await?;
+        let response: ApiResponse<ThumbnailResponse> = self.get(&url).await?;
         match response.data {
-            Some(data) => Ok(data),
+            Some(data) => Ok(data.url().to_string()),
             None => Err(Error::InvalidResponse(format!(
                 "API returned no data for get_thumbnail_url request: {:?}",
                 response
@@ -330,6 +330,51 @@ impl ApiV4Client {
         }
     }
 
+    /// Replace the content of a file with raw bytes
+    ///
+    /// With `previous` set to the primary entity ID the content was read
+    /// from, the server refuses the update if the file changed since.
+    pub async fn put_file_content(
+        &self,
+        path: &str,
+        data: Vec<u8>,
+        previous: Option<&str>,
+    ) -> Result<File, Error> {
+        let uri = path_to_uri(path);
+        let mut query = vec![("uri", uri.as_str())];
+        if let Some(previous) = previous {
+            query.push(("previous", previous));
+        }
+        let mut request = self
+            .http_client
+            .put(self.get_url("/file/content"))
+            .query(&query)
+            .body(data);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(Error::Api {
+                code: status.as_u16() as i32,
+                message: text.trim().to_string(),
+            });
+        }
+        let response: ApiResponse<File> = serde_json::from_str(&text)?;
+        if response.code != 0 {
+            return Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            });
+        }
+        response
+            .data
+            .ok_or_else(|| Error::InvalidResponse("Missing data in API response".to_string()))
+    }
+
     pub async fn create_viewer_session(
         &self,
         request: &CreateViewerSessionRequest<'_>,
@@ -345,6 +390,32 @@ impl ApiV4Client {
         }
     }
 
+    pub async fn create_direct_links(
+        &self,
+        request: &CreateDirectLinkRequest,
+    ) -> Result<Vec<DirectLinkResponse>, Error> {
+        let response: ApiResponse<Vec<DirectLinkResponse>> =
+            self.put("/file/source", request).await?;
+        match response.data {
+            Some(data) => Ok(data),
+            None => Err(Error::InvalidResponse(format!(
+                "API returned no data for create_direct_links request: {:?}",
+                response
+            ))),
+        }
+    }
+
+    pub async fn delete_direct_link(&self, id: &str) -> Result<(), Error> {
+        let response: ApiResponse<()> = self.delete(&format!("/file/source/{}", id)).await?;
+        match response.code {
+            0 => Ok(()),
+            code => Err(Error::Api {
+                code,
+                message: response.msg,
+            }),
+        }
+    }
+
     pub async fn create_file(&self, request: &CreateFileRequest<'_>) -> Result<File, Error> {
         let response: ApiResponse<File> = self.post("/file/create", request).await?;
         match response.data {
@@ -452,6 +523,18 @@ impl ApiV4Client {
         }
     }
 
+    /// Apply metadata patches to several files at once
+    pub async fn patch_metadata_batch(&self, request: &PatchMetadataRequest) -> Result<(), Error> {
+        let response: ApiResponse<()> = self.patch("/file/metadata", request).await?;
+        match response.code {
+            0 => Ok(()),
+            code => Err(Error::Api {
+                code,
+                message: response.msg,
+            }),
+        }
+    }
+
     pub async fn mount_storage_policy(
         &self,
         path: &str,
@@ -1,7 +1,10 @@
 //! File-related models for Cloudreve API v4
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// File or folder metadata
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,6 +26,44 @@ pub struct File {
     pub owned: bool,
     #[serde(default)]
     pub primary_entity: Option<String>,
+    /// Only present when requested with `extended=true`
+    #[serde(default)]
+    pub extended_info: Option<Box<ExtendedInfo>>,
+}
+
+impl File {
+    /// Decode the current user's permissions on the file, if sent
+    pub fn permissions(&self) -> Option<PermissionSet> {
+        PermissionSet::from_boolset(self.permission.as_deref()?).ok()
+    }
+
+    /// Decode the operations allowed on the file, if sent
+    ///
+    /// Listings usually leave this empty and send the capabilities for all
+    /// files in [`NavigatorProps::capability`] instead.
+    pub fn capabilities(&self) -> Option<CapabilitySet> {
+        CapabilitySet::from_boolset(self.capability.as_deref()?)
+    }
+}
+
+/// Decode a boolean set as sent by the server into its first 64 bits
+///
+/// The set is base64 of the bitset bytes, where bit `n` is bit `n % 8` of
+/// byte `n / 8`.
+fn decode_boolset(encoded: &str) -> Result<u64, base64::DecodeError> {
+    let bytes = BASE64.decode(encoded)?;
+    Ok(bytes
+        .iter()
+        .take(8)
+        .enumerate()
+        .fold(0, |bits, (i, byte)| bits | u64::from(*byte) << (8 * i)))
+}
+
+/// Encode bits as a boolean set for the server
+fn encode_boolset(bits: u64) -> String {
+    let bytes = bits.to_le_bytes();
+    let used = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    BASE64.encode(&bytes[..used])
 }
 
 /// File type enum
@@ -91,6 +132,103 @@ pub struct NavigatorProps {
     pub order_direction_options: Vec<String>,
 }
 
+impl NavigatorProps {
+    /// Decode the operations allowed on the listed files
+    pub fn capabilities(&self) -> Option<CapabilitySet> {
+        CapabilitySet::from_boolset(&self.capability)
+    }
+}
+
+/// An operation on a file or folder
+///
+/// Values are the bit positions of the server's navigator capabilities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileCapability {
+    CreateFile = 0,
+    Rename = 1,
+    Upload = 6,
+    Download = 7,
+    UpdateMetadata = 8,
+    ListChildren = 9,
+    GenerateThumbnail = 10,
+    Delete = 14,
+    Lock = 15,
+    SoftDelete = 16,
+    Restore = 17,
+    Share = 18,
+    Info = 19,
+    VersionControl = 20,
+    EnterFolder = 22,
+}
+
+impl FileCapability {
+    /// All operations this client knows
+    pub const ALL: [FileCapability; 15] = [
+        FileCapability::CreateFile,
+        FileCapability::Rename,
+        FileCapability::Upload,
+        FileCapability::Download,
+        FileCapability::UpdateMetadata,
+        FileCapability::ListChildren,
+        FileCapability::GenerateThumbnail,
+        FileCapability::Delete,
+        FileCapability::Lock,
+        FileCapability::SoftDelete,
+        FileCapability::Restore,
+        FileCapability::Share,
+        FileCapability::Info,
+        FileCapability::VersionControl,
+        FileCapability::EnterFolder,
+    ];
+}
+
+/// The operations allowed on a file or folder
+///
+/// Decoded from the server's boolean set; bits of operations this client
+/// does not know are kept but not named.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// Decode a boolean set as sent by the server
+    ///
+    /// `None` if the value is empty or not a boolean set.
+    pub fn from_boolset(encoded: &str) -> Option<Self> {
+        if encoded.is_empty() {
+            return None;
+        }
+        decode_boolset(encoded).ok().map(Self)
+    }
+
+    /// Encode as a boolean set
+    pub fn to_boolset(self) -> String {
+        encode_boolset(self.0)
+    }
+
+    /// Add an operation
+    pub const fn with(self, capability: FileCapability) -> Self {
+        Self(self.0 | 1 << capability as u64)
+    }
+
+    /// Check whether an operation is allowed
+    pub const fn contains(self, capability: FileCapability) -> bool {
+        self.0 & 1 << capability as u64 != 0
+    }
+
+    /// Iterate over the allowed operations this client knows
+    pub fn iter(self) -> impl Iterator<Item = FileCapability> {
+        FileCapability::ALL
+            .into_iter()
+            .filter(move |capability| self.contains(*capability))
+    }
+}
+
+impl FromIterator<FileCapability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = FileCapability>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::default(), Self::with)
+    }
+}
+
 /// Explorer view settings
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExplorerView {
@@ -163,19 +301,233 @@ pub struct FolderSummary {
     pub calculated_at: String,
 }
 
+/// A single file permission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Read = 0,
+    Update = 1,
+    Create = 2,
+    Delete = 3,
+}
+
+/// A set of file permissions
+///
+/// Serialized like the server's boolean sets: a base64 string of the
+/// bitset bytes, where permission `n` is bit `n % 8` of byte `n / 8`. The
+/// words `read`, `write` and `none` are accepted when deserializing as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PermissionSet(u32);
+
+impl PermissionSet {
+    /// A set without permissions
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// A set with only [`Permission::Read`]
+    pub const fn read_only() -> Self {
+        Self::empty().with(Permission::Read)
+    }
+
+    /// A set with all permissions
+    pub const fn read_write() -> Self {
+        Self::read_only()
+            .with(Permission::Update)
+            .with(Permission::Create)
+            .with(Permission::Delete)
+    }
+
+    /// Add a permission
+    pub const fn with(self, permission: Permission) -> Self {
+        Self(self.0 | 1 << permission as u32)
+    }
+
+    /// Remove a permission
+    pub const fn without(self, permission: Permission) -> Self {
+        Self(self.0 & !(1 << permission as u32))
+    }
+
+    /// Check whether the set contains a permission
+    pub const fn contains(self, permission: Permission) -> bool {
+        self.0 & 1 << permission as u32 != 0
+    }
+
+    /// Combine the permissions of two sets
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_readable(self) -> bool {
+        self.contains(Permission::Read)
+    }
+
+    pub const fn is_writable(self) -> bool {
+        self.contains(Permission::Update)
+    }
+
+    pub const fn can_create(self) -> bool {
+        self.contains(Permission::Create)
+    }
+
+    pub const fn can_delete(self) -> bool {
+        self.contains(Permission::Delete)
+    }
+
+    /// Decode a boolean set as sent by the server
+    ///
+    /// Bits past the first 32 are dropped.
+    pub fn from_boolset(encoded: &str) -> Result<Self, base64::DecodeError> {
+        Ok(Self(decode_boolset(encoded)? as u32))
+    }
+
+    /// Encode as a boolean set for the server
+    pub fn to_boolset(self) -> String {
+        encode_boolset(u64::from(self.0))
+    }
+}
+
+impl Serialize for PermissionSet {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_boolset())
+    }
+}
+
+impl<'de> Deserialize<'de> for PermissionSet {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "read" => Ok(PermissionSet::read_only()),
+            "write" => Ok(PermissionSet::read_write()),
+            "none" => Ok(PermissionSet::empty()),
+            encoded => PermissionSet::from_boolset(encoded).map_err(|e| {
+                serde::de::Error::custom(format!("Invalid permission set {}: {}", encoded, e))
+            }),
+        }
+    }
+}
+
+/// Deserialize a map that the server may send as `null`
+fn null_as_empty<'de, D>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<String, PermissionSet>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
 /// Permission settings
-#[derive(Debug, Serialize, Deserialize, Clone)]
+///
+/// Explicit grants are keyed by user or group ID. Unset classes fall back
+/// to the server's defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct PermissionSetting {
-    #[serde(rename = "user_explicit")]
-    pub user_explicit: Value,
-    #[serde(rename = "group_explicit")]
-    pub group_explicit: Value,
-    #[serde(rename = "same_group")]
-    pub same_group: String,
-    #[serde(rename = "other")]
-    pub other: String,
-    #[serde(rename = "anonymous")]
-    pub anonymous: String,
-    #[serde(rename = "everyone")]
-    pub everyone: String,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub user_explicit: BTreeMap<String, PermissionSet>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub group_explicit: BTreeMap<String, PermissionSet>,
+    /// Users in the owner's group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_group: Option<PermissionSet>,
+    /// Signed-in users outside the owner's group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other: Option<PermissionSet>,
+    /// Visitors who are not signed in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anonymous: Option<PermissionSet>,
+    /// All signed-in users, in addition to their class
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub everyone: Option<PermissionSet>,
+}
+
+/// Who permissions are evaluated for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PermissionSubject<'a> {
+    /// `None` for visitors who are not signed in
+    pub user_id: Option<&'a str>,
+    pub group_id: Option<&'a str>,
+    /// Whether the user is in the owner's group
+    pub same_group: bool,
+}
+
+impl PermissionSetting {
+    /// Settings that let everybody read
+    pub fn read_only() -> Self {
+        let read = Some(PermissionSet::read_only());
+        Self {
+            same_group: read,
+            other: read,
+            anonymous: read,
+            everyone: read,
+            ..Default::default()
+        }
+    }
+
+    /// Grant permissions to a user
+    pub fn grant_user(mut self, user_id: impl Into<String>, permissions: PermissionSet) -> Self {
+        self.user_explicit.insert(user_id.into(), permissions);
+        self
+    }
+
+    /// Grant permissions to a group
+    pub fn grant_group(mut self, group_id: impl Into<String>, permissions: PermissionSet) -> Self {
+        self.group_explicit.insert(group_id.into(), permissions);
+        self
+    }
+
+    pub fn with_same_group(mut self, permissions: PermissionSet) -> Self {
+        self.same_group = Some(permissions);
+        self
+    }
+
+    pub fn with_other(mut self, permissions: PermissionSet) -> Self {
+        self.other = Some(permissions);
+        self
+    }
+
+    pub fn with_anonymous(mut self, permissions: PermissionSet) -> Self {
+        self.anonymous = Some(permissions);
+        self
+    }
+
+    pub fn with_everyone(mut self, permissions: PermissionSet) -> Self {
+        self.everyone = Some(permissions);
+        self
+    }
+
+    /// Compute the permissions a subject has
+    ///
+    /// Visitors get the anonymous permissions. For signed-in users an
+    /// explicit user grant wins over a group grant, which wins over the
+    /// same-group or other class; the everyone permissions are added to
+    /// the class permissions only.
+    pub fn effective(&self, subject: &PermissionSubject) -> PermissionSet {
+        let Some(user_id) = subject.user_id else {
+            return self.anonymous.unwrap_or_default();
+        };
+        if let Some(explicit) = self.user_explicit.get(user_id) {
+            return *explicit;
+        }
+        if let Some(explicit) = subject.group_id.and_then(|g| self.group_explicit.get(g)) {
+            return *explicit;
+        }
+        let class = if subject.same_group {
+            self.same_group
+        } else {
+            self.other
+        };
+        class
+            .unwrap_or_default()
+            .union(self.everyone.unwrap_or_default())
+    }
 }
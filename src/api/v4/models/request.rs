@@ -54,18 +54,8 @@ pub struct SetFilePermissionRequest<'a> {
     /// - Relative path: "folder/file.txt"
     /// - Already formatted URI: "cloudreve://my/folder/file.txt"
     pub uri: &'a str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user_explicit: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub group_explicit: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub same_group: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub other: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub anonymous: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub everyone: Option<&'a str>,
+    #[serde(flatten)]
+    pub permissions: &'a super::file::PermissionSetting,
 }
 
 /// Create upload session request
@@ -142,6 +132,14 @@ pub struct CreateViewerSessionRequest<'a> {
     /// - Relative path: "folder/file.txt"
     /// - Already formatted URI: "cloudreve://my/folder/file.txt"
     pub uri: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewer_id: Option<&'a str>,
+    /// "view", "edit" or "embedview"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_action: Option<&'a str>,
+    /// Entity ID of the version to open; the current one when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'a str>,
 }
 
 /// Create file request
@@ -209,6 +207,30 @@ pub struct UpdateMetadataRequest {
     pub clear_metadata: Option<bool>,
 }
 
+/// Create direct links for one or more files
+#[derive(Debug, Serialize)]
+pub struct CreateDirectLinkRequest {
+    /// File URIs
+    pub uris: Vec<String>,
+}
+
+/// Patch metadata of one or more files
+#[derive(Debug, Serialize)]
+pub struct PatchMetadataRequest {
+    /// File URIs
+    pub uris: Vec<String>,
+    pub patches: Vec<MetadataPatch>,
+}
+
+/// A single metadata change
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataPatch {
+    pub key: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub remove: bool,
+}
+
 /// Mount storage policy request
 #[derive(Debug, Serialize)]
 pub struct MountStoragePolicyRequest {
@@ -288,8 +310,16 @@ pub struct ImportRequest<'a> {
 
 /// Select download files request
 #[derive(Debug, Serialize)]
-pub struct SelectDownloadFilesRequest<'a> {
-    pub selected_files: Vec<&'a str>,
+pub struct SelectDownloadFilesRequest {
+    pub selected_files: Vec<DownloadFileSelection>,
+}
+
+/// Whether to download one file of a torrent
+#[derive(Debug, Serialize)]
+pub struct DownloadFileSelection {
+    /// Index of the file in the torrent
+    pub index: i32,
+    pub download: bool,
 }
 
 /// Delete file request
@@ -48,6 +48,29 @@ pub struct DownloadUrlItem {
     pub stream_saver_display_name: Option<String>,
 }
 
+/// Thumbnail URL response
+///
+/// Servers answer with a signed URL object; a bare URL string is accepted too.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ThumbnailResponse {
+    Signed {
+        url: String,
+        #[serde(default)]
+        expires: Option<String>,
+    },
+    Url(String),
+}
+
+impl ThumbnailResponse {
+    /// Get the thumbnail URL
+    pub fn url(&self) -> &str {
+        match self {
+            ThumbnailResponse::Signed { url, .. } | ThumbnailResponse::Url(url) => url,
+        }
+    }
+}
+
 /// Archive list response
 #[derive(Debug, Deserialize)]
 pub struct ArchiveListResponse {
@@ -58,13 +81,56 @@ pub struct ArchiveListResponse {
 #[derive(Debug, Deserialize)]
 pub struct ArchiveFileItem {
     pub name: String,
+    #[serde(default)]
     pub size: u64,
+    #[serde(default)]
     pub r#type: String,
+    #[serde(default)]
     pub path: String,
+    #[serde(default)]
+    pub is_directory: bool,
+    #[serde(default)]
+    pub updated_at: Option<String>,
 }
 
 /// Viewer session response
 #[derive(Debug, Deserialize)]
 pub struct ViewerSessionResponse {
+    #[serde(default)]
     pub session_id: String,
+    #[serde(default)]
+    pub session: Option<ViewerSession>,
+    /// WOPI source URL for WOPI viewers
+    #[serde(default)]
+    pub wopi_src: Option<String>,
+}
+
+impl ViewerSessionResponse {
+    /// Get the session ID from either response form
+    pub fn id(&self) -> &str {
+        match &self.session {
+            Some(session) if !session.id.is_empty() => &session.id,
+            _ => &self.session_id,
+        }
+    }
+}
+
+/// Viewer session with its access token
+#[derive(Debug, Deserialize)]
+pub struct ViewerSession {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub access_token: String,
+    #[serde(default)]
+    pub expires: Option<String>,
+}
+
+/// Direct link created for a file
+#[derive(Debug, Deserialize)]
+pub struct DirectLinkResponse {
+    pub link: String,
+    /// URI of the file the link points to
+    #[serde(default)]
+    pub file_url: String,
 }
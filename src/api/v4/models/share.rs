@@ -31,6 +31,9 @@ pub struct ShareLink {
     pub expired_at: Option<String>,
     #[serde(default)]
     pub download_count: u64,
+    /// Downloads left before the link expires; absent if unlimited
+    #[serde(default)]
+    pub remain_downloads: Option<i64>,
 }
 
 /// Share source type enum
@@ -94,6 +97,8 @@ pub struct CreateShareLinkRequest {
     pub price: Option<i32>,
     pub password: Option<String>,
     pub show_readme: Option<bool>,
+    /// Number of downloads before the link expires
+    pub downloads: Option<u32>,
 }
 
 /// Edit share link request
@@ -101,10 +106,14 @@ pub struct CreateShareLinkRequest {
 pub struct EditShareLinkRequest {
     pub permissions: super::file::PermissionSetting,
     pub uri: String,
+    pub is_private: Option<bool>,
     pub share_view: Option<bool>,
     pub expire: Option<u32>,
     pub price: Option<i32>,
+    pub password: Option<String>,
     pub show_readme: Option<bool>,
+    /// Number of downloads before the link expires
+    pub downloads: Option<u32>,
 }
 
 /// Abuse report request
@@ -1,6 +1,7 @@
 //! Site configuration models for Cloudreve API v4
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Site configuration section type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -96,17 +97,38 @@ pub struct SiteConfig {
 }
 
 /// File viewer configuration
+///
+/// Servers may also send viewer groups, whose viewers are in
+/// [`FileViewer::viewers`].
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct FileViewer {
     #[serde(default)]
+    pub id: String,
+    /// Viewer type: "builtin", "wopi" or "custom"
+    #[serde(default, rename = "type")]
+    pub viewer_type: String,
+    #[serde(default, alias = "exts")]
     pub extensions: Vec<String>,
     #[serde(default)]
     pub handler: String,
-    #[serde(default)]
+    #[serde(default, alias = "display_name")]
     pub name: String,
     #[serde(default)]
     pub priority: i32,
+    /// URL template of custom viewers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// WOPI action URLs by extension and action, e.g. `docx` -> `edit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wopi_actions: Option<HashMap<String, HashMap<String, String>>>,
+    /// Largest file size the viewer accepts; 0 for no limit
+    #[serde(default)]
+    pub max_size: u64,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub viewers: Vec<FileViewer>,
 }
 
 /// Payment setting
@@ -147,12 +169,34 @@ pub struct GroupSKU {
 pub struct CustomProps {
     #[serde(default)]
     pub key: String,
+    /// Identifier newer servers send instead of `key`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
     #[serde(default)]
     pub name: String,
     #[serde(default)]
     pub r#type: String,
     #[serde(default)]
     pub options: Option<Vec<String>>,
+    /// Lower bound of numbers, or minimum length of text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Upper bound of numbers and ratings, or maximum length of text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl CustomProps {
+    /// Get the property key, whichever field the server used
+    pub fn prop_key(&self) -> &str {
+        if self.key.is_empty() {
+            &self.id
+        } else {
+            &self.key
+        }
+    }
 }
 
 /// Custom navigation item
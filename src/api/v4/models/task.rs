@@ -47,6 +47,20 @@ pub enum TaskStatus {
     Completed,
 }
 
+impl TaskStatus {
+    /// Get the status as the API spells it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "queued",
+            TaskStatus::Processing => "processing",
+            TaskStatus::Suspending => "suspending",
+            TaskStatus::Error => "error",
+            TaskStatus::Canceled => "canceled",
+            TaskStatus::Completed => "completed",
+        }
+    }
+}
+
 /// Task type enum
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TaskType {
@@ -201,4 +215,12 @@ pub struct ExtractArchiveRequest<'a> {
     pub src: Vec<&'a str>,
     #[serde(rename = "dst")]
     pub dst: &'a str,
+    /// Text encoding of the entry names, e.g. "gbk"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+    /// Paths inside the archive to extract; all entries when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_mask: Option<Vec<&'a str>>,
 }
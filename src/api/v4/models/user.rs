@@ -161,6 +161,17 @@ pub struct UpdateUserSettingRequest<'a> {
     pub value: &'a str,
 }
 
+/// Partial update of user preferences via `PATCH /user/setting`
+///
+/// Only the fields that are set are sent to the server.
+#[derive(Debug, Serialize, Default)]
+pub struct UserSettingPatch<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nick: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_theme: Option<&'a str>,
+}
+
 /// Credit change record
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreditChangeRecord {
@@ -21,6 +21,7 @@ impl ApiV4Client {
             price: request.price,
             password: request.password.clone(),
             show_readme: request.show_readme,
+            downloads: request.downloads,
         };
 
         let response: ApiResponse<String> = self.put("/share", &converted_request).await?;
@@ -87,13 +88,31 @@ impl ApiV4Client {
         Ok(shares)
     }
 
+    /// Edit a share link
+    ///
+    /// Like creation, the server answers with the link's URL.
     pub async fn edit_share_link(
         &self,
         share_id: &str,
         request: &EditShareLinkRequest,
-    ) -> Result<ShareLink, Error> {
-        let response: ApiResponse<ShareLink> =
-            self.post(&format!("/share/{}", share_id), request).await?;
+    ) -> Result<String, Error> {
+        // Convert URI format internally
+        let uri = path_to_uri(&request.uri);
+        let converted_request = EditShareLinkRequest {
+            uri,
+            permissions: request.permissions.clone(),
+            is_private: request.is_private,
+            share_view: request.share_view,
+            expire: request.expire,
+            price: request.price,
+            password: request.password.clone(),
+            show_readme: request.show_readme,
+            downloads: request.downloads,
+        };
+
+        let response: ApiResponse<String> = self
+            .post(&format!("/share/{}", share_id), &converted_request)
+            .await?;
         response.data.ok_or_else(|| {
             Error::InvalidResponse(format!(
                 "API returned error: code={}, msg={}",
@@ -135,7 +154,7 @@ impl ApiV4Client {
         let response: ApiResponse<Value> = self.get(&endpoint).await?;
 
         if let Some(data) = response.data {
-            let share: ShareLink = serde_json::from_value(data).unwrap();
+            let share: ShareLink = serde_json::from_value(data)?;
             Ok(share)
         } else {
             Err(Error::InvalidResponse("No data in response".to_string()))
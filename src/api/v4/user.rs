@@ -94,6 +94,18 @@ impl ApiV4Client {
         }
     }
 
+    pub async fn patch_user_setting(&self, patch: &UserSettingPatch<'_>) -> Result<(), Error> {
+        let response: ApiResponse<()> = self.patch("/user/setting", patch).await?;
+        if response.code == 0 {
+            Ok(())
+        } else {
+            Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            })
+        }
+    }
+
     pub async fn get_user_setting(&self) -> Result<UserSettings, Error> {
         let response: ApiResponse<UserSettings> = self.get("/user/setting").await?;
         Ok(response.data.unwrap())
@@ -24,7 +24,7 @@ impl ApiV4Client {
     pub async fn select_download_files(
         &self,
         task_id: &str,
-        request: &SelectDownloadFilesRequest<'_>,
+        request: &SelectDownloadFilesRequest,
     ) -> Result<Vec<Task>, Error> {
         let response: ApiResponse<Vec<Task>> = self
             .patch(&format!("/workflow/download/{}", task_id), request)
@@ -61,7 +61,21 @@ impl ApiV4Client {
         page_size: i32,
         category: &str,
     ) -> Result<TaskListResponse, Error> {
-        let url = format!("/workflow?page_size={}&category={}", page_size, category);
+        self.list_workflow_tasks_with_token(page_size, category, None)
+            .await
+    }
+
+    /// List workflow tasks, continuing from a previous page's `next_token`
+    pub async fn list_workflow_tasks_with_token(
+        &self,
+        page_size: i32,
+        category: &str,
+        next_page_token: Option<&str>,
+    ) -> Result<TaskListResponse, Error> {
+        let mut url = format!("/workflow?page_size={}&category={}", page_size, category);
+        if let Some(token) = next_page_token {
+            url.push_str(&format!("&next_page_token={}", token));
+        }
         let response: ApiResponse<TaskListResponse> = self.get(&url).await?;
         if response.code != 0 {
             return Err(Error::Api {
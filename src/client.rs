@@ -98,6 +98,14 @@ impl UnifiedClient {
         }
     }
 
+    /// Get the underlying HTTP client
+    pub fn http_client(&self) -> &reqwest::Client {
+        match self {
+            UnifiedClient::V3(client) => &client.http_client,
+            UnifiedClient::V4(client) => &client.http_client,
+        }
+    }
+
     /// Check if the client is using V3
     pub fn is_v3(&self) -> bool {
         matches!(self, UnifiedClient::V3(_))
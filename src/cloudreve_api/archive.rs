@@ -0,0 +1,110 @@
+//! Server-side archive operations for CloudreveAPI
+//!
+//! v4 servers create and extract archives as workflow tasks; the methods
+//! here submit the task and wait for its [`TaskOutcome`]. v3 servers have
+//! no archive workflows.
+
+use crate::Error;
+use crate::api::v4::models::{
+    ArchiveFileItem, CreateArchiveRequest, ExtractArchiveRequest, GetArchiveListRequest,
+};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::task::{TaskOutcome, WaitOptions};
+use log::debug;
+
+impl super::CloudreveAPI {
+    /// Compress files and folders into a zip archive on the server
+    ///
+    /// Waits for the archive task to finish as set in `options`; give it a
+    /// timeout unless waiting forever on a stuck task is acceptable.
+    pub async fn compress(
+        &self,
+        paths: &[&str],
+        dest_zip: &str,
+        options: &WaitOptions,
+    ) -> Result<TaskOutcome, Error> {
+        debug!("Compressing {:?} into {}", paths, dest_zip);
+
+        let client = match self.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "compress".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        let src: Vec<String> = paths.iter().map(|p| path_to_uri(p)).collect();
+        let dst = path_to_uri(dest_zip);
+        let request = CreateArchiveRequest {
+            src: src.iter().map(String::as_str).collect(),
+            dst: &dst,
+        };
+        let task = client.create_archive(&request).await?;
+
+        let outcome = self.track(&task).wait(options).await?;
+        self.invalidate_cached(dest_zip);
+        Ok(outcome)
+    }
+
+    /// Extract an archive into a folder on the server
+    ///
+    /// `selected_entries` limits extraction to these paths inside the
+    /// archive, as listed by [`Self::list_archive`]. `encoding` sets the
+    /// text encoding of entry names for archives not using UTF-8. Waits for
+    /// the extract task to finish as set in `options`.
+    pub async fn extract(
+        &self,
+        archive: &str,
+        dest_dir: &str,
+        selected_entries: Option<&[&str]>,
+        encoding: Option<&str>,
+        password: Option<&str>,
+        options: &WaitOptions,
+    ) -> Result<TaskOutcome, Error> {
+        debug!("Extracting {} into {}", archive, dest_dir);
+
+        let client = match self.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "extract".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        let src = path_to_uri(archive);
+        let dst = path_to_uri(dest_dir);
+        let request = ExtractArchiveRequest {
+            src: vec![&src],
+            dst: &dst,
+            encoding,
+            password,
+            file_mask: selected_entries.map(|entries| entries.to_vec()),
+        };
+        let task = client.extract_archive(&request).await?;
+
+        let outcome = self.track(&task).wait(options).await?;
+        self.invalidate_cached(dest_dir);
+        Ok(outcome)
+    }
+
+    /// List the entries of an archive without extracting it
+    pub async fn list_archive(&self, path: &str) -> Result<Vec<ArchiveFileItem>, Error> {
+        debug!("Listing archive: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "list archive".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => {
+                let request = GetArchiveListRequest { uri: path };
+                Ok(client.get_archive_list(&request).await?.files)
+            }
+        }
+    }
+}
@@ -15,7 +15,11 @@ impl super::CloudreveAPI {
     pub async fn login(&mut self, email: &str, password: &str) -> Result<LoginResponse, Error> {
         debug!("Attempting login for {}", email);
 
-        match &mut self.inner {
+        // Objects resolved for a previous session may not belong to this user
+        self.path_cache.clear();
+        self.listing_cache.clear();
+
+        match self.client_mut()? {
             UnifiedClient::V3(client) => {
                 let request = v3_models::LoginRequest {
                     user_name: email,
@@ -47,7 +51,7 @@ impl super::CloudreveAPI {
     ///
     /// Returns the token info if authenticated, suitable for saving to CLI cache.
     pub fn get_token(&self) -> Result<TokenInfo, Error> {
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(client) => {
                 if let Some(cookie) = &client.session_cookie {
                     Ok(TokenInfo::V3Session(cookie.clone()))
@@ -74,7 +78,10 @@ impl super::CloudreveAPI {
     pub fn set_token(&mut self, token: &str) -> Result<(), Error> {
         debug!("Setting token from cache");
 
-        match &mut self.inner {
+        self.path_cache.clear();
+        self.listing_cache.clear();
+
+        match self.client_mut()? {
             UnifiedClient::V3(client) => {
                 client.set_session_cookie(token.to_string());
                 Ok(())
@@ -90,7 +97,7 @@ impl super::CloudreveAPI {
     ///
     /// Returns the session cookie if using V3 API, None otherwise.
     pub fn get_session_cookie(&self) -> Option<String> {
-        match &self.inner {
+        match self.client().ok()? {
             UnifiedClient::V3(client) => client.get_session_cookie().map(|s| s.to_string()),
             UnifiedClient::V4(_) => None,
         }
@@ -0,0 +1,215 @@
+//! Backend abstraction for CloudreveAPI
+//!
+//! [`CloudreveBackend`] is the set of path-based operations the unified
+//! client is built on. The v3 and v4 implementations translate them to the
+//! respective REST APIs; other implementations, such as an in-memory fake for
+//! unit tests or a wrapper that adds caching or metrics, can be plugged in
+//! with [`CloudreveAPI::with_backend`](super::CloudreveAPI::with_backend).
+//!
+//! Methods return boxed futures so that the trait stays object safe.
+//! Features outside of this trait (authentication, shares, WebDAV accounts,
+//! migration and server-side upload sessions) need a built-in v3 or v4
+//! client and return [`Error::UnsupportedFeature`] on a custom backend.
+
+use crate::Error;
+use crate::api::ApiVersion;
+use crate::cloudreve_api::file::{FileInfo, FileList, FileListAll};
+use crate::cloudreve_api::user::{StorageQuota, UserInfo};
+use futures::future::BoxFuture;
+use std::ops::Deref;
+
+pub(crate) mod v3;
+pub(crate) mod v4;
+
+pub(crate) use v3::V3Backend;
+pub(crate) use v4::V4Backend;
+
+/// Future returned by backend operations
+pub type BackendFuture<'a, T> = BoxFuture<'a, Result<T, Error>>;
+
+/// Path-based storage operations behind a [`CloudreveAPI`](super::CloudreveAPI)
+///
+/// Paths are absolute, `/`-separated and relative to the user's root. The
+/// unified client takes care of caching and invalidation, so implementations
+/// only talk to their storage.
+pub trait CloudreveBackend: Send + Sync {
+    /// API version whose models this backend returns
+    fn api_version(&self) -> ApiVersion;
+
+    /// Base URL of the server, used for display and absolute links
+    fn base_url(&self) -> &str;
+
+    /// Get the server version
+    fn ping(&self) -> BackendFuture<'_, String>;
+
+    /// List one page of a directory
+    fn list_files<'a>(
+        &'a self,
+        path: &'a str,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileList>;
+
+    /// List a whole directory, following pagination
+    fn list_files_all<'a>(
+        &'a self,
+        path: &'a str,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileListAll>;
+
+    /// Get information about a file or directory
+    fn get_file_info<'a>(&'a self, path: &'a str) -> BackendFuture<'a, FileInfo>;
+
+    /// Get information about a file or directory, or `None` if it does not exist
+    fn lookup<'a>(&'a self, path: &'a str) -> BackendFuture<'a, Option<FileInfo>>;
+
+    /// Create a directory
+    fn create_directory<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Delete a file or directory
+    fn delete<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Rename a file or directory in place
+    fn rename<'a>(&'a self, path: &'a str, new_name: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Move an object into a directory, keeping its name
+    fn move_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Copy an object into a directory, keeping its name
+    fn copy_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Upload a file in a single request
+    fn upload_file<'a>(
+        &'a self,
+        path: &'a str,
+        content: Vec<u8>,
+        policy_id: Option<&'a str>,
+    ) -> BackendFuture<'a, ()>;
+
+    /// Get a download URL for a file
+    fn download_url<'a>(&'a self, path: &'a str) -> BackendFuture<'a, String>;
+
+    /// Restore a file or directory from the trash
+    fn restore<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        let _ = path;
+        let version = self.api_version();
+        Box::pin(async move {
+            Err(Error::UnsupportedFeature(
+                "restore from trash".to_string(),
+                version.to_string(),
+            ))
+        })
+    }
+
+    /// Get information about the current user
+    fn user_info(&self) -> BackendFuture<'_, UserInfo>;
+
+    /// Get the storage quota of the current user
+    fn storage_quota(&self) -> BackendFuture<'_, StorageQuota>;
+}
+
+/// The backend a CloudreveAPI routes an operation to
+pub(crate) enum BackendRef<'a> {
+    V3(V3Backend<'a>),
+    V4(V4Backend<'a>),
+    Custom(&'a dyn CloudreveBackend),
+}
+
+impl<'a> Deref for BackendRef<'a> {
+    type Target = dyn CloudreveBackend + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            BackendRef::V3(backend) => backend,
+            BackendRef::V4(backend) => backend,
+            BackendRef::Custom(backend) => *backend,
+        }
+    }
+}
+
+/// A CloudreveAPI is itself a backend, so wrappers can be layered on top of it
+impl CloudreveBackend for super::CloudreveAPI {
+    fn api_version(&self) -> ApiVersion {
+        super::CloudreveAPI::api_version(self)
+    }
+
+    fn base_url(&self) -> &str {
+        super::CloudreveAPI::base_url(self)
+    }
+
+    fn ping(&self) -> BackendFuture<'_, String> {
+        Box::pin(self.get_server_version())
+    }
+
+    fn list_files<'a>(
+        &'a self,
+        path: &'a str,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileList> {
+        Box::pin(super::CloudreveAPI::list_files(self, path, page, page_size))
+    }
+
+    fn list_files_all<'a>(
+        &'a self,
+        path: &'a str,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileListAll> {
+        Box::pin(super::CloudreveAPI::list_files_all(self, path, page_size))
+    }
+
+    fn get_file_info<'a>(&'a self, path: &'a str) -> BackendFuture<'a, FileInfo> {
+        Box::pin(super::CloudreveAPI::get_file_info(self, path))
+    }
+
+    fn lookup<'a>(&'a self, path: &'a str) -> BackendFuture<'a, Option<FileInfo>> {
+        Box::pin(super::CloudreveAPI::lookup(self, path))
+    }
+
+    fn create_directory<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::create_directory(self, path))
+    }
+
+    fn delete<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::delete(self, path.into()))
+    }
+
+    fn rename<'a>(&'a self, path: &'a str, new_name: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::rename(self, path, new_name))
+    }
+
+    fn move_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::move_into_dir(self, src, dir))
+    }
+
+    fn copy_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::copy_into_dir(self, src, dir))
+    }
+
+    fn upload_file<'a>(
+        &'a self,
+        path: &'a str,
+        content: Vec<u8>,
+        policy_id: Option<&'a str>,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(super::CloudreveAPI::upload_file(
+            self, path, content, policy_id,
+        ))
+    }
+
+    fn download_url<'a>(&'a self, path: &'a str) -> BackendFuture<'a, String> {
+        Box::pin(self.download_file(path))
+    }
+
+    fn restore<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.restore_file(path))
+    }
+
+    fn user_info(&self) -> BackendFuture<'_, UserInfo> {
+        Box::pin(self.get_user_info())
+    }
+
+    fn storage_quota(&self) -> BackendFuture<'_, StorageQuota> {
+        Box::pin(self.get_storage_quota())
+    }
+}
@@ -0,0 +1,316 @@
+//! Backend implementation for the v3 API
+
+use super::{BackendFuture, CloudreveBackend};
+use crate::Error;
+use crate::api::ApiVersion;
+use crate::api::v3::ApiV3Client;
+use crate::api::v3::models as v3_models;
+use crate::cloudreve_api::file::{FileInfo, FileList, FileListAll};
+use crate::cloudreve_api::path::{normalize_path, split_path};
+use crate::cloudreve_api::path_cache::V3PathCache;
+use crate::cloudreve_api::user::{StorageQuota, UserInfo};
+use log::debug;
+
+/// Routes backend operations to a v3 client
+///
+/// The v3 API addresses objects by ID, so path-based operations resolve
+/// the ID through the shared path cache first.
+pub(crate) struct V3Backend<'a> {
+    client: &'a ApiV3Client,
+    path_cache: &'a V3PathCache,
+}
+
+impl<'a> V3Backend<'a> {
+    pub(crate) fn new(client: &'a ApiV3Client, path_cache: &'a V3PathCache) -> Self {
+        Self { client, path_cache }
+    }
+
+    /// Get the storage policy used for uploads into a directory
+    pub(crate) async fn default_policy_id(&self, dir: &str) -> Result<String, Error> {
+        debug!("Getting policy_id from directory: {}", dir);
+        let dir_list = self.client.list_directory(dir).await?;
+        Ok(dir_list.policy.id)
+    }
+
+    async fn list_directory(&self, path: &str) -> Result<v3_models::DirectoryList, Error> {
+        // V3 doesn't support pagination in list_directory
+        let dir_list = self.client.list_directory(path).await?;
+        self.path_cache
+            .insert_listing(normalize_path(path), &dir_list.objects);
+        Ok(dir_list)
+    }
+
+    async fn delete_object(&self, path: &str) -> Result<(), Error> {
+        if normalize_path(path) == "/" {
+            return Err(Error::InvalidResponse(
+                "Cannot delete root directory".to_string(),
+            ));
+        }
+
+        // Find the object's ID and type, listing the parent only on a cache miss
+        let obj = self.path_cache.find(self.client, path).await?;
+
+        // Separate into files and folders based on object type
+        let (folders, files) = if obj.object_type == "dir" {
+            (vec![obj.id.as_str()], Vec::<&str>::new())
+        } else {
+            (Vec::<&str>::new(), vec![obj.id.as_str()])
+        };
+
+        let request = v3_models::DeleteObjectRequest {
+            items: files,
+            dirs: folders,
+            force: true,
+            unlink: false,
+        };
+        self.client.delete_object(&request).await
+    }
+
+    async fn rename_object(&self, path: &str, new_name: &str) -> Result<(), Error> {
+        if normalize_path(path) == "/" {
+            return Err(Error::InvalidResponse(
+                "Cannot rename root directory".to_string(),
+            ));
+        }
+
+        let obj = self.path_cache.find(self.client, path).await?;
+
+        debug!(
+            "V3 rename: found object id={}, type={}",
+            obj.id, obj.object_type
+        );
+
+        // Use object ID for rename
+        let request = v3_models::RenameObjectRequest {
+            action: "rename",
+            src: source_items(&obj),
+            new_name,
+        };
+        self.client.rename_object(&request).await
+    }
+
+    async fn move_object(&self, src: &str, dir: &str) -> Result<(), Error> {
+        let (src_dir, _) = split_path(src);
+        let obj = self.path_cache.find(self.client, src).await?;
+        let request = v3_models::MoveObjectRequest {
+            action: "move",
+            src_dir,
+            src: source_items(&obj),
+            dst: normalize_path(dir),
+        };
+        self.client.move_object(&request).await
+    }
+
+    async fn copy_object(&self, src: &str, dir: &str) -> Result<(), Error> {
+        let (src_dir, _) = split_path(src);
+        let obj = self.path_cache.find(self.client, src).await?;
+        let request = v3_models::CopyObjectRequest {
+            src_dir,
+            src: source_items(&obj),
+            dst: normalize_path(dir),
+        };
+        self.client.copy_object(&request).await
+    }
+
+    async fn upload(
+        &self,
+        path: &str,
+        content: Vec<u8>,
+        policy_id: Option<&str>,
+    ) -> Result<(), Error> {
+        // V3: Need to get policy_id if not provided
+        let final_policy_id = match policy_id {
+            Some(pid) => pid.to_string(),
+            None => self.default_policy_id(split_path(path).0).await?,
+        };
+
+        // V3 uses parent directory as path, not full file path
+        let upload_dir = if let Some(pos) = path.rfind('/') {
+            if pos == 0 { "/" } else { &path[..pos] }
+        } else {
+            "/"
+        };
+        let file_name = path.rsplit('/').next().unwrap_or("file");
+        debug!("V3 upload - dir: {}, file: {}", upload_dir, file_name);
+        let request = v3_models::UploadFileRequest {
+            path: upload_dir,
+            name: file_name,
+            policy_id: &final_policy_id,
+            size: content.len() as i64,
+            last_modified: 0,
+            mime_type: "",
+        };
+        let session = self.client.upload_file(&request).await?;
+
+        // Upload single chunk (for simplicity)
+        self.client
+            .upload_chunk(&session.session_id, 0, content)
+            .await?;
+
+        // Note: complete_upload is only needed for certain storage policies (like OneDrive)
+        // For other policies, the upload is complete after the chunk is uploaded
+        // We attempt to complete but ignore errors if it's not supported
+        match self.client.complete_upload(&session.session_id).await {
+            Ok(_) => {}
+            Err(Error::Api { code: 40011, .. }) => {
+                // "上传会话不存在或已过期" - might mean upload already completed
+                debug!("complete_upload not needed or already completed");
+            }
+            Err(_) => {
+                // Other errors, also ignore for now
+                debug!("complete_upload returned error, ignoring");
+            }
+        }
+        Ok(())
+    }
+
+    async fn download(&self, path: &str) -> Result<String, Error> {
+        // V3: Need file ID, not path
+        let file_id = self.path_cache.find(self.client, path).await?.id;
+
+        debug!("V3: Found file ID: {}", file_id);
+
+        // Download using file ID
+        let url_info = self.client.download_file(&file_id).await?;
+        // Construct full URL from base_url and relative path
+        Ok(format!(
+            "{}{}",
+            self.client.base_url.trim_end_matches('/'),
+            url_info.url
+        ))
+    }
+
+    async fn fetch_user_info(&self) -> Result<UserInfo, Error> {
+        // V3: Get user info from site config
+        let config = self.client.get_site_config().await?;
+        if let Some(user) = config.user {
+            Ok(UserInfo {
+                id: user.id,
+                email: user.user_name,
+                nickname: user.nickname,
+                group: Some(user.group.name),
+                status: Some(user.status.to_string()),
+            })
+        } else {
+            Err(Error::InvalidResponse(
+                "No user info in site config".to_string(),
+            ))
+        }
+    }
+
+    async fn fetch_storage_quota(&self) -> Result<StorageQuota, Error> {
+        let storage = self.client.get_user_storage().await?;
+        let used = storage.used as u64;
+        let total = storage.total as u64;
+        Ok(StorageQuota {
+            used,
+            total,
+            free: total.saturating_sub(used),
+        })
+    }
+}
+
+impl CloudreveBackend for V3Backend<'_> {
+    fn api_version(&self) -> ApiVersion {
+        ApiVersion::V3
+    }
+
+    fn base_url(&self) -> &str {
+        &self.client.base_url
+    }
+
+    fn ping(&self) -> BackendFuture<'_, String> {
+        Box::pin(self.client.ping())
+    }
+
+    fn list_files<'a>(
+        &'a self,
+        path: &'a str,
+        _page: Option<u32>,
+        _page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileList> {
+        Box::pin(async move { Ok(FileList::V3(self.list_directory(path).await?)) })
+    }
+
+    fn list_files_all<'a>(
+        &'a self,
+        path: &'a str,
+        _page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileListAll> {
+        Box::pin(async move { Ok(FileListAll::V3(self.list_directory(path).await?)) })
+    }
+
+    fn get_file_info<'a>(&'a self, path: &'a str) -> BackendFuture<'a, FileInfo> {
+        // V3: Find the object in its parent directory listing
+        Box::pin(async move { Ok(FileInfo::V3(self.path_cache.find(self.client, path).await?)) })
+    }
+
+    fn lookup<'a>(&'a self, path: &'a str) -> BackendFuture<'a, Option<FileInfo>> {
+        Box::pin(async move {
+            Ok(self
+                .path_cache
+                .lookup(self.client, path)
+                .await?
+                .map(FileInfo::V3))
+        })
+    }
+
+    fn create_directory<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let request = v3_models::CreateDirectoryRequest { path };
+            self.client.create_directory(&request).await
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.delete_object(path))
+    }
+
+    fn rename<'a>(&'a self, path: &'a str, new_name: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.rename_object(path, new_name))
+    }
+
+    fn move_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.move_object(src, dir))
+    }
+
+    fn copy_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.copy_object(src, dir))
+    }
+
+    fn upload_file<'a>(
+        &'a self,
+        path: &'a str,
+        content: Vec<u8>,
+        policy_id: Option<&'a str>,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(self.upload(path, content, policy_id))
+    }
+
+    fn download_url<'a>(&'a self, path: &'a str) -> BackendFuture<'a, String> {
+        Box::pin(self.download(path))
+    }
+
+    fn user_info(&self) -> BackendFuture<'_, UserInfo> {
+        Box::pin(self.fetch_user_info())
+    }
+
+    fn storage_quota(&self) -> BackendFuture<'_, StorageQuota> {
+        Box::pin(self.fetch_storage_quota())
+    }
+}
+
+/// Build V3 source items for a single object
+pub(crate) fn source_items(obj: &v3_models::Object) -> v3_models::SourceItems<'_> {
+    if obj.object_type == "dir" {
+        v3_models::SourceItems {
+            dirs: vec![obj.id.as_str()],
+            items: vec![],
+        }
+    } else {
+        v3_models::SourceItems {
+            dirs: vec![],
+            items: vec![obj.id.as_str()],
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! Backend implementation for the v4 API
+
+use super::{BackendFuture, CloudreveBackend};
+use crate::Error;
+use crate::api::ApiVersion;
+use crate::api::v4::ApiV4Client;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::path_to_uri;
+use crate::cloudreve_api::file::{FileInfo, FileList, FileListAll};
+use crate::cloudreve_api::path::split_path;
+use crate::cloudreve_api::user::{StorageQuota, UserInfo};
+use log::debug;
+
+/// V4 error codes returned for objects that do not exist
+const V4_NOT_FOUND_CODES: [i32; 2] = [404, 40016];
+
+/// Routes backend operations to a v4 client
+pub(crate) struct V4Backend<'a> {
+    client: &'a ApiV4Client,
+}
+
+impl<'a> V4Backend<'a> {
+    pub(crate) fn new(client: &'a ApiV4Client) -> Self {
+        Self { client }
+    }
+
+    /// Get the storage policy used for uploads into a directory
+    pub(crate) async fn default_policy_id(&self, dir: &str) -> Result<String, Error> {
+        debug!("Getting policy_id from directory: {}", dir);
+        let list_request = v4_models::ListFilesRequest {
+            path: dir,
+            page: Some(0),
+            page_size: Some(1),
+            ..Default::default()
+        };
+        match self.client.list_files(&list_request).await {
+            Ok(response) => Ok(response
+                .storage_policy
+                .map(|p| p.id)
+                .unwrap_or_else(|| "default".to_string())),
+            Err(_) => Ok("default".to_string()),
+        }
+    }
+
+    async fn list_page(
+        &self,
+        path: &str,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    ) -> Result<FileList, Error> {
+        let client = self.client;
+        let page_size = page_size.unwrap_or(100);
+
+        // First, fetch the first page to check pagination mode (cursor vs offset)
+        let first_request = v4_models::ListFilesRequest {
+            path,
+            page: Some(0),
+            page_size: Some(page_size),
+            order_by: None,
+            order_direction: None,
+            next_page_token: None,
+        };
+        let first_response = client.list_files(&first_request).await?;
+
+        // If no page specified or requesting page 0, return the first page
+        if page.is_none() || page == Some(0) {
+            return Ok(FileList::V4(Box::new(first_response)));
+        }
+
+        let target_page = page.unwrap();
+        let is_cursor = first_response.pagination.is_cursor;
+
+        if is_cursor {
+            // Cursor pagination: need to fetch pages sequentially to get next_page_token
+            let mut next_token = first_response.pagination.next_token.clone();
+            let mut current_response = first_response;
+
+            for current_page in 1..=target_page {
+                // Check if we have more pages
+                if next_token.is_none() || next_token.as_ref().map(|t| t.is_empty()).unwrap_or(true)
+                {
+                    return Err(Error::InvalidResponse(format!(
+                        "Page {} does not exist (only {} pages available)",
+                        target_page, current_page
+                    )));
+                }
+
+                // Fetch next page using the token
+                let request = v4_models::ListFilesRequest {
+                    path,
+                    page: Some(current_page),
+                    page_size: Some(page_size),
+                    order_by: None,
+                    order_direction: None,
+                    next_page_token: next_token.as_deref(),
+                };
+                current_response = client.list_files(&request).await?;
+
+                // If this is the target page, return it
+                if current_page == target_page {
+                    return Ok(FileList::V4(Box::new(current_response)));
+                }
+
+                // Get next token for next iteration
+                next_token = current_response.pagination.next_token.clone();
+            }
+
+            // Should not reach here, but handle the case
+            Ok(FileList::V4(Box::new(current_response)))
+        } else {
+            // Offset pagination: can directly request the target page
+            let request = v4_models::ListFilesRequest {
+                path,
+                page: Some(target_page),
+                page_size: Some(page_size),
+                order_by: None,
+                order_direction: None,
+                next_page_token: None,
+            };
+            let list_response = client.list_files(&request).await?;
+            Ok(FileList::V4(Box::new(list_response)))
+        }
+    }
+
+    async fn list_all(&self, path: &str, page_size: Option<u32>) -> Result<FileListAll, Error> {
+        let page_size = page_size.unwrap_or(500); // Default to 500 items per page
+
+        let mut all_files = Vec::new();
+        let mut parent: Option<v4_models::File> = None;
+        let mut storage_policy: Option<v4_models::StoragePolicy> = None;
+        let mut capability = String::new();
+        #[allow(unused_assignments)]
+        let mut pagination: Option<v4_models::PaginationResults> = None;
+        let mut next_token: Option<String> = None;
+        let mut page_num = 1;
+
+        loop {
+            let request = v4_models::ListFilesRequest {
+                path,
+                page: Some(page_num),
+                page_size: Some(page_size),
+                order_by: None,
+                order_direction: None,
+                next_page_token: next_token.as_deref(),
+            };
+            let list_response = self.client.list_files(&request).await?;
+
+            // Store parent, storage_policy and capability from first response
+            if parent.is_none() {
+                parent = Some(list_response.parent.clone());
+                storage_policy = list_response.storage_policy.clone();
+                capability = list_response.props.capability.clone();
+            }
+
+            // Collect files
+            all_files.extend(list_response.files);
+
+            // Check if there are more pages (before moving pagination)
+            next_token = list_response.pagination.next_token.clone();
+            let has_more = next_token.is_some();
+
+            // Store pagination info from last response
+            pagination = Some(list_response.pagination);
+
+            if !has_more {
+                break;
+            }
+
+            page_num += 1;
+            debug!(
+                "Fetching page {} (next_token: {})",
+                page_num,
+                next_token.as_ref().unwrap()
+            );
+        }
+
+        let parent = parent.expect("parent should always be set after first API call");
+        let pagination = pagination.expect("should have at least one response");
+        let combined_response = v4_models::ListResponse {
+            files: all_files,
+            parent,
+            pagination,
+            props: v4_models::NavigatorProps {
+                capability,
+                max_page_size: page_size as i32,
+                order_by_options: Vec::new(),
+                order_direction_options: Vec::new(),
+            },
+            context_hint: String::new(),
+            mixed_type: false,
+            storage_policy,
+            view: None,
+        };
+
+        Ok(FileListAll::V4(Box::new(combined_response)))
+    }
+
+    async fn file_info(&self, path: &str) -> Result<FileInfo, Error> {
+        let request = v4_models::GetFileInfoRequest {
+            uri: path,
+            include_extended_info: Some(false),
+        };
+        let file = self.client.get_file_info_extended(&request).await?;
+        Ok(FileInfo::V4(file))
+    }
+
+    async fn find(&self, path: &str) -> Result<Option<FileInfo>, Error> {
+        let uri = path_to_uri(path);
+        let response: Result<v4_models::ApiResponse<v4_models::File>, Error> =
+            self.client.get(&format!("/file/info?uri={}", uri)).await;
+        match response {
+            Ok(v4_models::ApiResponse {
+                data: Some(file), ..
+            }) => Ok(Some(FileInfo::V4(file))),
+            Ok(response) if V4_NOT_FOUND_CODES.contains(&response.code) => Ok(None),
+            Ok(response) => Err(Error::Api {
+                code: response.code,
+                message: response.msg,
+            }),
+            Err(Error::Api { code, .. }) if V4_NOT_FOUND_CODES.contains(&code) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn rename_file(&self, path: &str, new_name: &str) -> Result<(), Error> {
+        let uri = path_to_uri(path);
+        let request = v4_models::RenameFileRequest {
+            uri: uri.as_str(),
+            new_name,
+        };
+        let _ = self.client.rename_file(&request).await?;
+        Ok(())
+    }
+
+    async fn move_file(&self, src: &str, dir: &str) -> Result<(), Error> {
+        let src_uri = path_to_uri(src);
+        let dir_uri = path_to_uri(dir);
+        let request = v4_models::MoveFileRequest {
+            uris: vec![src_uri.as_str()],
+            dst: dir_uri.as_str(),
+            copy: None,
+        };
+        self.client.move_file(&request).await
+    }
+
+    async fn copy_file(&self, src: &str, dir: &str) -> Result<(), Error> {
+        let src_uri = path_to_uri(src);
+        let dir_uri = path_to_uri(dir);
+        let request = v4_models::CopyFileRequest {
+            uris: vec![src_uri.as_str()],
+            dst: dir_uri.as_str(),
+        };
+        self.client.copy_file(&request).await
+    }
+
+    async fn upload(
+        &self,
+        path: &str,
+        content: Vec<u8>,
+        policy_id: Option<&str>,
+    ) -> Result<(), Error> {
+        // V4: Need to get policy_id if not provided
+        let final_policy_id = match policy_id {
+            Some(pid) => pid.to_string(),
+            None => self.default_policy_id(split_path(path).0).await?,
+        };
+
+        // V4: Use upload session
+        let request = v4_models::CreateUploadSessionRequest {
+            uri: &path_to_uri(path),
+            size: content.len() as u64,
+            policy_id: &final_policy_id,
+            last_modified: None,
+            mime_type: None,
+            metadata: None,
+            entity_type: None,
+        };
+        let session = self.client.create_upload_session(&request).await?;
+
+        // Upload content
+        self.client
+            .upload_file_chunk(&session.session_id, 0, &content)
+            .await?;
+        Ok(())
+    }
+
+    async fn download(&self, path: &str) -> Result<String, Error> {
+        let request = v4_models::CreateDownloadUrlRequest {
+            uris: vec![path],
+            download: Some(true),
+            redirect: Some(false), // 不自动重定向，返回 JSON 响应
+            entity: None,
+            use_primary_site_url: None,
+            skip_error: None,
+            archive: None,
+            no_cache: None,
+        };
+        let response = self.client.create_download_url(&request).await?;
+        if let Some(first_url) = response.urls.first() {
+            Ok(first_url.url.clone())
+        } else {
+            Err(Error::InvalidResponse(
+                "No download URL returned".to_string(),
+            ))
+        }
+    }
+
+    async fn restore_file(&self, path: &str) -> Result<(), Error> {
+        let request = v4_models::RestoreFileRequest { uris: vec![path] };
+        self.client.restore_from_trash(&request).await?;
+        Ok(())
+    }
+
+    async fn fetch_storage_quota(&self) -> Result<StorageQuota, Error> {
+        let quota = self.client.get_user_capacity().await?;
+        Ok(StorageQuota {
+            used: quota.used,
+            total: quota.total,
+            free: quota.total.saturating_sub(quota.used),
+        })
+    }
+}
+
+impl CloudreveBackend for V4Backend<'_> {
+    fn api_version(&self) -> ApiVersion {
+        ApiVersion::V4
+    }
+
+    fn base_url(&self) -> &str {
+        &self.client.base_url
+    }
+
+    fn ping(&self) -> BackendFuture<'_, String> {
+        Box::pin(self.client.ping())
+    }
+
+    fn list_files<'a>(
+        &'a self,
+        path: &'a str,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileList> {
+        Box::pin(self.list_page(path, page, page_size))
+    }
+
+    fn list_files_all<'a>(
+        &'a self,
+        path: &'a str,
+        page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileListAll> {
+        Box::pin(self.list_all(path, page_size))
+    }
+
+    fn get_file_info<'a>(&'a self, path: &'a str) -> BackendFuture<'a, FileInfo> {
+        Box::pin(self.file_info(path))
+    }
+
+    fn lookup<'a>(&'a self, path: &'a str) -> BackendFuture<'a, Option<FileInfo>> {
+        Box::pin(self.find(path))
+    }
+
+    fn create_directory<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.client.create_directory(path).await?;
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.client.delete_file(path).await?;
+            Ok(())
+        })
+    }
+
+    fn rename<'a>(&'a self, path: &'a str, new_name: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.rename_file(path, new_name))
+    }
+
+    fn move_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.move_file(src, dir))
+    }
+
+    fn copy_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.copy_file(src, dir))
+    }
+
+    fn upload_file<'a>(
+        &'a self,
+        path: &'a str,
+        content: Vec<u8>,
+        policy_id: Option<&'a str>,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(self.upload(path, content, policy_id))
+    }
+
+    fn download_url<'a>(&'a self, path: &'a str) -> BackendFuture<'a, String> {
+        Box::pin(self.download(path))
+    }
+
+    fn restore<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(self.restore_file(path))
+    }
+
+    fn user_info(&self) -> BackendFuture<'_, UserInfo> {
+        // V4: Use a placeholder - actual implementation would call user endpoint
+        Box::pin(async {
+            Err(Error::InvalidResponse(
+                "User info endpoint not yet implemented for V4".to_string(),
+            ))
+        })
+    }
+
+    fn storage_quota(&self) -> BackendFuture<'_, StorageQuota> {
+        Box::pin(self.fetch_storage_quota())
+    }
+}
@@ -0,0 +1,472 @@
+//! Batch operations for CloudreveAPI
+//!
+//! Cloudreve rejects requests that touch more objects than the site's
+//! `max_batch_size`. The operations in this module split their input into
+//! server-sized batches and run several batches at once, collecting a result
+//! for every item instead of failing the whole call.
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::file::DeleteResult;
+use crate::cloudreve_api::path::{join_path, normalize_path, split_path};
+use crate::cloudreve_api::site::SiteConfigValue;
+use futures::stream::{self, StreamExt};
+use log::debug;
+use std::future::Future;
+
+/// Batch size used when the server does not report `max_batch_size`
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Number of batches sent to the server at the same time
+pub const BATCH_CONCURRENCY: usize = 4;
+
+/// Result of a batch move, copy or rename operation
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub errors: Vec<(String, String)>,
+}
+
+impl BatchResult {
+    /// Add the counts and errors of another result to this one
+    pub fn merge(&mut self, other: BatchResult) {
+        self.succeeded += other.succeeded;
+        self.failed += other.failed;
+        self.errors.extend(other.errors);
+    }
+
+    /// Record a failure for every path in a batch
+    fn fail_all(&mut self, paths: &[&str], error: &Error) {
+        self.failed += paths.len();
+        for path in paths {
+            self.errors.push((path.to_string(), error.to_string()));
+        }
+    }
+}
+
+/// Split paths into batches of at most `max_batch_size` items
+///
+/// With `group_by_parent`, every batch only contains siblings, which the v3
+/// API requires for moves and deletes. Batches keep the order in which their
+/// paths first appear.
+///
+/// # Examples
+/// ```
+/// use cloudreve_api::cloudreve_api::batch::plan_batches;
+///
+/// let batches = plan_batches(&["/a/1", "/b/1", "/a/2"], 10, true);
+/// assert_eq!(batches, vec![vec!["/a/1", "/a/2"], vec!["/b/1"]]);
+/// ```
+pub fn plan_batches<'a>(
+    paths: &[&'a str],
+    max_batch_size: usize,
+    group_by_parent: bool,
+) -> Vec<Vec<&'a str>> {
+    let max_batch_size = max_batch_size.max(1);
+    let mut groups: Vec<(&str, Vec<&'a str>)> = Vec::new();
+
+    for path in paths {
+        let parent = if group_by_parent {
+            split_path(path).0
+        } else {
+            ""
+        };
+        match groups.iter_mut().find(|(p, _)| *p == parent) {
+            Some((_, group)) => group.push(path),
+            None => groups.push((parent, vec![path])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|(_, group)| {
+            group
+                .chunks(max_batch_size)
+                .map(|chunk| chunk.to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Check whether the server refused a whole batch without handling any item
+///
+/// Only then is it safe to retry the items one by one: v4 batches are not
+/// atomic, so after any other error some items may already have been moved
+/// or copied. Covers batches over the server's size limit and servers that
+/// do not support the batch request.
+fn is_batch_rejected(error: &Error) -> bool {
+    match error {
+        Error::UnsupportedFeature(_, _) => true,
+        Error::Api { code, message } => {
+            let message = message.to_lowercase();
+            matches!(code, 405 | 413)
+                || message.contains("too many")
+                || message.contains("batch size")
+        }
+        _ => false,
+    }
+}
+
+/// Run one future per batch with at most [`BATCH_CONCURRENCY`] in flight
+async fn run_batches<B, T, F, Fut>(batches: Vec<B>, op: F) -> Vec<T>
+where
+    F: Fn(B) -> Fut,
+    Fut: Future<Output = T>,
+{
+    stream::iter(batches.into_iter().map(op))
+        .buffer_unordered(BATCH_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// Batch operation methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Get the maximum number of objects the server accepts in one request
+    ///
+    /// Reads `max_batch_size` from the v4 explorer site config. v3 servers do
+    /// not report a limit, so [`DEFAULT_MAX_BATCH_SIZE`] is used.
+    pub async fn max_batch_size(&self) -> usize {
+        if !matches!(self.client(), Ok(UnifiedClient::V4(_))) {
+            return DEFAULT_MAX_BATCH_SIZE;
+        }
+
+        match self.get_site_config(Some("explorer")).await {
+            Ok(SiteConfigValue::V4(config)) => config
+                .max_batch_size
+                .filter(|size| *size >= 1.0)
+                .map(|size| size as usize)
+                .unwrap_or(DEFAULT_MAX_BATCH_SIZE),
+            Ok(SiteConfigValue::V3(_)) => DEFAULT_MAX_BATCH_SIZE,
+            Err(e) => {
+                debug!("Could not read max_batch_size, using default: {}", e);
+                DEFAULT_MAX_BATCH_SIZE
+            }
+        }
+    }
+
+    /// Batch delete multiple files and/or folders
+    ///
+    /// Paths are split into batches that respect the server's
+    /// `max_batch_size` and deleted with bounded concurrency. Files and
+    /// folders can be mixed; folders are deleted recursively by the server.
+    ///
+    /// # Arguments
+    /// * `paths` - Slice of paths to delete (can mix files and folders)
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cloudreve_api::CloudreveAPI;
+    /// # async fn example(api: &CloudreveAPI) -> cloudreve_api::Result<()> {
+    /// // Delete multiple items at once
+    /// api.batch_delete(&[
+    ///     "/folder/file1.txt",
+    ///     "/folder/file2.txt",
+    ///     "/another_folder",  // folder will be deleted recursively
+    /// ]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn batch_delete(&self, paths: &[&str]) -> Result<DeleteResult, Error> {
+        debug!("Batch deleting {} paths", paths.len());
+
+        if paths.is_empty() {
+            return Ok(DeleteResult::default());
+        }
+        if paths.iter().any(|path| normalize_path(path) == "/") {
+            return Err(Error::InvalidResponse(
+                "Cannot delete root directory".to_string(),
+            ));
+        }
+
+        let batches = plan_batches(paths, self.max_batch_size().await, true);
+        let results = run_batches(batches, |batch| async move {
+            let result = match self.client() {
+                Ok(UnifiedClient::V3(client)) => self.batch_delete_v3(client, &batch).await,
+                Ok(UnifiedClient::V4(client)) => self.batch_delete_v4(client, &batch).await,
+                Err(_) => Ok(self.batch_delete_one_by_one(&batch).await),
+            };
+            result.unwrap_or_else(|e| DeleteResult {
+                deleted: 0,
+                failed: batch.len(),
+                errors: batch
+                    .iter()
+                    .map(|p| (p.to_string(), e.to_string()))
+                    .collect(),
+            })
+        })
+        .await;
+
+        let mut total = DeleteResult::default();
+        for result in results {
+            total.deleted += result.deleted;
+            total.failed += result.failed;
+            total.errors.extend(result.errors);
+        }
+        Ok(total)
+    }
+
+    /// Move many files and/or folders into a directory
+    ///
+    /// Objects keep their names. Paths are split into batches that respect
+    /// the server's `max_batch_size` and moved with bounded concurrency.
+    pub async fn batch_move(&self, paths: &[&str], dest_dir: &str) -> Result<BatchResult, Error> {
+        debug!("Batch moving {} paths into {}", paths.len(), dest_dir);
+        self.batch_move_copy(paths, dest_dir, false).await
+    }
+
+    /// Copy many files and/or folders into a directory
+    ///
+    /// Objects keep their names. Paths are split into batches that respect
+    /// the server's `max_batch_size` and copied with bounded concurrency.
+    /// The v3 API copies one object per request, so v3 batches are sent item
+    /// by item.
+    pub async fn batch_copy(&self, paths: &[&str], dest_dir: &str) -> Result<BatchResult, Error> {
+        debug!("Batch copying {} paths into {}", paths.len(), dest_dir);
+        self.batch_move_copy(paths, dest_dir, true).await
+    }
+
+    /// Rename many files and/or folders
+    ///
+    /// Takes `(path, new_name)` pairs. Cloudreve renames one object per
+    /// request, so renames are sent individually with bounded concurrency.
+    pub async fn batch_rename(&self, renames: &[(&str, &str)]) -> Result<BatchResult, Error> {
+        debug!("Batch renaming {} paths", renames.len());
+
+        let max_batch_size = self.max_batch_size().await;
+        let batches: Vec<&[(&str, &str)]> = renames.chunks(max_batch_size).collect();
+        let results = run_batches(batches, |batch| async move {
+            let mut result = BatchResult::default();
+            for (path, new_name) in batch {
+                match self.rename(path, new_name).await {
+                    Ok(()) => result.succeeded += 1,
+                    Err(e) => result.fail_all(&[path], &e),
+                }
+            }
+            result
+        })
+        .await;
+
+        Ok(results
+            .into_iter()
+            .fold(BatchResult::default(), |mut total, result| {
+                total.merge(result);
+                total
+            }))
+    }
+
+    async fn batch_move_copy(
+        &self,
+        paths: &[&str],
+        dest_dir: &str,
+        copy: bool,
+    ) -> Result<BatchResult, Error> {
+        if paths.is_empty() {
+            return Ok(BatchResult::default());
+        }
+        if paths.iter().any(|path| normalize_path(path) == "/") {
+            return Err(Error::InvalidResponse(
+                "Cannot move or copy the root directory".to_string(),
+            ));
+        }
+
+        let batches = plan_batches(paths, self.max_batch_size().await, true);
+        let results = run_batches(batches, |batch| async move {
+            match self.client() {
+                Ok(UnifiedClient::V3(client)) if !copy => {
+                    self.batch_move_v3(client, &batch, dest_dir).await
+                }
+                Ok(UnifiedClient::V4(client)) => {
+                    self.batch_move_copy_v4(client, &batch, dest_dir, copy)
+                        .await
+                }
+                _ => self.batch_one_by_one(&batch, dest_dir, copy).await,
+            }
+        })
+        .await;
+
+        Ok(results
+            .into_iter()
+            .fold(BatchResult::default(), |mut total, result| {
+                total.merge(result);
+                total
+            }))
+    }
+
+    /// Delete each path of a batch with its own request
+    async fn batch_delete_one_by_one(&self, batch: &[&str]) -> DeleteResult {
+        let mut result = DeleteResult::default();
+        for path in batch {
+            match self.delete((*path).into()).await {
+                Ok(()) => result.deleted += 1,
+                Err(e) => {
+                    result.failed += 1;
+                    result.errors.push((path.to_string(), e.to_string()));
+                }
+            }
+        }
+        result
+    }
+
+    /// Move or copy each path of a batch with its own request
+    async fn batch_one_by_one(&self, batch: &[&str], dest_dir: &str, copy: bool) -> BatchResult {
+        let mut result = BatchResult::default();
+        for path in batch {
+            let outcome = if copy {
+                self.copy_into_dir(path, dest_dir).await
+            } else {
+                self.move_into_dir(path, dest_dir).await
+            };
+            match outcome {
+                Ok(()) => result.succeeded += 1,
+                Err(e) => result.fail_all(&[path], &e),
+            }
+        }
+        result
+    }
+
+    /// Move a batch of siblings with a single v3 request
+    async fn batch_move_v3(
+        &self,
+        client: &crate::api::v3::ApiV3Client,
+        batch: &[&str],
+        dest_dir: &str,
+    ) -> BatchResult {
+        let mut result = BatchResult::default();
+        let src_dir = split_path(batch[0]).0;
+
+        let mut found = Vec::new();
+        let mut objects = Vec::new();
+        for path in batch {
+            match self.v3_lookup(client, path).await {
+                Ok(Some(obj)) => {
+                    found.push(*path);
+                    objects.push(obj);
+                }
+                Ok(None) => {
+                    result.failed += 1;
+                    result
+                        .errors
+                        .push((path.to_string(), "File not found".to_string()));
+                }
+                Err(e) => result.fail_all(&[path], &e),
+            }
+        }
+
+        if found.is_empty() {
+            return result;
+        }
+
+        let mut items = v3_models::SourceItems {
+            dirs: vec![],
+            items: vec![],
+        };
+        for obj in &objects {
+            if obj.object_type == "dir" {
+                items.dirs.push(obj.id.as_str());
+            } else {
+                items.items.push(obj.id.as_str());
+            }
+        }
+
+        let request = v3_models::MoveObjectRequest {
+            action: "move",
+            src_dir,
+            src: items,
+            dst: normalize_path(dest_dir),
+        };
+        match client.move_object(&request).await {
+            Ok(()) => {
+                result.succeeded += found.len();
+                for path in &found {
+                    self.invalidate_cached(path);
+                    self.invalidate_cached(&join_path(dest_dir, split_path(path).1));
+                }
+            }
+            Err(e) => result.fail_all(&found, &e),
+        }
+        result
+    }
+
+    /// Move or copy a batch with a single v4 request
+    ///
+    /// If the server refuses the batch as a whole, as it does for batches
+    /// over its size limit, each item is retried on its own. Any other error
+    /// is reported for every item of the batch without a retry, since the
+    /// server may have handled some of them already.
+    async fn batch_move_copy_v4(
+        &self,
+        client: &crate::api::v4::ApiV4Client,
+        batch: &[&str],
+        dest_dir: &str,
+        copy: bool,
+    ) -> BatchResult {
+        let uris: Vec<String> = batch.iter().map(|path| path_to_uri(path)).collect();
+        let dst = path_to_uri(dest_dir);
+        let request = v4_models::MoveFileRequest {
+            uris: uris.iter().map(|uri| uri.as_str()).collect(),
+            dst: &dst,
+            copy: copy.then_some(true),
+        };
+
+        match client.move_file(&request).await {
+            Ok(()) => {
+                for path in batch {
+                    if !copy {
+                        self.invalidate_cached(path);
+                    }
+                    self.invalidate_cached(&join_path(dest_dir, split_path(path).1));
+                }
+                BatchResult {
+                    succeeded: batch.len(),
+                    ..Default::default()
+                }
+            }
+            Err(e) if is_batch_rejected(&e) => {
+                debug!(
+                    "Batch {} rejected ({}), falling back to individual requests",
+                    if copy { "copy" } else { "move" },
+                    e
+                );
+                self.batch_one_by_one(batch, dest_dir, copy).await
+            }
+            Err(e) => {
+                for path in batch {
+                    self.invalidate_cached(path);
+                    self.invalidate_cached(&join_path(dest_dir, split_path(path).1));
+                }
+                let mut result = BatchResult::default();
+                result.fail_all(batch, &e);
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_batch_rejected() {
+        let too_many = Error::Api {
+            code: 40001,
+            message: "Too many URIs in one request".to_string(),
+        };
+        assert!(is_batch_rejected(&too_many));
+        assert!(is_batch_rejected(&Error::UnsupportedFeature(
+            "batch move".to_string(),
+            "v4".to_string()
+        )));
+
+        let partial = Error::Api {
+            code: 40016,
+            message: "Object not exist".to_string(),
+        };
+        assert!(!is_batch_rejected(&partial));
+        assert!(!is_batch_rejected(&Error::Timeout("move".to_string())));
+    }
+}
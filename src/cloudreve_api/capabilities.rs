@@ -0,0 +1,221 @@
+//! Server capability discovery for CloudreveAPI
+//!
+//! Some operations only exist on one API version, and some depend on how the
+//! site is configured. [`Capabilities`] collects both up front so that
+//! applications can hide actions that would fail with
+//! [`Error::UnsupportedFeature`](crate::Error::UnsupportedFeature).
+
+use crate::Error;
+use crate::api::ApiVersion;
+use crate::api::v4::models::{FileViewer, SiteConfig as V4SiteConfig};
+use crate::cloudreve_api::site::SiteConfigValue;
+use log::debug;
+use std::collections::BTreeSet;
+
+/// Site config sections read on v4 servers
+const V4_SECTIONS: [&str; 4] = ["basic", "login", "explorer", "thumb"];
+
+/// An optional feature of the unified API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Feature {
+    /// Restore files from the trash
+    RestoreFromTrash,
+    /// File previews
+    Preview,
+    /// Thumbnails for the extensions in [`Capabilities::thumb_exts`]
+    Thumbnails,
+    /// Edit all settings of existing share links; v3 can only change the
+    /// password and preview
+    ShareUpdate,
+    /// Delete share links
+    ShareDelete,
+    /// Create, update and delete WebDAV accounts
+    DavAccountManagement,
+    /// Passkey (WebAuthn) login
+    Passkeys,
+    /// Self-service registration
+    Registration,
+    /// Credit points
+    Points,
+    /// Online viewers and editors for files
+    FileViewers,
+}
+
+/// Features the unified API implements for a given API version
+fn version_features(version: ApiVersion) -> &'static [Feature] {
+    match version {
+        ApiVersion::V3 => &[Feature::Preview, Feature::Thumbnails, Feature::ShareDelete],
+        ApiVersion::V4 => &[
+            Feature::RestoreFromTrash,
+            Feature::Preview,
+            Feature::Thumbnails,
+            Feature::ShareUpdate,
+            Feature::ShareDelete,
+            Feature::DavAccountManagement,
+        ],
+    }
+}
+
+/// What a server and this client can do together
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub api_version: ApiVersion,
+    /// Server version reported by `ping`, if it could be read
+    pub server_version: Option<String>,
+    /// Whether the client runs on a custom backend
+    pub custom_backend: bool,
+    /// Passkey login is enabled
+    pub authn: bool,
+    pub register_enabled: bool,
+    pub point_enabled: bool,
+    /// Extensions the server generates thumbnails for; `None` if it does not say
+    pub thumb_exts: Option<Vec<String>>,
+    pub file_viewers: Vec<FileViewer>,
+    features: BTreeSet<Feature>,
+}
+
+impl Capabilities {
+    /// Build the capabilities from the API version and the site config
+    ///
+    /// Without a site config only version-dependent features are reported.
+    pub fn new(
+        api_version: ApiVersion,
+        server_version: Option<String>,
+        site_config: Option<&SiteConfigValue>,
+    ) -> Self {
+        let mut caps = Self {
+            api_version,
+            server_version,
+            custom_backend: false,
+            authn: false,
+            register_enabled: false,
+            point_enabled: false,
+            thumb_exts: None,
+            file_viewers: Vec::new(),
+            features: version_features(api_version).iter().copied().collect(),
+        };
+
+        match site_config {
+            Some(SiteConfigValue::V3(config)) => {
+                caps.authn = config.authn;
+                caps.register_enabled = config.register_enabled;
+            }
+            Some(SiteConfigValue::V4(config)) => {
+                caps.authn = config.authn.unwrap_or(false);
+                caps.register_enabled = config.register_enabled.unwrap_or(false);
+                caps.point_enabled = config.point_enabled.unwrap_or(false);
+                caps.thumb_exts = config.thumb_exts.clone();
+                caps.file_viewers = config.file_viewers.clone().unwrap_or_default();
+            }
+            None => {}
+        }
+
+        for (enabled, feature) in [
+            (caps.authn, Feature::Passkeys),
+            (caps.register_enabled, Feature::Registration),
+            (caps.point_enabled, Feature::Points),
+            (!caps.file_viewers.is_empty(), Feature::FileViewers),
+        ] {
+            if enabled {
+                caps.features.insert(feature);
+            }
+        }
+        caps
+    }
+
+    /// Check whether a feature is available
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Get all available features
+    pub fn features(&self) -> impl Iterator<Item = Feature> + '_ {
+        self.features.iter().copied()
+    }
+
+    /// Check whether a thumbnail can be requested for a file name
+    pub fn can_thumbnail(&self, file_name: &str) -> bool {
+        if !self.supports(Feature::Thumbnails) {
+            return false;
+        }
+        let Some(exts) = &self.thumb_exts else {
+            return true;
+        };
+        file_name
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| exts.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+}
+
+/// Combine the v4 site config sections into one config
+///
+/// Each section only fills its own fields, so the first value found wins.
+fn merge_v4_sections(sections: Vec<V4SiteConfig>) -> V4SiteConfig {
+    let mut merged = V4SiteConfig::default();
+    for section in sections {
+        merged.authn = merged.authn.or(section.authn);
+        merged.register_enabled = merged.register_enabled.or(section.register_enabled);
+        merged.point_enabled = merged.point_enabled.or(section.point_enabled);
+        merged.thumb_exts = merged.thumb_exts.or(section.thumb_exts);
+        merged.file_viewers = merged.file_viewers.or(section.file_viewers);
+    }
+    merged
+}
+
+/// Capability discovery methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Discover what the server supports
+    ///
+    /// Combines the API version, the server version and the site config
+    /// flags. Site config sections that cannot be read are skipped, so this
+    /// only fails if none of them can be read. On a custom backend only the
+    /// core file operations are reported.
+    pub async fn capabilities(&self) -> Result<Capabilities, Error> {
+        debug!("Discovering server capabilities");
+
+        let server_version = match self.get_server_version().await {
+            Ok(version) => Some(version),
+            Err(e) => {
+                debug!("Could not read server version: {}", e);
+                None
+            }
+        };
+
+        if self.has_custom_backend() {
+            let mut caps = Capabilities::new(self.api_version(), server_version, None);
+            caps.custom_backend = true;
+            caps.features.clear();
+            return Ok(caps);
+        }
+
+        let site_config = match self.api_version() {
+            ApiVersion::V3 => self.get_site_config(None).await?,
+            ApiVersion::V4 => {
+                let mut sections = Vec::new();
+                let mut last_error = None;
+                for section in V4_SECTIONS {
+                    match self.get_site_config(Some(section)).await {
+                        Ok(SiteConfigValue::V4(config)) => sections.push(*config),
+                        Ok(SiteConfigValue::V3(_)) => {}
+                        Err(e) => {
+                            debug!("Could not read site config section {}: {}", section, e);
+                            last_error = Some(e);
+                        }
+                    }
+                }
+                if sections.is_empty()
+                    && let Some(e) = last_error
+                {
+                    return Err(e);
+                }
+                SiteConfigValue::V4(Box::new(merge_v4_sections(sections)))
+            }
+        };
+
+        Ok(Capabilities::new(
+            self.api_version(),
+            server_version,
+            Some(&site_config),
+        ))
+    }
+}
@@ -29,7 +29,7 @@ impl super::CloudreveAPI {
     pub async fn list_dav_accounts(&self, page_size: u32) -> Result<DavListResponse, Error> {
         debug!("Listing WebDAV accounts with page_size: {}", page_size);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(client) => {
                 let accounts = client.get_webdav_accounts().await?;
                 let dav_accounts = accounts
@@ -80,7 +80,7 @@ impl super::CloudreveAPI {
     ) -> Result<(), Error> {
         debug!("Creating WebDAV account: {} at {}", name, uri);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
                 "create WebDAV account".to_string(),
                 "v3".to_string(),
@@ -112,7 +112,7 @@ impl super::CloudreveAPI {
     ) -> Result<(), Error> {
         debug!("Updating WebDAV account: {}", id);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
                 "update WebDAV account".to_string(),
                 "v3".to_string(),
@@ -147,7 +147,7 @@ impl super::CloudreveAPI {
     pub async fn delete_dav_account(&self, id: &str) -> Result<(), Error> {
         debug!("Deleting WebDAV account: {}", id);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
                 "delete WebDAV account".to_string(),
                 "v3".to_string(),
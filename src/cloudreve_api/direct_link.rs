@@ -0,0 +1,314 @@
+//! Direct link operations for CloudreveAPI
+//!
+//! Direct links are permanent URLs that serve a file without signing in,
+//! e.g. to embed images in other pages. v4 stores them per file and can
+//! delete them; v3 derives the link from the file, so there is exactly one
+//! per file and it cannot be revoked.
+
+use crate::Error;
+use crate::api::v3::models::{FileSource, FileSourceRequest};
+use crate::api::v4::ApiV4Client;
+use crate::api::v4::models::{
+    CreateDirectLinkRequest, DirectLink, DirectLinkResponse, GetFileInfoRequest,
+};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::file::FileInfo;
+use crate::cloudreve_api::path::normalize_path;
+use log::debug;
+use std::collections::HashSet;
+
+/// Unified direct link item
+#[derive(Debug, Clone)]
+pub struct DirectLinkItem {
+    /// Link ID for [`CloudreveAPI::delete_direct_link`](super::CloudreveAPI::delete_direct_link);
+    /// `None` on v3
+    pub id: Option<String>,
+    pub url: String,
+    /// Path of the linked file
+    pub path: String,
+    /// Number of downloads through the link (v4 only)
+    pub downloads: Option<u64>,
+    pub created_at: Option<String>,
+}
+
+impl DirectLinkItem {
+    fn from_v4(link: DirectLink, path: &str) -> Self {
+        Self {
+            id: Some(link.id),
+            url: link.url,
+            path: path.to_string(),
+            downloads: Some(link.downloaded.max(0.0) as u64),
+            created_at: Some(link.created_at),
+        }
+    }
+}
+
+/// Compare two v4 file URIs, ignoring percent-encoding and trailing slashes
+fn same_uri(a: &str, b: &str) -> bool {
+    let canonical = |uri: &str| {
+        urlencoding::decode(uri)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or_else(|_| uri.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    };
+    canonical(a) == canonical(b)
+}
+
+/// Split indexes into batches without repeated names, keeping their order
+fn distinct_name_batches(names: &[&str]) -> Vec<Vec<usize>> {
+    let mut batches: Vec<(HashSet<&str>, Vec<usize>)> = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        match batches.iter_mut().find(|(seen, _)| !seen.contains(name)) {
+            Some((seen, batch)) => {
+                seen.insert(name);
+                batch.push(i);
+            }
+            None => batches.push((HashSet::from([*name]), vec![i])),
+        }
+    }
+    batches.into_iter().map(|(_, batch)| batch).collect()
+}
+
+/// Find the v3 source of a file by name, ignoring sources without a URL
+fn find_source<'a>(sources: &'a [FileSource], name: &str) -> Option<&'a FileSource> {
+    sources
+        .iter()
+        .find(|source| source.name == name && !source.url.is_empty())
+}
+
+/// Pick the created v4 link for each requested URI
+///
+/// Links are matched by their source URI. Servers that leave it out return
+/// the links in request order, so a link without one is taken by position.
+fn match_created_links<'a>(
+    uris: &[String],
+    created: &'a [DirectLinkResponse],
+) -> Vec<Option<&'a DirectLinkResponse>> {
+    uris.iter()
+        .enumerate()
+        .map(|(i, uri)| {
+            created
+                .iter()
+                .find(|link| !link.file_url.is_empty() && same_uri(&link.file_url, uri))
+                .or_else(|| created.get(i).filter(|link| link.file_url.is_empty()))
+        })
+        .collect()
+}
+
+/// Get the link ID from a v4 direct link URL, e.g. `https://host/f/aB3x/a.png`
+fn link_id(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let mut segments = url.path_segments()?;
+    segments.find(|segment| *segment == "f")?;
+    segments
+        .next()
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+/// Get the stored direct links of a file from its v4 extended info
+async fn v4_direct_links(client: &ApiV4Client, path: &str) -> Result<Vec<DirectLinkItem>, Error> {
+    let request = GetFileInfoRequest {
+        uri: path,
+        include_extended_info: Some(true),
+    };
+    let file = client.get_file_info_extended(&request).await?;
+    let path = normalize_path(path);
+    Ok(file
+        .extended_info
+        .and_then(|info| info.direct_links)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|link| DirectLinkItem::from_v4(link, path))
+        .collect())
+}
+
+impl super::CloudreveAPI {
+    /// Create direct links for files
+    ///
+    /// Returns one link per path, in order. Files that already have a
+    /// direct link get the existing one. Download counts and creation times
+    /// are left out; [`Self::list_direct_links`] has them. The storage policy of each file
+    /// must allow direct links; fails with [`Error::InvalidResponse`] if the
+    /// server returns no link for one of them.
+    pub async fn create_direct_link(&self, paths: &[&str]) -> Result<Vec<DirectLinkItem>, Error> {
+        debug!("Creating direct links for {:?}", paths);
+
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                let mut objects = Vec::with_capacity(paths.len());
+                for path in paths {
+                    match self.get_file_info(path).await? {
+                        FileInfo::V3(obj) => objects.push((normalize_path(path), obj)),
+                        FileInfo::V4(_) => {
+                            return Err(Error::UnsupportedFeature(
+                                "direct links for v4 files".to_string(),
+                                "v3".to_string(),
+                            ));
+                        }
+                    }
+                }
+
+                // Sources only carry the file name and a numeric parent ID
+                // that paths do not resolve to, so names are kept distinct
+                // within each request and sources are matched by name
+                let names: Vec<&str> = objects.iter().map(|(_, obj)| obj.name.as_str()).collect();
+                let mut links = Vec::with_capacity(objects.len());
+                for batch in distinct_name_batches(&names) {
+                    let items = batch.iter().map(|&i| objects[i].1.id.clone()).collect();
+                    let sources = client.get_file_source(&FileSourceRequest { items }).await?;
+                    for i in batch {
+                        let (path, obj) = &objects[i];
+                        let source = find_source(&sources, &obj.name).ok_or_else(|| {
+                            Error::InvalidResponse(format!("No direct link for {}", path))
+                        })?;
+                        links.push((
+                            i,
+                            DirectLinkItem {
+                                id: None,
+                                url: source.url.clone(),
+                                path: path.to_string(),
+                                downloads: None,
+                                created_at: None,
+                            },
+                        ));
+                    }
+                }
+                links.sort_by_key(|(i, _)| *i);
+                Ok(links.into_iter().map(|(_, link)| link).collect())
+            }
+            UnifiedClient::V4(client) => {
+                let uris: Vec<String> = paths.iter().map(|p| path_to_uri(p)).collect();
+                let request = CreateDirectLinkRequest { uris: uris.clone() };
+                let created = client.create_direct_links(&request).await?;
+
+                paths
+                    .iter()
+                    .zip(match_created_links(&uris, &created))
+                    .map(|(path, link)| {
+                        let link = link.ok_or_else(|| {
+                            Error::InvalidResponse(format!("No direct link for {}", path))
+                        })?;
+                        Ok(DirectLinkItem {
+                            id: link_id(&link.link),
+                            url: link.link.clone(),
+                            path: normalize_path(path).to_string(),
+                            downloads: None,
+                            created_at: None,
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// List the direct links of a file
+    ///
+    /// On v3 this is the file's derived link, which requires a storage
+    /// policy that allows direct links.
+    pub async fn list_direct_links(&self, path: &str) -> Result<Vec<DirectLinkItem>, Error> {
+        debug!("Listing direct links of: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => self.create_direct_link(&[path]).await,
+            UnifiedClient::V4(client) => v4_direct_links(client, path).await,
+        }
+    }
+
+    /// Delete a direct link by ID
+    pub async fn delete_direct_link(&self, id: &str) -> Result<(), Error> {
+        debug!("Deleting direct link: {}", id);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "deleting direct links".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => client.delete_direct_link(id).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_uri() {
+        assert!(same_uri(
+            "cloudreve://my/docs/a%20b.txt",
+            "cloudreve://my/docs/a b.txt"
+        ));
+        assert!(same_uri("cloudreve://my/docs/", "cloudreve://my/docs"));
+        assert!(!same_uri(
+            "cloudreve://my/docs/a.txt",
+            "cloudreve://my/a.txt"
+        ));
+    }
+
+    #[test]
+    fn test_v3_sources_matched_by_name_in_batches() {
+        let batches = distinct_name_batches(&["a.png", "b.png", "a.png", "a.png"]);
+        assert_eq!(batches, vec![vec![0, 1], vec![2], vec![3]]);
+
+        let sources = vec![
+            FileSource {
+                url: "https://drive.example.com/f/2/b.png".to_string(),
+                name: "b.png".to_string(),
+                parent: 1,
+            },
+            FileSource {
+                url: String::new(),
+                name: "a.png".to_string(),
+                parent: 1,
+            },
+        ];
+        assert_eq!(find_source(&sources, "b.png").unwrap().parent, 1);
+        assert!(find_source(&sources, "a.png").is_none());
+        assert!(find_source(&sources, "c.png").is_none());
+    }
+
+    #[test]
+    fn test_created_links_matched_by_uri_or_position() {
+        let created: Vec<DirectLinkResponse> = serde_json::from_value(serde_json::json!([
+            {"link": "https://drive.example.com/f/Zk9q/b.png", "file_url": "cloudreve://my/b.png"},
+            {"link": "https://drive.example.com/f/aB3x/a.png", "file_url": "cloudreve://my/a.png"}
+        ]))
+        .unwrap();
+        let uris = vec![
+            "cloudreve://my/a.png".to_string(),
+            "cloudreve://my/b.png".to_string(),
+        ];
+        let matched = match_created_links(&uris, &created);
+        assert_eq!(
+            matched[0].unwrap().link,
+            "https://drive.example.com/f/aB3x/a.png"
+        );
+        assert_eq!(
+            matched[1].unwrap().link,
+            "https://drive.example.com/f/Zk9q/b.png"
+        );
+
+        let positional: Vec<DirectLinkResponse> = serde_json::from_value(serde_json::json!([
+            {"link": "https://drive.example.com/f/aB3x/a.png"}
+        ]))
+        .unwrap();
+        let matched = match_created_links(&uris, &positional);
+        assert_eq!(
+            matched[0].unwrap().link,
+            "https://drive.example.com/f/aB3x/a.png"
+        );
+        assert!(matched[1].is_none());
+    }
+
+    #[test]
+    fn test_link_id() {
+        assert_eq!(
+            link_id("https://drive.example.com/f/aB3x/a.png").as_deref(),
+            Some("aB3x")
+        );
+        assert_eq!(link_id("https://drive.example.com/a.png"), None);
+    }
+}
@@ -13,7 +13,7 @@ impl super::CloudreveAPI {
     pub async fn create_download_url(&self, path: &str) -> Result<String, Error> {
         debug!("Creating download URL for: {}", path);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V3(client) => {
                 // V3: Need to get file ID first, then get download URL
                 let url = client.download_file(path).await?;
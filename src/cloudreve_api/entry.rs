@@ -0,0 +1,197 @@
+//! Typed, version-agnostic file entries
+//!
+//! [`FileItem`](super::FileItem) and [`FileInfo`](super::FileInfo) expose
+//! what both API versions have in common as strings. [`Entry`] goes one step
+//! further: timestamps are parsed into `DateTime<Utc>`, the object type is an
+//! [`EntryKind`], paths are plain `/`-separated paths on both versions, and
+//! the version-specific object stays available as [`RawEntry`].
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::uri_to_path;
+use crate::cloudreve_api::file::{FileInfo, FileList, FileListAll};
+use crate::cloudreve_api::path::join_path;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::warn;
+
+/// Timestamp formats without an offset, interpreted as UTC
+const NAIVE_TIMESTAMP_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// Kind of a file system object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    File,
+    Folder,
+}
+
+/// The version-specific object an entry was built from
+#[derive(Debug, Clone)]
+pub enum RawEntry {
+    V3(v3_models::Object),
+    V4(Box<v4_models::File>),
+}
+
+/// A file or folder with typed fields
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    /// Full path, e.g. `/docs/report.pdf`
+    pub path: String,
+    pub kind: EntryKind,
+    /// Size in bytes; 0 for folders
+    pub size: u64,
+    /// MIME type guessed from the file extension, best effort; `None` for
+    /// folders
+    pub mime: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub raw: RawEntry,
+}
+
+impl Entry {
+    /// Build an entry from a v3 object
+    pub fn from_v3(obj: v3_models::Object) -> Result<Self, Error> {
+        let kind = if obj.object_type == "dir" {
+            EntryKind::Folder
+        } else {
+            EntryKind::File
+        };
+        let dir = if obj.path.is_empty() { "/" } else { &obj.path };
+        Ok(Self {
+            name: obj.name.clone(),
+            path: join_path(dir, &obj.name),
+            kind,
+            size: obj.size.max(0) as u64,
+            mime: guess_mime(kind, &obj.name),
+            created_at: parse_timestamp(&obj.create_date)?,
+            updated_at: parse_timestamp(&obj.date)?,
+            raw: RawEntry::V3(obj),
+        })
+    }
+
+    /// Build an entry from a v4 file
+    pub fn from_v4(file: v4_models::File) -> Result<Self, Error> {
+        let kind = match file.r#type {
+            v4_models::FileType::File => EntryKind::File,
+            v4_models::FileType::Folder => EntryKind::Folder,
+        };
+        Ok(Self {
+            name: file.name.clone(),
+            path: uri_to_path(&file.path)
+                .map(str::to_string)
+                .unwrap_or_else(|_| file.path.clone()),
+            kind,
+            size: file.size.max(0) as u64,
+            mime: guess_mime(kind, &file.name),
+            created_at: parse_timestamp(&file.created_at)?,
+            updated_at: parse_timestamp(&file.updated_at)?,
+            raw: RawEntry::V4(Box::new(file)),
+        })
+    }
+
+    /// Check whether the entry is a folder
+    pub fn is_folder(&self) -> bool {
+        self.kind == EntryKind::Folder
+    }
+
+    /// Get the lowercase file extension, if any
+    pub fn extension(&self) -> Option<String> {
+        extension(&self.name)
+    }
+}
+
+impl TryFrom<FileInfo> for Entry {
+    type Error = Error;
+
+    fn try_from(info: FileInfo) -> Result<Self, Error> {
+        match info {
+            FileInfo::V3(obj) => Entry::from_v3(obj),
+            FileInfo::V4(file) => Entry::from_v4(file),
+        }
+    }
+}
+
+impl FileInfo {
+    /// Convert into a typed entry
+    pub fn to_entry(&self) -> Result<Entry, Error> {
+        Entry::try_from(self.clone())
+    }
+}
+
+/// Keep the entries that could be built, logging the ones that could not
+pub(crate) fn collect_entries(entries: impl Iterator<Item = Result<Entry, Error>>) -> Vec<Entry> {
+    entries
+        .filter_map(|entry| {
+            entry
+                .inspect_err(|e| warn!("Skipping listing entry: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+impl FileList {
+    /// Get files and folders as typed entries
+    ///
+    /// Objects with unparseable fields are skipped and logged, so a single
+    /// bad object does not make the whole listing unreadable.
+    pub fn entries(&self) -> Vec<Entry> {
+        match self {
+            FileList::V3(d) => collect_entries(d.objects.iter().cloned().map(Entry::from_v3)),
+            FileList::V4(r) => collect_entries(r.files.iter().cloned().map(Entry::from_v4)),
+        }
+    }
+}
+
+impl FileListAll {
+    /// Get files and folders of all pages as typed entries
+    ///
+    /// Objects with unparseable fields are skipped and logged like in
+    /// [`FileList::entries`].
+    pub fn entries(&self) -> Vec<Entry> {
+        match self {
+            FileListAll::V3(d) => collect_entries(d.objects.iter().cloned().map(Entry::from_v3)),
+            FileListAll::V4(r) => collect_entries(r.files.iter().cloned().map(Entry::from_v4)),
+        }
+    }
+}
+
+/// Parse a timestamp as returned by either API version
+///
+/// Accepts RFC 3339 with any number of fractional digits, which both
+/// versions use, and falls back to timestamps without an offset, which are
+/// taken as UTC.
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, Error> {
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+    NAIVE_TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|naive| naive.and_utc())
+        .ok_or_else(|| Error::InvalidTimestamp(value.to_string()))
+}
+
+/// Get the lowercase extension of a file name
+pub(crate) fn extension(name: &str) -> Option<String> {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => Some(ext.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// Guess a MIME type from a file name
+///
+/// Best effort from the file extension through `mime_guess`; files with an
+/// unknown or no extension are `application/octet-stream`.
+pub(crate) fn guess_mime(kind: EntryKind, name: &str) -> Option<String> {
+    if kind == EntryKind::Folder {
+        return None;
+    }
+    Some(
+        mime_guess::from_path(name)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string(),
+    )
+}
@@ -0,0 +1,407 @@
+//! Listing cache for CloudreveAPI
+//!
+//! An optional cache for `list_files`, `list_files_all` and `get_file_info`,
+//! meant for interactive tools that browse the same folders repeatedly. The
+//! cache is disabled until [`CloudreveAPI::enable_listing_cache`] is called.
+//!
+//! Entries expire after a TTL, and the least recently used entries are
+//! evicted once the configured number of entries or cached items is
+//! exceeded. Create, rename, move, copy, delete and upload calls made through
+//! this crate drop the affected entries automatically.
+
+use crate::cloudreve_api::file::{FileInfo, FileList, FileListAll};
+use crate::cloudreve_api::path::{normalize_path, split_path};
+use log::debug;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings for the listing cache
+#[derive(Debug, Clone)]
+pub struct ListingCacheConfig {
+    /// How long an entry is served before it is fetched again
+    pub ttl: Duration,
+    /// Maximum number of cached responses
+    pub max_entries: usize,
+    /// Maximum number of files and folders across all cached responses
+    pub max_items: usize,
+}
+
+impl Default for ListingCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(10),
+            max_entries: 256,
+            max_items: 50_000,
+        }
+    }
+}
+
+/// Hit and miss counters of the listing cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListingCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+    /// Number of responses currently cached
+    pub entries: usize,
+}
+
+impl ListingCacheStats {
+    /// Fraction of lookups served from the cache, between 0 and 1
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// What a cache entry was produced by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ListingKey {
+    List {
+        path: String,
+        page: Option<u32>,
+        page_size: Option<u32>,
+    },
+    ListAll {
+        path: String,
+        page_size: Option<u32>,
+    },
+    Info {
+        path: String,
+    },
+}
+
+impl ListingKey {
+    pub(crate) fn list(path: &str, page: Option<u32>, page_size: Option<u32>) -> Self {
+        ListingKey::List {
+            path: normalize_path(path).to_string(),
+            page,
+            page_size,
+        }
+    }
+
+    pub(crate) fn list_all(path: &str, page_size: Option<u32>) -> Self {
+        ListingKey::ListAll {
+            path: normalize_path(path).to_string(),
+            page_size,
+        }
+    }
+
+    pub(crate) fn info(path: &str) -> Self {
+        ListingKey::Info {
+            path: normalize_path(path).to_string(),
+        }
+    }
+
+    /// Whether a change at `changed` can make this entry stale
+    ///
+    /// Listings go stale when an object inside them or the listed directory
+    /// itself changes; file info goes stale when the object or one of its
+    /// ancestors changes.
+    fn affected_by(&self, changed: &str) -> bool {
+        let is_at_or_below = |path: &str| {
+            changed == "/" || path == changed || path.starts_with(&format!("{}/", changed))
+        };
+        match self {
+            ListingKey::List { path, .. } | ListingKey::ListAll { path, .. } => {
+                is_at_or_below(path) || path == split_path(changed).0
+            }
+            ListingKey::Info { path } => is_at_or_below(path),
+        }
+    }
+}
+
+/// A cached response
+#[derive(Debug, Clone)]
+pub(crate) enum ListingValue {
+    List(FileList),
+    ListAll(FileListAll),
+    Info(FileInfo),
+}
+
+impl ListingValue {
+    /// Rough size of the response, counted in files and folders
+    fn weight(&self) -> usize {
+        match self {
+            ListingValue::List(list) => list.total_count().max(1),
+            ListingValue::ListAll(list) => list.total_count().max(1),
+            ListingValue::Info(_) => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: ListingValue,
+    stored: Instant,
+    last_used: u64,
+    weight: usize,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    config: Option<ListingCacheConfig>,
+    entries: HashMap<ListingKey, Entry>,
+    total_weight: usize,
+    clock: u64,
+    stats: ListingCacheStats,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &ListingKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_weight -= entry.weight;
+        }
+    }
+
+    /// Evict least recently used entries until the bounds are met
+    fn enforce_bounds(&mut self, config: &ListingCacheConfig) {
+        while self.entries.len() > config.max_entries || self.total_weight > config.max_items {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// Shared listing cache behind a CloudreveAPI and its clones
+#[derive(Debug, Default)]
+pub(crate) struct ListingCache {
+    state: Mutex<CacheState>,
+}
+
+impl ListingCache {
+    fn state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn configure(&self, config: Option<ListingCacheConfig>) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.total_weight = 0;
+        state.config = config;
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.total_weight = 0;
+    }
+
+    pub(crate) fn stats(&self) -> ListingCacheStats {
+        let state = self.state();
+        ListingCacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+
+    /// Get a fresh entry, counting a hit or a miss
+    ///
+    /// Returns `None` without counting anything when the cache is disabled.
+    pub(crate) fn get(&self, key: &ListingKey) -> Option<ListingValue> {
+        let mut state = self.state();
+        let ttl = state.config.as_ref()?.ttl;
+
+        state.clock += 1;
+        let now = state.clock;
+        let fresh = match state.entries.get_mut(key) {
+            Some(entry) if entry.stored.elapsed() < ttl => {
+                entry.last_used = now;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                state.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        match fresh {
+            Some(value) => {
+                state.stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                state.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a response if the cache is enabled
+    pub(crate) fn insert(&self, key: ListingKey, value: ListingValue) {
+        let mut state = self.state();
+        let Some(config) = state.config.clone() else {
+            return;
+        };
+
+        let weight = value.weight();
+        if weight > config.max_items {
+            return;
+        }
+
+        state.remove(&key);
+        state.clock += 1;
+        let entry = Entry {
+            value,
+            stored: Instant::now(),
+            last_used: state.clock,
+            weight,
+        };
+        state.total_weight += weight;
+        state.entries.insert(key, entry);
+        state.enforce_bounds(&config);
+    }
+
+    /// Drop every entry that a change at `path` can make stale
+    pub(crate) fn invalidate(&self, path: &str) {
+        let path = normalize_path(path);
+        let mut state = self.state();
+        if state.entries.is_empty() {
+            return;
+        }
+
+        let stale: Vec<ListingKey> = state
+            .entries
+            .keys()
+            .filter(|key| key.affected_by(path))
+            .cloned()
+            .collect();
+        if !stale.is_empty() {
+            debug!(
+                "Listing cache: dropping {} entries for {}",
+                stale.len(),
+                path
+            );
+        }
+        for key in &stale {
+            state.remove(key);
+        }
+        state.stats.invalidations += stale.len() as u64;
+    }
+}
+
+/// Listing cache methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Turn on the listing cache, replacing any previous configuration
+    ///
+    /// Cached responses are shared with clones of this client.
+    pub fn enable_listing_cache(&self, config: ListingCacheConfig) {
+        debug!("Enabling listing cache: {:?}", config);
+        self.listing_cache.configure(Some(config));
+    }
+
+    /// Turn off the listing cache and drop all entries
+    pub fn disable_listing_cache(&self) {
+        debug!("Disabling listing cache");
+        self.listing_cache.configure(None);
+    }
+
+    /// Drop all cached listings and file info, keeping the configuration
+    pub fn clear_listing_cache(&self) {
+        self.listing_cache.clear();
+    }
+
+    /// Get hit and miss statistics of the listing cache
+    pub fn listing_cache_stats(&self) -> ListingCacheStats {
+        self.listing_cache.stats()
+    }
+
+    /// Drop cached state that a change at `path` can make stale
+    ///
+    /// Called after every mutation made through this crate.
+    pub(super) fn invalidate_cached(&self, path: &str) {
+        self.path_cache.invalidate(path);
+        self.listing_cache.invalidate(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::v3::models as v3_models;
+
+    fn info(name: &str) -> ListingValue {
+        ListingValue::Info(FileInfo::V3(v3_models::Object {
+            id: name.to_string(),
+            name: name.to_string(),
+            path: String::new(),
+            thumb: false,
+            size: 0,
+            object_type: "file".to_string(),
+            date: String::new(),
+            create_date: String::new(),
+            source_enabled: false,
+        }))
+    }
+
+    fn enabled(max_entries: usize) -> ListingCache {
+        let cache = ListingCache::default();
+        cache.configure(Some(ListingCacheConfig {
+            max_entries,
+            ..Default::default()
+        }));
+        cache
+    }
+
+    #[test]
+    fn test_disabled_cache_counts_nothing() {
+        let cache = ListingCache::default();
+        cache.insert(ListingKey::info("/a"), info("a"));
+
+        assert!(cache.get(&ListingKey::info("/a")).is_none());
+        assert_eq!(cache.stats(), ListingCacheStats::default());
+    }
+
+    #[test]
+    fn test_hits_and_misses() {
+        let cache = enabled(10);
+        assert!(cache.get(&ListingKey::info("/a")).is_none());
+        cache.insert(ListingKey::info("/a"), info("a"));
+        assert!(cache.get(&ListingKey::info("/a/")).is_some());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = enabled(2);
+        cache.insert(ListingKey::info("/a"), info("a"));
+        cache.insert(ListingKey::info("/b"), info("b"));
+        cache.get(&ListingKey::info("/a"));
+        cache.insert(ListingKey::info("/c"), info("c"));
+
+        assert!(cache.get(&ListingKey::info("/a")).is_some());
+        assert!(cache.get(&ListingKey::info("/b")).is_none());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_invalidation_scope() {
+        let key_parent = ListingKey::list("/docs", None, None);
+        let key_self = ListingKey::list_all("/docs/sub", None);
+        let key_child = ListingKey::info("/docs/sub/a.txt");
+        let key_other = ListingKey::list("/docs/other", None, None);
+
+        assert!(key_parent.affected_by("/docs/sub"));
+        assert!(key_self.affected_by("/docs/sub"));
+        assert!(key_child.affected_by("/docs/sub"));
+        assert!(!key_other.affected_by("/docs/sub"));
+        assert!(!ListingKey::info("/docs").affected_by("/docs/sub"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Typed media metadata for CloudreveAPI
+//!
+//! The v4 `media_meta` task extracts EXIF data from images, tags from audio
+//! files and stream info from videos into the file's server-maintained
+//! metadata, under the `exif:`, `music:` and `stream:` namespaces.
+//! [`MediaMetadata`] parses those keys into typed fields; keys a server
+//! does not fill are `None`.
+
+use crate::Error;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::entry::{Entry, RawEntry, parse_timestamp};
+use crate::cloudreve_api::metadata::FileMetadata;
+use crate::cloudreve_api::path::normalize_path;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::debug;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Key prefix of image EXIF data
+const EXIF_NAMESPACE: &str = "exif:";
+
+/// Key prefix of audio tags
+const MUSIC_NAMESPACE: &str = "music:";
+
+/// Key prefix of audio and video stream info
+const STREAM_NAMESPACE: &str = "stream:";
+
+/// Timestamp format of the EXIF `DateTimeOriginal` tag
+const EXIF_TIMESTAMP_FORMAT: &str = "%Y:%m:%d %H:%M:%S";
+
+/// GPS position an image was taken at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Altitude in meters
+    pub altitude: Option<f64>,
+}
+
+/// EXIF data of an image
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageMetadata {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_make: Option<String>,
+    pub lens_model: Option<String>,
+    pub software: Option<String>,
+    pub taken_at: Option<DateTime<Utc>>,
+    pub gps: Option<GpsLocation>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub orientation: Option<u8>,
+    pub iso: Option<u32>,
+    pub f_number: Option<f64>,
+    /// Exposure time as recorded, e.g. `1/250`
+    pub exposure_time: Option<String>,
+    /// Focal length in millimeters
+    pub focal_length: Option<f64>,
+}
+
+/// Tags of an audio file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub composer: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub track: Option<u32>,
+    pub disc: Option<u32>,
+}
+
+/// Stream info of a video or audio file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VideoInfo {
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Bit rate in bits per second
+    pub bit_rate: Option<u64>,
+    /// Container format, e.g. `mov,mp4,m4a,3gp,3g2,mj2`
+    pub format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+}
+
+/// Media metadata of a file, grouped by kind
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaMetadata {
+    pub image: Option<ImageMetadata>,
+    pub audio: Option<AudioTags>,
+    pub video: Option<VideoInfo>,
+}
+
+/// Server-maintained keys of one namespace
+struct Namespace<'a> {
+    system: &'a BTreeMap<String, String>,
+    prefix: &'static str,
+}
+
+impl<'a> Namespace<'a> {
+    /// Select a namespace, or `None` if the file has no keys in it
+    fn select(system: &'a BTreeMap<String, String>, prefix: &'static str) -> Option<Self> {
+        system
+            .keys()
+            .any(|key| key.starts_with(prefix))
+            .then_some(Self { system, prefix })
+    }
+
+    /// Get the first non-empty value of the given keys
+    fn text(&self, keys: &[&str]) -> Option<String> {
+        keys.iter()
+            .filter_map(|key| self.system.get(&format!("{}{}", self.prefix, key)))
+            .map(|value| value.trim())
+            .find(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Parse the first value of the given keys that parses
+    fn parse<T: FromStr>(&self, keys: &[&str]) -> Option<T> {
+        keys.iter()
+            .filter_map(|key| self.system.get(&format!("{}{}", self.prefix, key)))
+            .find_map(|value| value.trim().parse().ok())
+    }
+
+    /// Parse a count such as a track number, which may be written as `3/12`
+    fn count(&self, keys: &[&str]) -> Option<u32> {
+        let value = self.text(keys)?;
+        value.split('/').next()?.trim().parse().ok()
+    }
+}
+
+/// Parse an EXIF capture time
+///
+/// Servers store either RFC 3339 or the raw EXIF format, which has no
+/// offset and is taken as UTC.
+fn parse_taken_at(value: &str) -> Option<DateTime<Utc>> {
+    parse_timestamp(value).ok().or_else(|| {
+        NaiveDateTime::parse_from_str(value.trim(), EXIF_TIMESTAMP_FORMAT)
+            .ok()
+            .map(|naive| naive.and_utc())
+    })
+}
+
+impl ImageMetadata {
+    fn from_namespace(exif: &Namespace) -> Self {
+        let gps = match (
+            exif.parse::<f64>(&["latitude", "gps_latitude"]),
+            exif.parse::<f64>(&["longitude", "gps_longitude"]),
+        ) {
+            (Some(latitude), Some(longitude)) => Some(GpsLocation {
+                latitude,
+                longitude,
+                altitude: exif.parse(&["altitude", "gps_altitude"]),
+            }),
+            _ => None,
+        };
+        Self {
+            camera_make: exif.text(&["camera_make", "make"]),
+            camera_model: exif.text(&["camera_model", "model"]),
+            lens_make: exif.text(&["lens_make"]),
+            lens_model: exif.text(&["lens_model"]),
+            software: exif.text(&["software"]),
+            taken_at: exif
+                .text(&["taken_at", "date_time_original"])
+                .and_then(|value| parse_taken_at(&value)),
+            gps,
+            width: exif.parse(&["x", "width"]),
+            height: exif.parse(&["y", "height"]),
+            orientation: exif.parse(&["orientation"]),
+            iso: exif.parse(&["iso"]),
+            f_number: exif.parse(&["f", "f_number"]),
+            exposure_time: exif.text(&["exposure_time"]),
+            focal_length: exif.parse(&["focal_length"]),
+        }
+    }
+}
+
+impl AudioTags {
+    fn from_namespace(music: &Namespace) -> Self {
+        Self {
+            title: music.text(&["title"]),
+            artist: music.text(&["artist"]),
+            album: music.text(&["album"]),
+            album_artist: music.text(&["album_artist"]),
+            composer: music.text(&["composer"]),
+            genre: music.text(&["genre"]),
+            year: music.parse(&["year"]),
+            track: music.count(&["track"]),
+            disc: music.count(&["disc"]),
+        }
+    }
+}
+
+impl VideoInfo {
+    fn from_namespace(stream: &Namespace) -> Self {
+        Self {
+            duration: stream.parse(&["duration"]),
+            bit_rate: stream.parse(&["bit_rate", "bitrate"]),
+            format: stream.text(&["format", "format_name"]),
+            width: stream.parse(&["width"]),
+            height: stream.parse(&["height"]),
+            video_codec: stream.text(&["video_codec", "codec"]),
+            audio_codec: stream.text(&["audio_codec"]),
+        }
+    }
+}
+
+impl MediaMetadata {
+    /// Parse the media keys of a file's metadata
+    pub fn from_metadata(metadata: &FileMetadata) -> Self {
+        let system = &metadata.system;
+        Self {
+            image: Namespace::select(system, EXIF_NAMESPACE)
+                .map(|exif| ImageMetadata::from_namespace(&exif)),
+            audio: Namespace::select(system, MUSIC_NAMESPACE)
+                .map(|music| AudioTags::from_namespace(&music)),
+            video: Namespace::select(system, STREAM_NAMESPACE)
+                .map(|stream| VideoInfo::from_namespace(&stream)),
+        }
+    }
+
+    /// Check whether the server extracted no media metadata
+    pub fn is_empty(&self) -> bool {
+        self.image.is_none() && self.audio.is_none() && self.video.is_none()
+    }
+
+    /// Get the capture time of an image
+    pub fn taken_at(&self) -> Option<DateTime<Utc>> {
+        self.image.as_ref()?.taken_at
+    }
+}
+
+impl super::CloudreveAPI {
+    /// Get the media metadata of a file
+    ///
+    /// Empty until the server's `media_meta` task processed the file.
+    pub async fn get_media_metadata(&self, path: &str) -> Result<MediaMetadata, Error> {
+        debug!("Getting media metadata of: {}", path);
+
+        let metadata = self.get_metadata(path).await?;
+        Ok(MediaMetadata::from_metadata(&metadata))
+    }
+
+    /// Find the images in a folder taken between two times, inclusive
+    ///
+    /// Subfolders are searched if `recursive` is set. Files count as images
+    /// when the server extracted EXIF metadata for them, which is read from
+    /// the folder listings; images without a capture time are skipped.
+    /// Results are sorted by capture time. v3 has no media metadata and
+    /// fails with [`Error::UnsupportedFeature`].
+    pub async fn photos_taken_between(
+        &self,
+        dir: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        recursive: bool,
+    ) -> Result<Vec<(Entry, ImageMetadata)>, Error> {
+        debug!(
+            "Finding photos in {} taken between {} and {}",
+            dir, start, end
+        );
+
+        if let UnifiedClient::V3(_) = self.client()? {
+            return Err(Error::UnsupportedFeature(
+                "media metadata".to_string(),
+                "v3".to_string(),
+            ));
+        }
+
+        let mut photos = Vec::new();
+        let mut pending = vec![normalize_path(dir).to_string()];
+        while let Some(dir) = pending.pop() {
+            for entry in self.list_files_all(&dir, None).await?.entries() {
+                if entry.is_folder() {
+                    if recursive {
+                        pending.push(entry.path);
+                    }
+                    continue;
+                }
+                let RawEntry::V4(file) = &entry.raw else {
+                    continue;
+                };
+                let metadata = FileMetadata::from_value(file.metadata.as_ref());
+                let Some(image) = MediaMetadata::from_metadata(&metadata).image else {
+                    continue;
+                };
+                if image.taken_at.is_some_and(|t| start <= t && t <= end) {
+                    photos.push((entry, image));
+                }
+            }
+        }
+        photos.sort_by_key(|(_, image)| image.taken_at);
+        Ok(photos)
+    }
+}
@@ -0,0 +1,297 @@
+//! Typed file metadata for CloudreveAPI
+//!
+//! v4 servers keep string metadata per file under namespaced keys: user
+//! tags under `tag:` with the tag color as value, custom properties defined
+//! in the site config's `custom_props` under `props:`, and everything else,
+//! such as media info, is maintained by the server. [`FileMetadata`] sorts
+//! the keys into these groups and [`MetadataChange`]s to custom properties
+//! are validated against their definitions before they are sent.
+
+use crate::Error;
+use crate::api::v4::models::{CustomProps, MetadataPatch, PatchMetadataRequest};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::file::FileInfo;
+use crate::cloudreve_api::site::SiteConfigValue;
+use log::debug;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Key prefix of user tags
+const TAG_PREFIX: &str = "tag:";
+
+/// Key prefix of custom properties
+const PROP_PREFIX: &str = "props:";
+
+/// Site config sections that may carry the custom property definitions
+const PROP_SECTIONS: [&str; 2] = ["explorer", "basic"];
+
+/// A metadata key with its namespace
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MetadataKey {
+    /// User tag; the value is the tag color, possibly empty
+    Tag(String),
+    /// Custom property from the site config's `custom_props`
+    Prop(String),
+    /// Metadata maintained by the server, read-only
+    System(String),
+}
+
+impl MetadataKey {
+    /// Parse a raw key as stored on the server
+    pub fn parse(raw: &str) -> Self {
+        if let Some(name) = raw.strip_prefix(TAG_PREFIX) {
+            MetadataKey::Tag(name.to_string())
+        } else if let Some(key) = raw.strip_prefix(PROP_PREFIX) {
+            MetadataKey::Prop(key.to_string())
+        } else {
+            MetadataKey::System(raw.to_string())
+        }
+    }
+
+    /// Get the raw key as stored on the server
+    pub fn to_raw(&self) -> String {
+        match self {
+            MetadataKey::Tag(name) => format!("{}{}", TAG_PREFIX, name),
+            MetadataKey::Prop(key) => format!("{}{}", PROP_PREFIX, key),
+            MetadataKey::System(key) => key.clone(),
+        }
+    }
+
+    /// Check whether users may change the key
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, MetadataKey::System(_))
+    }
+}
+
+/// Metadata of a file, grouped by namespace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// Tag names with their colors
+    pub tags: BTreeMap<String, String>,
+    /// Custom property values by property key
+    pub props: BTreeMap<String, String>,
+    /// Server-maintained metadata by raw key
+    pub system: BTreeMap<String, String>,
+}
+
+impl FileMetadata {
+    /// Build from the metadata object of a v4 file
+    ///
+    /// Values that are not strings are kept in their JSON form.
+    pub fn from_value(metadata: Option<&Value>) -> Self {
+        let mut parsed = Self::default();
+        let Some(Value::Object(map)) = metadata else {
+            return parsed;
+        };
+        for (raw, value) in map {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            match MetadataKey::parse(raw) {
+                MetadataKey::Tag(name) => parsed.tags.insert(name, value),
+                MetadataKey::Prop(key) => parsed.props.insert(key, value),
+                MetadataKey::System(key) => parsed.system.insert(key, value),
+            };
+        }
+        parsed
+    }
+
+    /// Get the value of a key
+    pub fn get(&self, key: &MetadataKey) -> Option<&str> {
+        match key {
+            MetadataKey::Tag(name) => self.tags.get(name),
+            MetadataKey::Prop(key) => self.props.get(key),
+            MetadataKey::System(key) => self.system.get(key),
+        }
+        .map(String::as_str)
+    }
+}
+
+/// A change to one metadata key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataChange {
+    pub key: MetadataKey,
+    /// New value; `None` removes the key
+    pub value: Option<String>,
+}
+
+impl MetadataChange {
+    /// Set a key to a value
+    pub fn set(key: MetadataKey, value: impl Into<String>) -> Self {
+        Self {
+            key,
+            value: Some(value.into()),
+        }
+    }
+
+    /// Remove a key
+    pub fn remove(key: MetadataKey) -> Self {
+        Self { key, value: None }
+    }
+}
+
+/// Check a custom property value against its definition
+///
+/// Multi-select values are JSON arrays of options. Property types this
+/// client does not know are accepted as they are.
+pub fn validate_prop(definition: &CustomProps, value: &str) -> Result<(), Error> {
+    let options = definition.options.as_deref().unwrap_or_default();
+    let in_range = |n: f64| {
+        definition.min.is_none_or(|min| n >= min) && definition.max.is_none_or(|max| n <= max)
+    };
+
+    let reason = match definition.r#type.as_str() {
+        "text" if !in_range(value.chars().count() as f64) => Some("length out of range"),
+        "number" => match value.parse::<f64>() {
+            Ok(n) if n.is_finite() && in_range(n) => None,
+            Ok(_) => Some("out of range"),
+            Err(_) => Some("not a number"),
+        },
+        "boolean" if value != "true" && value != "false" => Some("not a boolean"),
+        "rating" => match value.parse::<u32>() {
+            Ok(n) if f64::from(n) <= definition.max.unwrap_or(5.0) => None,
+            _ => Some("not a valid rating"),
+        },
+        "select" if !options.iter().any(|o| o == value) => Some("not one of the options"),
+        "multi_select" => match serde_json::from_str::<Vec<String>>(value) {
+            Ok(selected) if selected.iter().all(|s| options.contains(s)) => None,
+            Ok(_) => Some("not one of the options"),
+            Err(_) => Some("not a JSON array of options"),
+        },
+        "link" if url::Url::parse(value).is_err() => Some("not a URL"),
+        _ => None,
+    };
+    match reason {
+        Some(reason) => Err(Error::InvalidValue(format!(
+            "{} for property {}: {}",
+            value,
+            definition.prop_key(),
+            reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+impl super::CloudreveAPI {
+    /// Get the typed metadata of a file
+    pub async fn get_metadata(&self, path: &str) -> Result<FileMetadata, Error> {
+        debug!("Getting metadata of: {}", path);
+
+        if let UnifiedClient::V3(_) = self.client()? {
+            return Err(Error::UnsupportedFeature(
+                "metadata".to_string(),
+                "v3".to_string(),
+            ));
+        }
+        match self.get_file_info(path).await? {
+            FileInfo::V4(file) => Ok(FileMetadata::from_value(file.metadata.as_ref())),
+            FileInfo::V3(_) => Err(Error::UnsupportedFeature(
+                "metadata".to_string(),
+                "v3".to_string(),
+            )),
+        }
+    }
+
+    /// Get the custom property definitions of the site
+    pub async fn custom_prop_definitions(&self) -> Result<Vec<CustomProps>, Error> {
+        debug!("Getting custom property definitions");
+
+        if let UnifiedClient::V3(_) = self.client()? {
+            return Err(Error::UnsupportedFeature(
+                "custom properties".to_string(),
+                "v3".to_string(),
+            ));
+        }
+        for section in PROP_SECTIONS {
+            if let SiteConfigValue::V4(config) = self.get_site_config(Some(section)).await?
+                && let Some(props) = config.custom_props
+            {
+                return Ok(props);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Set one metadata key of a file
+    pub async fn set_metadata(
+        &self,
+        path: &str,
+        key: MetadataKey,
+        value: &str,
+    ) -> Result<(), Error> {
+        self.update_metadata(&[path], &[MetadataChange::set(key, value)])
+            .await
+    }
+
+    /// Remove one metadata key of a file
+    pub async fn remove_metadata(&self, path: &str, key: MetadataKey) -> Result<(), Error> {
+        self.update_metadata(&[path], &[MetadataChange::remove(key)])
+            .await
+    }
+
+    /// Apply metadata changes to several files at once
+    ///
+    /// Fails with [`Error::InvalidValue`] before sending anything if a
+    /// change touches server-maintained metadata, names an undefined custom
+    /// property or sets a property to a value its definition rejects.
+    pub async fn update_metadata(
+        &self,
+        paths: &[&str],
+        changes: &[MetadataChange],
+    ) -> Result<(), Error> {
+        debug!("Updating {} metadata keys of {:?}", changes.len(), paths);
+
+        let client = match self.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "metadata".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        if let Some(change) = changes.iter().find(|c| !c.key.is_writable()) {
+            return Err(Error::InvalidValue(format!(
+                "{} is maintained by the server",
+                change.key.to_raw()
+            )));
+        }
+        let sets_props = changes
+            .iter()
+            .any(|c| matches!(c.key, MetadataKey::Prop(_)) && c.value.is_some());
+        if sets_props {
+            let definitions = self.custom_prop_definitions().await?;
+            for change in changes {
+                if let (MetadataKey::Prop(key), Some(value)) = (&change.key, &change.value) {
+                    let definition = definitions
+                        .iter()
+                        .find(|d| d.prop_key() == key)
+                        .ok_or_else(|| {
+                            Error::InvalidValue(format!("Undefined custom property: {}", key))
+                        })?;
+                    validate_prop(definition, value)?;
+                }
+            }
+        }
+
+        let request = PatchMetadataRequest {
+            uris: paths.iter().map(|p| path_to_uri(p)).collect(),
+            patches: changes
+                .iter()
+                .map(|change| MetadataPatch {
+                    key: change.key.to_raw(),
+                    value: change.value.clone().unwrap_or_default(),
+                    remove: change.value.is_none(),
+                })
+                .collect(),
+        };
+        client.patch_metadata_batch(&request).await?;
+        for path in paths {
+            self.invalidate_cached(path);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! Account migration from Cloudreve v3 to v4
+//!
+//! File contents are moved with [`CloudreveAPI::transfer_tree`]; this module
+//! covers the rest of an account: share links, WebDAV accounts and user
+//! settings. A migration runs in three steps:
+//!
+//! 1. [`CloudreveAPI::inventory_v3_account`] collects everything from the v3 server
+//! 2. [`MigrationPlan::new`] maps each object to its v4 request
+//! 3. [`CloudreveAPI::execute_migration`] creates the objects on the v4 server
+//!
+//! The plan can be inspected before it is executed. The resulting
+//! [`MigrationReport`] maps old share keys to their new URLs so that links
+//! published elsewhere can be updated.
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::models as v4_models;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::CloudreveAPI;
+use crate::cloudreve_api::path::{join_path, normalize_path};
+use crate::cloudreve_api::share::v3_expired_by_time;
+use log::{debug, warn};
+use std::collections::HashMap;
+
+/// Options controlling which parts of an account are migrated
+#[derive(Debug, Clone)]
+pub struct MigrationOptions {
+    /// Directory on the v4 server that corresponds to the v3 root
+    ///
+    /// Use the same value that was passed as the destination of
+    /// [`CloudreveAPI::transfer_tree`] when the files were copied.
+    pub dest_root: String,
+    pub shares: bool,
+    pub dav_accounts: bool,
+    pub settings: bool,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        Self {
+            dest_root: "/".to_string(),
+            shares: true,
+            dav_accounts: true,
+            settings: true,
+        }
+    }
+}
+
+/// A v3 share together with the path of the object it shares
+#[derive(Debug, Clone)]
+pub struct InventoryShare {
+    pub share: v3_models::Share,
+    /// Full path of the shared object, if it could be resolved
+    pub source_path: Option<String>,
+}
+
+/// Account data collected from a v3 server
+#[derive(Debug, Default)]
+pub struct V3Inventory {
+    /// Base URL of the v3 server, used to build the old share URLs
+    pub base_url: String,
+    pub shares: Vec<InventoryShare>,
+    pub dav_accounts: Vec<v3_models::WebdavAccount>,
+    pub nickname: Option<String>,
+    pub preferred_theme: Option<String>,
+}
+
+/// A share link to recreate on the v4 server
+#[derive(Debug)]
+pub struct PlannedShare {
+    pub old_key: String,
+    pub old_url: String,
+    pub request: v4_models::CreateShareLinkRequest,
+}
+
+/// A WebDAV account to recreate on the v4 server
+#[derive(Debug)]
+pub struct PlannedDavAccount {
+    pub old_id: i32,
+    pub request: v4_models::CreateDavAccountRequest,
+}
+
+/// User settings to apply on the v4 server
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlannedSettings {
+    pub nickname: Option<String>,
+    pub preferred_theme: Option<String>,
+}
+
+/// Everything a migration will create, mapped to v4 requests
+///
+/// Objects that cannot be migrated are listed in `skipped`. Objects that are
+/// migrated with reduced fidelity are listed in `warnings`: shares lose their
+/// visit counts, and WebDAV accounts get new passwords.
+#[derive(Debug, Default)]
+pub struct MigrationPlan {
+    pub shares: Vec<PlannedShare>,
+    pub dav_accounts: Vec<PlannedDavAccount>,
+    pub settings: Option<PlannedSettings>,
+    pub skipped: Vec<(String, String)>,
+    pub warnings: Vec<(String, String)>,
+}
+
+/// Mapping of an old v3 share to the share created on v4
+#[derive(Debug, Clone)]
+pub struct ShareMapping {
+    pub old_key: String,
+    pub old_url: String,
+    pub new_url: String,
+}
+
+/// Mapping of an old v3 WebDAV account to the account created on v4
+///
+/// v4 generates new WebDAV passwords, so clients have to be reconfigured with
+/// `new_password`.
+#[derive(Debug, Clone)]
+pub struct DavAccountMapping {
+    pub old_id: i32,
+    pub name: String,
+    pub new_id: String,
+    pub new_password: String,
+}
+
+/// Result of executing a migration plan
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub shares: Vec<ShareMapping>,
+    pub dav_accounts: Vec<DavAccountMapping>,
+    pub settings_migrated: bool,
+    pub skipped: Vec<(String, String)>,
+    pub warnings: Vec<(String, String)>,
+    pub errors: Vec<(String, String)>,
+}
+
+/// Map a v3 path onto the v4 directory that replaces the v3 root
+///
+/// # Examples
+/// ```
+/// use cloudreve_api::cloudreve_api::migration::remap_path;
+///
+/// assert_eq!(remap_path("/docs/a.txt", "/"), "/docs/a.txt");
+/// assert_eq!(remap_path("/docs/a.txt", "/from-v3"), "/from-v3/docs/a.txt");
+/// assert_eq!(remap_path("/", "/from-v3"), "/from-v3");
+/// ```
+pub fn remap_path(path: &str, dest_root: &str) -> String {
+    let relative = normalize_path(path).trim_start_matches('/');
+    if relative.is_empty() {
+        normalize_path(dest_root).to_string()
+    } else {
+        join_path(dest_root, relative)
+    }
+}
+
+impl MigrationPlan {
+    /// Map an inventory to the v4 requests that recreate it
+    pub fn new(inventory: &V3Inventory, options: &MigrationOptions) -> Self {
+        let mut plan = MigrationPlan::default();
+
+        if options.shares {
+            for item in &inventory.shares {
+                plan.add_share(inventory, item, &options.dest_root);
+            }
+        }
+
+        if options.dav_accounts {
+            for account in &inventory.dav_accounts {
+                plan.warnings.push((
+                    format!("WebDAV account {}", account.name),
+                    "v4 generates a new password; clients must be reconfigured".to_string(),
+                ));
+                plan.dav_accounts.push(PlannedDavAccount {
+                    old_id: account.id,
+                    request: v4_models::CreateDavAccountRequest {
+                        uri: remap_path(&account.uri, &options.dest_root),
+                        name: account.name.clone(),
+                        readonly: None,
+                        proxy: None,
+                        disable_sys_files: None,
+                    },
+                });
+            }
+        }
+
+        if options.settings && (inventory.nickname.is_some() || inventory.preferred_theme.is_some())
+        {
+            plan.settings = Some(PlannedSettings {
+                nickname: inventory.nickname.clone(),
+                preferred_theme: inventory.preferred_theme.clone(),
+            });
+        }
+
+        plan
+    }
+
+    fn add_share(&mut self, inventory: &V3Inventory, item: &InventoryShare, dest_root: &str) {
+        let share = &item.share;
+        let label = format!("share {}", share.key);
+
+        let Some(source_path) = &item.source_path else {
+            self.skipped
+                .push((label, "shared object could not be located".to_string()));
+            return;
+        };
+        if share.downloads > 0 && share.remain_downloads == 0 {
+            self.skipped
+                .push((label, "download limit already reached".to_string()));
+            return;
+        }
+        if v3_expired_by_time(share) {
+            self.skipped.push((label, "already expired".to_string()));
+            return;
+        }
+
+        if share.views > 0 {
+            self.warnings.push((
+                label,
+                format!("visit count of {} is not carried over", share.views),
+            ));
+        }
+
+        let password = (!share.password.is_empty()).then(|| share.password.clone());
+        self.shares.push(PlannedShare {
+            old_key: share.key.clone(),
+            old_url: format!(
+                "{}/s/{}",
+                inventory.base_url.trim_end_matches('/'),
+                share.key
+            ),
+            request: v4_models::CreateShareLinkRequest {
+                permissions: v4_models::PermissionSetting::read_only(),
+                uri: remap_path(source_path, dest_root),
+                is_private: Some(password.is_some()),
+                share_view: Some(share.preview),
+                expire: (share.expire > 0).then(|| u32::try_from(share.expire).unwrap_or(u32::MAX)),
+                price: None,
+                password,
+                show_readme: None,
+                downloads: (share.remain_downloads > 0)
+                    .then(|| u32::try_from(share.remain_downloads).unwrap_or(u32::MAX)),
+            },
+        });
+    }
+}
+
+/// Account migration methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Collect the shares, WebDAV accounts and settings of a v3 account
+    ///
+    /// v3 share listings only include the name of the shared object, so each
+    /// share's path is resolved with a file search. Shares whose object is
+    /// missing or whose name is ambiguous are kept with `source_path: None`.
+    pub async fn inventory_v3_account(&self) -> Result<V3Inventory, Error> {
+        debug!("Collecting v3 account inventory");
+
+        let client = match self.client()? {
+            UnifiedClient::V3(client) => client,
+            UnifiedClient::V4(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "v3 account inventory".to_string(),
+                    "v4".to_string(),
+                ));
+            }
+        };
+
+        let mut shares = Vec::new();
+        let mut page = 1;
+        loop {
+            let list = client.list_shares(page).await?;
+            if list.items.is_empty() {
+                break;
+            }
+            shares.extend(list.items);
+            if shares.len() as i64 >= list.total {
+                break;
+            }
+            page += 1;
+        }
+
+        let mut search_cache: HashMap<String, Vec<v3_models::Object>> = HashMap::new();
+        let mut inventory_shares = Vec::with_capacity(shares.len());
+        for share in shares {
+            let source_path = match &share.source {
+                Some(source) => {
+                    if !search_cache.contains_key(&source.name) {
+                        let found = match client.search_files(&source.name).await {
+                            Ok(list) => list.objects,
+                            Err(e) => {
+                                warn!("Search for shared object {} failed: {}", source.name, e);
+                                Vec::new()
+                            }
+                        };
+                        search_cache.insert(source.name.clone(), found);
+                    }
+                    resolve_share_path(&share, &search_cache[&source.name])
+                }
+                None => None,
+            };
+            inventory_shares.push(InventoryShare { share, source_path });
+        }
+
+        let dav_accounts = client.get_webdav_accounts().await?;
+        let preferred_theme = client
+            .get_user_settings()
+            .await
+            .ok()
+            .map(|s| s.prefer_theme)
+            .filter(|theme| !theme.is_empty());
+        let nickname = client
+            .get_site_config()
+            .await
+            .ok()
+            .and_then(|config| config.user)
+            .map(|user| user.nickname)
+            .filter(|nick| !nick.is_empty());
+
+        Ok(V3Inventory {
+            base_url: self.base_url.clone(),
+            shares: inventory_shares,
+            dav_accounts,
+            nickname,
+            preferred_theme,
+        })
+    }
+
+    /// Create the objects of a migration plan on this v4 server
+    ///
+    /// Failures on individual objects are collected in
+    /// [`MigrationReport::errors`] and the migration continues.
+    pub async fn execute_migration(&self, plan: &MigrationPlan) -> Result<MigrationReport, Error> {
+        debug!(
+            "Executing migration: {} shares, {} WebDAV accounts",
+            plan.shares.len(),
+            plan.dav_accounts.len()
+        );
+
+        let client = match self.client()? {
+            UnifiedClient::V4(client) => client,
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "account migration target".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+        };
+
+        let mut report = MigrationReport {
+            skipped: plan.skipped.clone(),
+            warnings: plan.warnings.clone(),
+            ..Default::default()
+        };
+
+        for planned in &plan.shares {
+            match client.create_share_link(&planned.request).await {
+                Ok(new_url) => report.shares.push(ShareMapping {
+                    old_key: planned.old_key.clone(),
+                    old_url: planned.old_url.clone(),
+                    new_url,
+                }),
+                Err(e) => report
+                    .errors
+                    .push((format!("share {}", planned.old_key), e.to_string())),
+            }
+        }
+
+        for planned in &plan.dav_accounts {
+            match client.create_dav_account(&planned.request).await {
+                Ok(account) => report.dav_accounts.push(DavAccountMapping {
+                    old_id: planned.old_id,
+                    name: account.name,
+                    new_id: account.id,
+                    new_password: account.password,
+                }),
+                Err(e) => report
+                    .errors
+                    .push((format!("webdav account {}", planned.old_id), e.to_string())),
+            }
+        }
+
+        if let Some(settings) = &plan.settings {
+            let patch = v4_models::UserSettingPatch {
+                nick: settings.nickname.as_deref(),
+                preferred_theme: settings.preferred_theme.as_deref(),
+            };
+            match client.patch_user_setting(&patch).await {
+                Ok(()) => report.settings_migrated = true,
+                Err(e) => report.errors.push(("settings".to_string(), e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Migrate shares, WebDAV accounts and settings from this v3 account to `dest`
+    ///
+    /// Shorthand for taking an inventory, planning and executing the
+    /// migration in one go. Files must be transferred beforehand, as shares
+    /// and WebDAV accounts point at paths that have to exist on `dest`.
+    pub async fn migrate_account_to(
+        &self,
+        dest: &CloudreveAPI,
+        options: &MigrationOptions,
+    ) -> Result<MigrationReport, Error> {
+        debug!(
+            "Migrating account from {} to {}",
+            self.base_url, dest.base_url
+        );
+
+        let inventory = self.inventory_v3_account().await?;
+        let plan = MigrationPlan::new(&inventory, options);
+        dest.execute_migration(&plan).await
+    }
+}
+
+/// Pick the path of a shared object from search results
+///
+/// Only an unambiguous match is accepted.
+fn resolve_share_path(share: &v3_models::Share, found: &[v3_models::Object]) -> Option<String> {
+    let source = share.source.as_ref()?;
+    let mut candidates = found.iter().filter(|obj| {
+        obj.name == source.name
+            && (obj.object_type == "dir") == share.is_dir
+            && (share.is_dir || obj.size == source.size)
+    });
+    let first = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+    Some(join_path(&first.path, &first.name))
+}
@@ -4,42 +4,115 @@
 //! It automatically handles version detection, authentication, and request routing.
 //!
 //! The module is organized into submodules:
+//! - `archive`: Server-side archive creation, extraction and listing
 //! - `auth`: Authentication and token management
+//! - `backend`: Backend trait with the v3 and v4 implementations
+//! - `batch`: Batch move, copy, delete and rename within server limits
+//! - `capabilities`: Server capability discovery
+//! - `direct_link`: Permanent direct links to files
+//! - `entry`: Typed, version-agnostic file entries
 //! - `file`: File operations (list, create, delete, rename, move, copy)
+//! - `listing_cache`: Optional cache for listings and file info
+//! - `media`: Typed EXIF, audio tag and video stream metadata
+//! - `metadata`: Typed file metadata, tags and custom properties
+//! - `migration`: Share, WebDAV and settings migration from v3 to v4
+//! - `move_copy`: Copy and move with explicit destinations and rollback
+//! - `path`: Path helpers shared by the unified operations
+//! - `path_cache`: Path-to-ID cache for the v3 backend
+//! - `permission`: File and folder permission settings
+//! - `relocate`: Storage policy relocation with verification
+//! - `remote_download`: Remote (offline) downloads through aria2 or v4 workflows
 //! - `share`: Share link operations
+//! - `share_session`: Anonymous browsing, downloading and importing of share links
+//! - `task`: Tracking of background workflow tasks
+//! - `text`: Text file reading and editing with a version guard
+//! - `thumbnail`: Thumbnail and preview bytes with an optional local cache
+//! - `transfer`: Tree transfer between two Cloudreve instances
+//! - `viewer`: Viewer and WOPI sessions from the site's file viewers
 //! - `download`: Download URL operations
 //! - `dav`: WebDAV account operations
 
 use crate::Error;
 use crate::api::ApiVersion;
 use crate::client::UnifiedClient;
+use backend::{BackendRef, V3Backend, V4Backend};
+use listing_cache::ListingCache;
 use log::debug;
+use path_cache::V3PathCache;
+use std::sync::Arc;
 
 // Re-export submodule types for convenience
 pub use auth::{LoginResponse, TokenInfo, V3LoginResponse, V4LoginResponse};
+pub use backend::{BackendFuture, CloudreveBackend};
+pub use batch::BatchResult;
+pub use capabilities::{Capabilities, Feature};
 pub use dav::{DavAccount, DavListResponse};
+pub use direct_link::DirectLinkItem;
+pub use entry::{Entry, EntryKind, RawEntry};
 pub use file::{DeleteResult, DeleteTarget, FileInfo, FileItem, FileList, FileListAll};
-pub use share::{ShareItem, ShareUpdateProps};
+pub use listing_cache::{ListingCacheConfig, ListingCacheStats};
+pub use media::{AudioTags, GpsLocation, ImageMetadata, MediaMetadata, VideoInfo};
+pub use metadata::{FileMetadata, MetadataChange, MetadataKey};
+pub use migration::{
+    DavAccountMapping, MigrationOptions, MigrationPlan, MigrationReport, ShareMapping, V3Inventory,
+};
+pub use move_copy::Destination;
+pub use relocate::{RelocationPlan, RelocationReport};
+pub use remote_download::{RemoteDownload, RemoteDownloadFile, RemoteDownloadState};
+pub use share::{ShareItem, ShareOptions, SharePage, ShareUpdateProps};
+pub use share_session::{ImportReport, ShareSession};
 pub use site::SiteConfigValue;
+pub use task::{TaskHandle, TaskOutcome, WaitOptions};
+pub use text::{TextFile, WriteTextOptions};
+pub use thumbnail::{MediaContent, ThumbnailOptions};
+pub use transfer::{TransferCheckpoint, TransferOptions, TransferReport};
 pub use user::{StorageQuota, UserInfo};
+pub use viewer::{ViewerAction, ViewerKind, ViewerLaunch};
 
 // Submodules
+pub mod archive;
 pub mod auth;
+pub mod backend;
+pub mod batch;
+pub mod capabilities;
 pub mod dav;
+pub mod direct_link;
 pub mod download;
+pub mod entry;
 pub mod file;
+pub mod listing_cache;
+pub mod media;
+pub mod metadata;
+pub mod migration;
+pub mod move_copy;
+pub mod path;
+pub mod path_cache;
+pub mod permission;
+pub mod relocate;
+pub mod remote_download;
 pub mod share;
+pub mod share_session;
 pub mod site;
+pub mod task;
+pub mod text;
+pub mod thumbnail;
+pub mod transfer;
 pub mod user;
+pub mod viewer;
 
 /// Unified Cloudreve API client
 ///
 /// This client automatically detects the API version (v3 or v4) and routes
 /// all requests to the appropriate endpoints. It handles authentication
 /// differences transparently.
+///
+/// A client can also be built on a custom [`CloudreveBackend`] with
+/// [`with_backend`](Self::with_backend).
 pub struct CloudreveAPI {
-    inner: UnifiedClient,
+    inner: Transport,
     base_url: String,
+    path_cache: Arc<V3PathCache>,
+    listing_cache: Arc<ListingCache>,
 }
 
 impl CloudreveAPI {
@@ -54,7 +127,12 @@ impl CloudreveAPI {
         let inner = UnifiedClient::new(&base_url).await?;
         debug!("API version detected: {:?}", inner.api_version());
 
-        Ok(Self { inner, base_url })
+        Ok(Self {
+            inner: Transport::Client(inner),
+            base_url,
+            path_cache: Arc::new(V3PathCache::new()),
+            listing_cache: Arc::new(ListingCache::default()),
+        })
     }
 
     /// Create a new API client with a specific version
@@ -74,12 +152,49 @@ impl CloudreveAPI {
             ApiVersion::V4 => UnifiedClient::V4(crate::api::v4::ApiV4Client::new(&base_url)),
         };
 
-        Ok(Self { inner, base_url })
+        Ok(Self {
+            inner: Transport::Client(inner),
+            base_url,
+            path_cache: Arc::new(V3PathCache::new()),
+            listing_cache: Arc::new(ListingCache::default()),
+        })
+    }
+
+    /// Create an API client on top of a custom backend
+    ///
+    /// File, directory and user operations are routed to the backend, with
+    /// the listing and path caches applied as usual. Operations that need a
+    /// built-in v3 or v4 client, such as login, shares or WebDAV accounts,
+    /// return [`Error::UnsupportedFeature`].
+    pub fn with_backend(backend: Arc<dyn CloudreveBackend>) -> Self {
+        let base_url = backend.base_url().trim_end_matches('/').to_string();
+        debug!(
+            "Creating CloudreveAPI for {} with a custom {:?} backend",
+            base_url,
+            backend.api_version()
+        );
+
+        Self {
+            inner: Transport::Custom(backend),
+            base_url,
+            path_cache: Arc::new(V3PathCache::new()),
+            listing_cache: Arc::new(ListingCache::default()),
+        }
     }
 
     /// Get the detected API version
+    ///
+    /// For a custom backend this is the version whose models it returns.
     pub fn api_version(&self) -> ApiVersion {
-        self.inner.api_version()
+        match &self.inner {
+            Transport::Client(client) => client.api_version(),
+            Transport::Custom(backend) => backend.api_version(),
+        }
+    }
+
+    /// Check whether this client runs on a custom backend
+    pub fn has_custom_backend(&self) -> bool {
+        matches!(self.inner, Transport::Custom(_))
     }
 
     /// Get the base URL
@@ -91,33 +206,108 @@ impl CloudreveAPI {
     ///
     /// This is a temporary method for advanced use cases where direct V3/V4 client access is needed.
     /// In the future, all operations should be available through CloudreveAPI methods.
+    ///
+    /// # Panics
+    ///
+    /// Panics for a client built on a custom backend; use [`Self::try_inner`]
+    /// if that is possible.
     pub fn inner(&self) -> &UnifiedClient {
-        &self.inner
+        self.try_inner()
+            .expect("CloudreveAPI::inner called on a custom backend")
     }
 
     /// Get mutable access to the underlying UnifiedClient
     ///
     /// This is a temporary method for advanced use cases where direct V3/V4 client access is needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics for a client built on a custom backend; use
+    /// [`Self::try_inner_mut`] if that is possible.
     pub fn inner_mut(&mut self) -> &mut UnifiedClient {
-        &mut self.inner
+        self.try_inner_mut()
+            .expect("CloudreveAPI::inner_mut called on a custom backend")
+    }
+
+    /// Get access to the underlying UnifiedClient, if there is one
+    ///
+    /// Returns `None` for a client built on a custom backend.
+    pub fn try_inner(&self) -> Option<&UnifiedClient> {
+        match &self.inner {
+            Transport::Client(client) => Some(client),
+            Transport::Custom(_) => None,
+        }
+    }
+
+    /// Get mutable access to the underlying UnifiedClient, if there is one
+    ///
+    /// Returns `None` for a client built on a custom backend.
+    pub fn try_inner_mut(&mut self) -> Option<&mut UnifiedClient> {
+        match &mut self.inner {
+            Transport::Client(client) => Some(client),
+            Transport::Custom(_) => None,
+        }
     }
 
     /// Get the server version
     ///
     /// Returns the Cloudreve server version by pinging the /site/ping endpoint.
     pub async fn get_server_version(&self) -> Result<String, Error> {
+        self.backend().ping().await
+    }
+
+    /// Get the backend that file operations are routed to
+    pub(crate) fn backend(&self) -> BackendRef<'_> {
+        match &self.inner {
+            Transport::Client(UnifiedClient::V3(client)) => {
+                BackendRef::V3(V3Backend::new(client, &self.path_cache))
+            }
+            Transport::Client(UnifiedClient::V4(client)) => BackendRef::V4(V4Backend::new(client)),
+            Transport::Custom(backend) => BackendRef::Custom(backend.as_ref()),
+        }
+    }
+
+    /// Get the built-in client for operations outside of the backend trait
+    pub(crate) fn client(&self) -> Result<&UnifiedClient, Error> {
         match &self.inner {
-            UnifiedClient::V3(client) => client.ping().await,
-            UnifiedClient::V4(client) => client.ping().await,
+            Transport::Client(client) => Ok(client),
+            Transport::Custom(_) => Err(custom_backend_unsupported()),
+        }
+    }
+
+    /// Get the built-in client mutably, e.g. to store credentials
+    pub(crate) fn client_mut(&mut self) -> Result<&mut UnifiedClient, Error> {
+        match &mut self.inner {
+            Transport::Client(client) => Ok(client),
+            Transport::Custom(_) => Err(custom_backend_unsupported()),
         }
     }
 }
 
+/// What a CloudreveAPI sends its requests to
+#[derive(Clone)]
+enum Transport {
+    /// A built-in v3 or v4 client
+    Client(UnifiedClient),
+    /// A user-supplied backend
+    Custom(Arc<dyn CloudreveBackend>),
+}
+
+/// Error for operations that need a built-in v3 or v4 client
+fn custom_backend_unsupported() -> Error {
+    Error::UnsupportedFeature(
+        "operations outside of the backend trait".to_string(),
+        "custom backend".to_string(),
+    )
+}
+
 impl Clone for CloudreveAPI {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
             base_url: self.base_url.clone(),
+            path_cache: Arc::clone(&self.path_cache),
+            listing_cache: Arc::clone(&self.listing_cache),
         }
     }
 }
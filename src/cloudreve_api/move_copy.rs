@@ -0,0 +1,421 @@
+//! Copy and move placement for CloudreveAPI
+//!
+//! Cloudreve only copies and moves objects *into* a directory, keeping their
+//! names. Placing an object under a different name therefore takes several
+//! requests. The operations in this module record every intermediate step so
+//! that a failure halfway through can be undone instead of leaving staging
+//! folders or half-renamed objects behind.
+
+use crate::Error;
+use crate::cloudreve_api::file::{DeleteTarget, FileInfo};
+use crate::cloudreve_api::path::{join_path, normalize_path, split_path};
+use log::{debug, warn};
+
+/// Where a copied or moved object should end up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination<'a> {
+    /// Place the object inside this directory, keeping its current name
+    IntoDir(&'a str),
+    /// Place the object at exactly this path, renaming it if needed
+    Path(&'a str),
+}
+
+impl<'a> Destination<'a> {
+    /// Resolve the destination into a parent directory and a final name
+    pub fn resolve(&self, src_name: &'a str) -> (&'a str, &'a str) {
+        match *self {
+            Destination::IntoDir(dir) => (normalize_path(dir), src_name),
+            Destination::Path(path) => split_path(path),
+        }
+    }
+}
+
+/// A compensating action recorded while a multi-step operation runs
+#[derive(Debug)]
+enum Undo {
+    /// Delete an object created by the operation
+    Delete(String),
+    /// Rename the object at `path` back to `name`
+    Rename { path: String, name: String },
+    /// Move the object at `path` back into `dir`
+    MoveInto { path: String, dir: String },
+}
+
+/// Ordered record of completed steps that can be undone on failure
+#[derive(Debug, Default)]
+struct Journal {
+    steps: Vec<Undo>,
+}
+
+impl Journal {
+    fn record(&mut self, step: Undo) {
+        self.steps.push(step);
+    }
+
+    /// Undo all recorded steps in reverse order
+    ///
+    /// Rollback is best-effort: failures are logged and the remaining steps
+    /// are still attempted.
+    async fn rollback(self, api: &super::CloudreveAPI) {
+        for step in self.steps.into_iter().rev() {
+            debug!("Rolling back: {:?}", step);
+            let result = match &step {
+                Undo::Delete(path) => api.delete(DeleteTarget::Path(path.clone())).await,
+                Undo::Rename { path, name } => api.rename(path, name).await,
+                Undo::MoveInto { path, dir } => api.move_into_dir(path, dir).await,
+            };
+            if let Err(e) = result {
+                warn!("Rollback step {:?} failed: {}", step, e);
+            }
+        }
+    }
+}
+
+/// Build a name that is unlikely to collide with existing objects
+pub(super) fn staging_name(purpose: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!(".cloudreve-{}-{}-{}", purpose, std::process::id(), nanos)
+}
+
+/// Copy and move methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Copy a file or directory to an explicit destination
+    ///
+    /// Returns the path of the new copy. When `dest` names a different file
+    /// name than the source, the copy is renamed as part of the operation.
+    ///
+    /// An existing object at the destination is only replaced when
+    /// `overwrite` is `true`; otherwise [`Error::AlreadyExists`] is returned.
+    /// When replacing, the old object is parked under a temporary name and
+    /// only deleted once the copy is in place, so a failure restores it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cloudreve_api::{CloudreveAPI, Destination};
+    /// # async fn example(api: &CloudreveAPI) -> cloudreve_api::Result<()> {
+    /// // Duplicate a file next to the original
+    /// api.copy_to("/docs/report.pdf", Destination::Path("/docs/report-v2.pdf"), false)
+    ///     .await?;
+    /// // Copy into another folder, keeping the name
+    /// api.copy_to("/docs/report.pdf", Destination::IntoDir("/archive"), false)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy_to(
+        &self,
+        src: &str,
+        dest: Destination<'_>,
+        overwrite: bool,
+    ) -> Result<String, Error> {
+        let src = normalize_path(src);
+        let (src_dir, src_name) = split_path(src);
+        if src_name.is_empty() {
+            return Err(Error::InvalidResponse(
+                "Cannot copy root directory".to_string(),
+            ));
+        }
+
+        let (dest_dir, dest_name) = dest.resolve(src_name);
+        let dest_path = join_path(dest_dir, dest_name);
+        debug!("Copying {} to {}", src, dest_path);
+
+        if dest_path == src {
+            return Err(Error::AlreadyExists(dest_path));
+        }
+        self.check_target_dir(src, dest_dir).await?;
+
+        let mut journal = Journal::default();
+        let backup = match self.park_existing(&dest_path, overwrite).await? {
+            Some((backup_path, undo)) => {
+                journal.record(undo);
+                Some(backup_path)
+            }
+            None => None,
+        };
+
+        let result = if dest_name == src_name {
+            self.copy_into_dir(src, dest_dir).await
+        } else {
+            self.copy_renamed(src, src_dir, src_name, dest_dir, dest_name, &mut journal)
+                .await
+        };
+
+        self.finish_placement(result, journal, backup, dest_path)
+            .await
+    }
+
+    /// Copy `src` into `dest_dir` under `dest_name`
+    ///
+    /// If `dest_dir` has no object named like the source, the copy lands
+    /// there directly and is renamed in place. Otherwise the copy goes through
+    /// a private staging folder so it never clashes with existing objects.
+    async fn copy_renamed(
+        &self,
+        src: &str,
+        src_dir: &str,
+        src_name: &str,
+        dest_dir: &str,
+        dest_name: &str,
+        journal: &mut Journal,
+    ) -> Result<(), Error> {
+        let landing_path = join_path(dest_dir, src_name);
+        let direct = src_dir != dest_dir && self.lookup(&landing_path).await?.is_none();
+
+        if direct {
+            debug!("Copying into {} and renaming in place", dest_dir);
+            self.copy_into_dir(src, dest_dir).await?;
+            journal.record(Undo::Delete(landing_path.clone()));
+            return self.rename(&landing_path, dest_name).await;
+        }
+
+        let staging_dir = join_path(dest_dir, &staging_name("staging"));
+        debug!("Copying through staging folder {}", staging_dir);
+        self.create_directory(&staging_dir).await?;
+        journal.record(Undo::Delete(staging_dir.clone()));
+
+        self.copy_into_dir(src, &staging_dir).await?;
+        self.rename(&join_path(&staging_dir, src_name), dest_name)
+            .await?;
+        self.move_into_dir(&join_path(&staging_dir, dest_name), dest_dir)
+            .await?;
+
+        // The staging folder is empty now; failing to remove it is not fatal
+        if let Err(e) = self.delete(DeleteTarget::Path(staging_dir.clone())).await {
+            warn!("Failed to remove staging folder {}: {}", staging_dir, e);
+        }
+        Ok(())
+    }
+
+    /// Move a file or directory to an explicit destination
+    ///
+    /// Returns the new path of the object. The destination decides the
+    /// semantics, identically on V3 and V4:
+    /// - [`Destination::IntoDir`] moves the object into a directory, keeping
+    ///   its name.
+    /// - [`Destination::Path`] places it at exactly that path, renaming and
+    ///   moving as needed.
+    ///
+    /// The destination directory must exist. An existing object at the
+    /// destination is only replaced when `overwrite` is `true`; otherwise
+    /// [`Error::AlreadyExists`] is returned. If a step fails midway, the
+    /// completed steps are undone.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cloudreve_api::{CloudreveAPI, Destination};
+    /// # async fn example(api: &CloudreveAPI) -> cloudreve_api::Result<()> {
+    /// // Move and rename in one call
+    /// api.move_to("/a/old.txt", Destination::Path("/b/new.txt"), false)
+    ///     .await?;
+    /// // Move into a folder, keeping the name
+    /// api.move_to("/b/new.txt", Destination::IntoDir("/c"), false)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn move_to(
+        &self,
+        src: &str,
+        dest: Destination<'_>,
+        overwrite: bool,
+    ) -> Result<String, Error> {
+        let src = normalize_path(src);
+        let (src_dir, src_name) = split_path(src);
+        if src_name.is_empty() {
+            return Err(Error::InvalidResponse(
+                "Cannot move root directory".to_string(),
+            ));
+        }
+
+        let (dest_dir, dest_name) = dest.resolve(src_name);
+        let dest_path = join_path(dest_dir, dest_name);
+        debug!("Moving {} to {}", src, dest_path);
+
+        if dest_path == src {
+            return Ok(dest_path);
+        }
+        self.check_target_dir(src, dest_dir).await?;
+
+        let mut journal = Journal::default();
+        let backup = match self.park_existing(&dest_path, overwrite).await? {
+            Some((backup_path, undo)) => {
+                journal.record(undo);
+                Some(backup_path)
+            }
+            None => None,
+        };
+
+        let result = if src_dir == dest_dir {
+            self.rename(src, dest_name).await
+        } else if src_name == dest_name {
+            self.move_into_dir(src, dest_dir).await
+        } else {
+            self.move_renamed(src, src_dir, src_name, dest_dir, dest_name, &mut journal)
+                .await
+        };
+
+        self.finish_placement(result, journal, backup, dest_path)
+            .await
+    }
+
+    /// Move `src` into another directory under a new name
+    ///
+    /// If `dest_dir` has no object named like the source, the object is
+    /// moved first and renamed afterwards. Otherwise it is first given a
+    /// temporary name so the move cannot clash with existing objects.
+    async fn move_renamed(
+        &self,
+        src: &str,
+        src_dir: &str,
+        src_name: &str,
+        dest_dir: &str,
+        dest_name: &str,
+        journal: &mut Journal,
+    ) -> Result<(), Error> {
+        let landing_path = join_path(dest_dir, src_name);
+        if self.lookup(&landing_path).await?.is_none() {
+            self.move_into_dir(src, dest_dir).await?;
+            journal.record(Undo::MoveInto {
+                path: landing_path.clone(),
+                dir: src_dir.to_string(),
+            });
+            return self.rename(&landing_path, dest_name).await;
+        }
+
+        let temp_name = staging_name("moving");
+        debug!("Moving {} through temporary name {}", src, temp_name);
+        self.rename(src, &temp_name).await?;
+        journal.record(Undo::Rename {
+            path: join_path(src_dir, &temp_name),
+            name: src_name.to_string(),
+        });
+
+        self.move_into_dir(&join_path(src_dir, &temp_name), dest_dir)
+            .await?;
+        let temp_path = join_path(dest_dir, &temp_name);
+        journal.record(Undo::MoveInto {
+            path: temp_path.clone(),
+            dir: src_dir.to_string(),
+        });
+
+        self.rename(&temp_path, dest_name).await
+    }
+
+    /// Check whether `path` is an existing directory
+    pub(super) async fn is_existing_dir(&self, path: &str) -> Result<bool, Error> {
+        if normalize_path(path) == "/" {
+            return Ok(true);
+        }
+        Ok(self
+            .lookup(path)
+            .await?
+            .is_some_and(|info| info.is_folder()))
+    }
+
+    /// Check that `dest_dir` is an existing directory outside of `src`
+    async fn check_target_dir(&self, src: &str, dest_dir: &str) -> Result<(), Error> {
+        if dest_dir == src || dest_dir.starts_with(&format!("{}/", src)) {
+            return Err(Error::InvalidResponse(format!(
+                "Cannot place '{}' inside itself ('{}')",
+                src, dest_dir
+            )));
+        }
+        if dest_dir == "/" {
+            return Ok(());
+        }
+
+        match self.lookup(dest_dir).await? {
+            Some(info) if info.is_folder() => Ok(()),
+            Some(_) => Err(Error::InvalidResponse(format!(
+                "Destination '{}' is not a directory",
+                dest_dir
+            ))),
+            None => Err(Error::InvalidResponse(format!(
+                "Destination directory '{}' does not exist",
+                dest_dir
+            ))),
+        }
+    }
+
+    /// Move an existing destination out of the way when overwriting
+    ///
+    /// Returns the temporary path and the step that restores it, or `None`
+    /// when nothing exists at `dest_path`.
+    async fn park_existing(
+        &self,
+        dest_path: &str,
+        overwrite: bool,
+    ) -> Result<Option<(String, Undo)>, Error> {
+        if self.lookup(dest_path).await?.is_none() {
+            return Ok(None);
+        }
+        if !overwrite {
+            return Err(Error::AlreadyExists(dest_path.to_string()));
+        }
+
+        let (dest_dir, dest_name) = split_path(dest_path);
+        let backup_name = staging_name("backup");
+        debug!("Parking existing {} as {}", dest_path, backup_name);
+        self.rename(dest_path, &backup_name).await?;
+
+        let backup_path = join_path(dest_dir, &backup_name);
+        let undo = Undo::Rename {
+            path: backup_path.clone(),
+            name: dest_name.to_string(),
+        };
+        Ok(Some((backup_path, undo)))
+    }
+
+    /// Commit or roll back a placement once its main steps have run
+    async fn finish_placement(
+        &self,
+        result: Result<(), Error>,
+        journal: Journal,
+        backup: Option<String>,
+        dest_path: String,
+    ) -> Result<String, Error> {
+        match result {
+            Ok(()) => {
+                if let Some(backup_path) = backup
+                    && let Err(e) = self.delete(DeleteTarget::Path(backup_path.clone())).await
+                {
+                    warn!("Failed to remove replaced object {}: {}", backup_path, e);
+                }
+                Ok(dest_path)
+            }
+            Err(e) => {
+                debug!("Placement of {} failed, rolling back: {}", dest_path, e);
+                journal.rollback(self).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Look up an object by path, returning `None` if it does not exist
+    pub(super) async fn lookup(&self, path: &str) -> Result<Option<FileInfo>, Error> {
+        self.backend().lookup(path).await
+    }
+
+    /// Copy an object into a directory, keeping its name
+    pub(super) async fn copy_into_dir(&self, src: &str, dir: &str) -> Result<(), Error> {
+        debug!("Copying {} into {}", src, dir);
+
+        self.backend().copy_into_dir(src, dir).await?;
+        self.invalidate_cached(&join_path(dir, split_path(src).1));
+        Ok(())
+    }
+
+    /// Move an object into a directory, keeping its name
+    pub(super) async fn move_into_dir(&self, src: &str, dir: &str) -> Result<(), Error> {
+        debug!("Moving {} into {}", src, dir);
+
+        self.backend().move_into_dir(src, dir).await?;
+        self.invalidate_cached(src);
+        self.invalidate_cached(&join_path(dir, split_path(src).1));
+        Ok(())
+    }
+}
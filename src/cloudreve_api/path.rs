@@ -0,0 +1,91 @@
+//! Path helpers shared by the unified CloudreveAPI operations
+//!
+//! Paths handled here are plain slash-separated paths such as `/folder/file.txt`.
+//! Cloudreve v4 URIs should be converted with [`crate::api::v4::uri`] instead.
+
+/// Removes a trailing slash from a path, keeping the root as `/`
+///
+/// # Examples
+/// ```
+/// use cloudreve_api::cloudreve_api::path::normalize_path;
+///
+/// assert_eq!(normalize_path("/folder/"), "/folder");
+/// assert_eq!(normalize_path("/"), "/");
+/// ```
+pub fn normalize_path(path: &str) -> &str {
+    if path.ends_with('/') && path != "/" {
+        &path[..path.len() - 1]
+    } else {
+        path
+    }
+}
+
+/// Splits a path into its parent directory and final component
+///
+/// The root directory splits into `("/", "")`.
+///
+/// # Examples
+/// ```
+/// use cloudreve_api::cloudreve_api::path::split_path;
+///
+/// assert_eq!(split_path("/folder/file.txt"), ("/folder", "file.txt"));
+/// assert_eq!(split_path("/file.txt"), ("/", "file.txt"));
+/// ```
+pub fn split_path(path: &str) -> (&str, &str) {
+    let normalized = normalize_path(path);
+    if normalized == "/" {
+        return ("/", "");
+    }
+
+    match normalized.rfind('/') {
+        Some(0) => ("/", &normalized[1..]),
+        Some(pos) => (&normalized[..pos], &normalized[pos + 1..]),
+        None => ("/", normalized),
+    }
+}
+
+/// Joins a directory path and a name into a single path
+///
+/// # Examples
+/// ```
+/// use cloudreve_api::cloudreve_api::path::join_path;
+///
+/// assert_eq!(join_path("/folder", "file.txt"), "/folder/file.txt");
+/// assert_eq!(join_path("/", "file.txt"), "/file.txt");
+/// ```
+pub fn join_path(dir: &str, name: &str) -> String {
+    let dir = normalize_path(dir).trim_end_matches('/');
+    if dir.is_empty() {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/a/b/"), "/a/b");
+        assert_eq!(normalize_path("/a/b"), "/a/b");
+        assert_eq!(normalize_path("/"), "/");
+    }
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(split_path("/a/b/c.txt"), ("/a/b", "c.txt"));
+        assert_eq!(split_path("/a/b/"), ("/a", "b"));
+        assert_eq!(split_path("/c.txt"), ("/", "c.txt"));
+        assert_eq!(split_path("c.txt"), ("/", "c.txt"));
+        assert_eq!(split_path("/"), ("/", ""));
+    }
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("/a", "b"), "/a/b");
+        assert_eq!(join_path("/a/", "b"), "/a/b");
+        assert_eq!(join_path("/", "b"), "/b");
+    }
+}
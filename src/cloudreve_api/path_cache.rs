@@ -0,0 +1,263 @@
+//! Path resolution cache for the v3 backend
+//!
+//! The v3 API addresses objects by ID, so every path-based operation first
+//! lists the parent directory to find the object's ID and type. This cache
+//! remembers the entries of each listing for a short time so that a run of
+//! operations in the same directory only lists it once.
+//!
+//! Entries expire after a TTL and are invalidated whenever the crate itself
+//! creates, renames, moves, copies, deletes or uploads something at or below
+//! their path. Changes made by other clients are only picked up once the
+//! entry expires.
+
+use crate::Error;
+use crate::api::v3::ApiV3Client;
+use crate::api::v3::models as v3_models;
+use crate::cloudreve_api::path::{join_path, normalize_path, split_path};
+use log::debug;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default time after which cached entries are resolved again
+pub const DEFAULT_PATH_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Cache of v3 objects keyed by their full path
+///
+/// Only the object's listing entry is stored; its ID and type are what the
+/// v3 operations need.
+#[derive(Debug)]
+pub(crate) struct V3PathCache {
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    enabled: bool,
+    ttl: Duration,
+    entries: HashMap<String, (v3_models::Object, Instant)>,
+}
+
+impl V3PathCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                enabled: true,
+                ttl: DEFAULT_PATH_CACHE_TTL,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        let mut state = self.state();
+        state.enabled = enabled;
+        if !enabled {
+            state.entries.clear();
+        }
+    }
+
+    pub(crate) fn set_ttl(&self, ttl: Duration) {
+        self.state().ttl = ttl;
+    }
+
+    pub(crate) fn clear(&self) {
+        self.state().entries.clear();
+    }
+
+    /// Get a fresh entry for a path
+    fn get(&self, path: &str) -> Option<v3_models::Object> {
+        let mut state = self.state();
+        if !state.enabled {
+            return None;
+        }
+        let ttl = state.ttl;
+        match state.entries.get(path) {
+            Some((obj, stored)) if stored.elapsed() < ttl => Some(obj.clone()),
+            Some(_) => {
+                state.entries.remove(path);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Remember every object of a directory listing
+    ///
+    /// Expired entries of other paths are dropped at the same time, so the
+    /// cache stays bounded by what was listed within one TTL.
+    pub(crate) fn insert_listing(&self, dir: &str, objects: &[v3_models::Object]) {
+        let mut state = self.state();
+        if !state.enabled {
+            return;
+        }
+        let ttl = state.ttl;
+        state
+            .entries
+            .retain(|_, (_, stored)| stored.elapsed() < ttl);
+        let now = Instant::now();
+        for obj in objects {
+            state
+                .entries
+                .insert(join_path(dir, &obj.name), (obj.clone(), now));
+        }
+    }
+
+    /// Drop the entry for a path and everything below it
+    pub(crate) fn invalidate(&self, path: &str) {
+        let path = normalize_path(path);
+        let mut state = self.state();
+        if path == "/" {
+            state.entries.clear();
+            return;
+        }
+        let prefix = format!("{}/", path);
+        state
+            .entries
+            .retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+
+    /// Find a v3 object by path, listing its parent only on a cache miss
+    ///
+    /// Returns `None` if the parent directory has no object with that name.
+    pub(crate) async fn lookup(
+        &self,
+        client: &ApiV3Client,
+        path: &str,
+    ) -> Result<Option<v3_models::Object>, Error> {
+        let path = normalize_path(path);
+        let (parent, name) = split_path(path);
+        if name.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(obj) = self.get(path) {
+            debug!("V3 path cache hit: {}", path);
+            return Ok(Some(obj));
+        }
+
+        let dir_list = client.list_directory(parent).await?;
+        self.insert_listing(parent, &dir_list.objects);
+        Ok(dir_list.objects.into_iter().find(|obj| obj.name == name))
+    }
+
+    /// Find a v3 object by path, failing if it does not exist
+    pub(crate) async fn find(
+        &self,
+        client: &ApiV3Client,
+        path: &str,
+    ) -> Result<v3_models::Object, Error> {
+        self.lookup(client, path)
+            .await?
+            .ok_or_else(|| Error::InvalidResponse(format!("File not found: {}", path)))
+    }
+}
+
+/// Path cache methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Enable or disable the v3 path resolution cache
+    ///
+    /// The cache is enabled by default. Disabling it also drops all entries.
+    pub fn set_path_cache_enabled(&self, enabled: bool) {
+        debug!("Setting v3 path cache enabled: {}", enabled);
+        self.path_cache.set_enabled(enabled);
+    }
+
+    /// Set how long resolved v3 paths are reused
+    pub fn set_path_cache_ttl(&self, ttl: Duration) {
+        debug!("Setting v3 path cache TTL: {:?}", ttl);
+        self.path_cache.set_ttl(ttl);
+    }
+
+    /// Drop all resolved v3 paths
+    pub fn clear_path_cache(&self) {
+        self.path_cache.clear();
+    }
+
+    /// Find a v3 object by path, using the cache when possible
+    ///
+    /// Returns `None` if the parent directory has no object with that name.
+    pub(super) async fn v3_lookup(
+        &self,
+        client: &ApiV3Client,
+        path: &str,
+    ) -> Result<Option<v3_models::Object>, Error> {
+        self.path_cache.lookup(client, path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(name: &str, object_type: &str) -> v3_models::Object {
+        v3_models::Object {
+            id: format!("id-{}", name),
+            name: name.to_string(),
+            path: String::new(),
+            thumb: false,
+            size: 0,
+            object_type: object_type.to_string(),
+            date: String::new(),
+            create_date: String::new(),
+            source_enabled: false,
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let cache = V3PathCache::new();
+        cache.insert_listing("/docs", &[object("a.txt", "file"), object("sub", "dir")]);
+
+        assert_eq!(cache.get("/docs/a.txt").unwrap().id, "id-a.txt");
+        assert_eq!(cache.get("/docs/sub").unwrap().object_type, "dir");
+        assert!(cache.get("/docs/missing").is_none());
+    }
+
+    #[test]
+    fn test_invalidate_removes_descendants() {
+        let cache = V3PathCache::new();
+        cache.insert_listing("/docs", &[object("sub", "dir"), object("subway", "file")]);
+        cache.insert_listing("/docs/sub", &[object("b.txt", "file")]);
+
+        cache.invalidate("/docs/sub");
+
+        assert!(cache.get("/docs/sub").is_none());
+        assert!(cache.get("/docs/sub/b.txt").is_none());
+        assert!(cache.get("/docs/subway").is_some());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = V3PathCache::new();
+        cache.set_ttl(Duration::ZERO);
+        cache.insert_listing("/", &[object("a.txt", "file")]);
+
+        assert!(cache.get("/a.txt").is_none());
+    }
+
+    #[test]
+    fn test_insert_drops_expired_entries() {
+        let cache = V3PathCache::new();
+        cache.set_ttl(Duration::ZERO);
+        cache.insert_listing("/old", &[object("a.txt", "file")]);
+        cache.insert_listing("/new", &[object("b.txt", "file")]);
+
+        let state = cache.state();
+        assert!(!state.entries.contains_key("/old/a.txt"));
+        assert!(state.entries.contains_key("/new/b.txt"));
+    }
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let cache = V3PathCache::new();
+        cache.set_enabled(false);
+        cache.insert_listing("/", &[object("a.txt", "file")]);
+
+        assert!(cache.get("/a.txt").is_none());
+    }
+}
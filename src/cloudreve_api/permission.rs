@@ -0,0 +1,100 @@
+//! File permission operations for CloudreveAPI
+//!
+//! v4 files and folders can carry a [`PermissionSetting`] that grants
+//! [`PermissionSet`]s to single users, groups and classes of users. Folders
+//! without settings inherit them from their parent.
+
+use crate::Error;
+use crate::api::v4::models::{
+    GetFileInfoRequest, PermissionSet, PermissionSetting, SetFilePermissionRequest,
+};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use log::debug;
+
+impl super::CloudreveAPI {
+    /// Get the permission settings of a file or folder
+    ///
+    /// `None` if the file has no settings of its own.
+    pub async fn get_permissions(&self, path: &str) -> Result<Option<PermissionSetting>, Error> {
+        debug!("Getting permissions of: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "file permissions".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => {
+                let request = GetFileInfoRequest {
+                    uri: path,
+                    include_extended_info: Some(true),
+                };
+                let file = client.get_file_info_extended(&request).await?;
+                Ok(file.extended_info.and_then(|info| info.permissions))
+            }
+        }
+    }
+
+    /// Get the permissions the signed-in user has on a file or folder
+    pub async fn my_permissions(&self, path: &str) -> Result<Option<PermissionSet>, Error> {
+        debug!("Getting own permissions on: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "file permissions".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => {
+                let request = GetFileInfoRequest {
+                    uri: path,
+                    include_extended_info: None,
+                };
+                Ok(client.get_file_info_extended(&request).await?.permissions())
+            }
+        }
+    }
+
+    /// Replace the permission settings of a file or folder
+    pub async fn set_permissions(
+        &self,
+        path: &str,
+        permissions: &PermissionSetting,
+    ) -> Result<(), Error> {
+        debug!("Setting permissions of: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "file permissions".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => {
+                let uri = path_to_uri(path);
+                let request = SetFilePermissionRequest {
+                    uri: &uri,
+                    permissions,
+                };
+                client.set_file_permission(&request).await?;
+                self.invalidate_cached(path);
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the permission settings of a file or folder so that it
+    /// inherits them again
+    pub async fn clear_permissions(&self, path: &str) -> Result<(), Error> {
+        debug!("Clearing permissions of: {}", path);
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "file permissions".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => {
+                client.delete_file_permission(path).await?;
+                self.invalidate_cached(path);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Storage policy relocation for CloudreveAPI
+//!
+//! v4 servers can move the stored data of files to another storage policy
+//! with a relocate workflow task. [`CloudreveAPI::plan_relocation`] checks
+//! the target policy and estimates the work, [`CloudreveAPI::relocate`] runs
+//! the task while reporting progress and then confirms through each file's
+//! extended info that it landed on the target policy.
+
+use crate::Error;
+use crate::api::v4::models::{GetFileInfoRequest, Progress, RelocateRequest, StoragePolicy};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::path::normalize_path;
+use crate::cloudreve_api::task::{TaskOutcome, WaitOptions};
+use futures::StreamExt;
+use log::debug;
+use std::time::{Duration, Instant};
+
+/// Interval between progress updates while a relocation runs
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Files selected for a relocation and the data they hold
+#[derive(Debug, Clone)]
+pub struct RelocationPlan {
+    /// Target storage policy
+    pub policy: StoragePolicy,
+    /// Paths as given, sent to the server as the relocation sources
+    pub sources: Vec<String>,
+    /// Every file below the sources
+    pub files: Vec<String>,
+    /// Estimated number of bytes to move
+    pub total_bytes: u64,
+}
+
+/// Result of a relocation
+#[derive(Debug, Clone)]
+pub struct RelocationReport {
+    pub outcome: TaskOutcome,
+    pub files: usize,
+    pub total_bytes: u64,
+    /// Files whose storage policy is not the target after the task,
+    /// with the policy ID they are on
+    pub misplaced: Vec<(String, Option<String>)>,
+    /// Files whose storage policy could not be checked
+    pub errors: Vec<(String, String)>,
+}
+
+impl RelocationReport {
+    /// Check whether the task completed and every file is on the target policy
+    pub fn is_verified(&self) -> bool {
+        self.outcome.is_completed() && self.misplaced.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Find a storage policy by ID, including child policies
+fn find_policy<'a>(policies: &'a [StoragePolicy], id: &str) -> Option<&'a StoragePolicy> {
+    policies.iter().find_map(|policy| {
+        if policy.id == id {
+            Some(policy)
+        } else {
+            find_policy(policy.children.as_deref().unwrap_or_default(), id)
+        }
+    })
+}
+
+impl super::CloudreveAPI {
+    /// Check the target policy and collect the files to relocate
+    ///
+    /// Fails if `policy_id` is not one of the user's storage policies.
+    /// Folders are walked to estimate the bytes to move.
+    pub async fn plan_relocation(
+        &self,
+        paths: &[&str],
+        policy_id: &str,
+    ) -> Result<RelocationPlan, Error> {
+        debug!("Planning relocation of {:?} to policy {}", paths, policy_id);
+
+        let client = match self.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "storage policy relocation".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        let policies = client.get_storage_policies().await?;
+        let policy = find_policy(&policies, policy_id).cloned().ok_or_else(|| {
+            Error::InvalidResponse(format!("Storage policy not available: {}", policy_id))
+        })?;
+
+        let mut plan = RelocationPlan {
+            policy,
+            sources: paths
+                .iter()
+                .map(|p| normalize_path(p).to_string())
+                .collect(),
+            files: Vec::new(),
+            total_bytes: 0,
+        };
+        let mut pending = Vec::new();
+        for source in &plan.sources {
+            let entry = self.get_file_info(source).await?.to_entry()?;
+            if entry.is_folder() {
+                pending.push(entry.path);
+            } else {
+                plan.total_bytes += entry.size;
+                plan.files.push(entry.path);
+            }
+        }
+        while let Some(dir) = pending.pop() {
+            for entry in self.list_files_all(&dir, None).await?.entries() {
+                if entry.is_folder() {
+                    pending.push(entry.path);
+                } else {
+                    plan.total_bytes += entry.size;
+                    plan.files.push(entry.path);
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Move files to another storage policy and verify the result
+    ///
+    /// `on_progress` is called with the task progress while it runs, and
+    /// the task is waited for as set in `options`; its timeout covers the
+    /// whole run. Once the task completed, every planned file is checked
+    /// through its extended info; files on another policy end up in
+    /// [`RelocationReport::misplaced`].
+    pub async fn relocate(
+        &self,
+        plan: &RelocationPlan,
+        options: &WaitOptions,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Result<RelocationReport, Error> {
+        debug!(
+            "Relocating {} files ({} bytes) to policy {}",
+            plan.files.len(),
+            plan.total_bytes,
+            plan.policy.id
+        );
+
+        let client = match self.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "storage policy relocation".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        let src: Vec<String> = plan.sources.iter().map(|p| path_to_uri(p)).collect();
+        let request = RelocateRequest {
+            src: src.iter().map(String::as_str).collect(),
+            dst_policy_id: &plan.policy.id,
+        };
+        let task = client.relocate(&request).await?;
+
+        let started = Instant::now();
+        let handle = self.track(&task);
+        let follow = async {
+            let progress = handle.progress(PROGRESS_INTERVAL);
+            futures::pin_mut!(progress);
+            while let Some(update) = progress.next().await {
+                match update {
+                    Ok(progress) => on_progress(&progress),
+                    Err(e) => debug!("Progress of task {} unavailable: {}", handle.id(), e),
+                }
+            }
+        };
+        match options.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, follow).await.map_err(|_| {
+                Error::Timeout(format!(
+                    "task {} still running after {:?}",
+                    handle.id(),
+                    timeout
+                ))
+            })?,
+            None => follow.await,
+        }
+        let remaining = WaitOptions {
+            timeout: options
+                .timeout
+                .map(|timeout| timeout.saturating_sub(started.elapsed())),
+            ..options.clone()
+        };
+        let outcome = handle.wait(&remaining).await?;
+
+        let mut report = RelocationReport {
+            outcome,
+            files: plan.files.len(),
+            total_bytes: plan.total_bytes,
+            misplaced: Vec::new(),
+            errors: Vec::new(),
+        };
+        if !report.outcome.is_completed() {
+            return Ok(report);
+        }
+
+        for path in &plan.files {
+            let request = GetFileInfoRequest {
+                uri: path,
+                include_extended_info: Some(true),
+            };
+            match client.get_file_info_extended(&request).await {
+                Ok(file) => {
+                    let actual = file
+                        .extended_info
+                        .and_then(|info| info.storage_policy)
+                        .map(|policy| policy.id);
+                    if actual.as_deref() != Some(plan.policy.id.as_str()) {
+                        report.misplaced.push((path.clone(), actual));
+                    }
+                }
+                Err(e) => report.errors.push((path.clone(), e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+}
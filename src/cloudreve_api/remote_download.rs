@@ -0,0 +1,432 @@
+//! Remote (offline) download operations for CloudreveAPI
+//!
+//! The server fetches URLs, magnet links and torrents into the user's
+//! storage. v3 servers run these through aria2 at `/aria2`, v4 servers as
+//! download workflow tasks. Both are presented as [`RemoteDownload`]s with a
+//! common [`RemoteDownloadState`].
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::{path_to_uri, uri_to_path};
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::path::normalize_path;
+use log::debug;
+use serde::Deserialize;
+
+/// Page size used when listing v4 download tasks
+const DOWNLOAD_PAGE_SIZE: i32 = 50;
+
+/// State of a remote download, normalized across API versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemoteDownloadState {
+    Queued,
+    Downloading,
+    Paused,
+    /// Download finished and the torrent is being seeded
+    Seeding,
+    /// Download finished and the files are being moved into storage
+    Transferring,
+    Completed,
+    Error,
+    Canceled,
+    Unknown,
+}
+
+impl RemoteDownloadState {
+    /// Check whether the download has stopped for good
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Error | Self::Canceled)
+    }
+
+    /// Map a v3 aria2 status code
+    fn from_v3(status: i32) -> Self {
+        match status {
+            0 => Self::Queued,
+            1 => Self::Downloading,
+            2 => Self::Paused,
+            3 => Self::Error,
+            4 => Self::Transferring,
+            5 => Self::Canceled,
+            7 => Self::Seeding,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Map a finished v3 download, whose transfer into storage may still run
+    fn from_v3_finished(status: i32, task_status: i32) -> Self {
+        match (status, task_status) {
+            (4, 0 | 1) => Self::Transferring,
+            (4, 2) => Self::Error,
+            (4, 3) => Self::Canceled,
+            (4, 4) => Self::Completed,
+            (status, _) => Self::from_v3(status),
+        }
+    }
+
+    /// Map a v4 task status, using the phase and download state while it runs
+    fn from_v4(status: &str, phase: Option<&str>, download_state: Option<&str>) -> Self {
+        match status {
+            "queued" => Self::Queued,
+            "processing" | "suspending" => match (phase, download_state) {
+                (Some("transfer"), _) => Self::Transferring,
+                (Some("seeding"), _) | (_, Some("seeding")) => Self::Seeding,
+                (_, Some("paused")) => Self::Paused,
+                _ => Self::Downloading,
+            },
+            "completed" => Self::Completed,
+            "error" => Self::Error,
+            "canceled" => Self::Canceled,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A file inside a remote download, e.g. one file of a torrent
+#[derive(Debug, Clone)]
+pub struct RemoteDownloadFile {
+    /// Index to pass to [`select_download_files`](super::CloudreveAPI::select_download_files)
+    pub index: u32,
+    pub path: String,
+    pub size: u64,
+    pub downloaded: u64,
+    pub selected: bool,
+}
+
+/// A remote download task
+#[derive(Debug, Clone)]
+pub struct RemoteDownload {
+    /// aria2 GID on v3, task ID on v4
+    pub id: String,
+    pub name: String,
+    pub state: RemoteDownloadState,
+    /// Folder the files are saved to
+    pub destination: String,
+    /// Total size in bytes; 0 until known
+    pub total: u64,
+    pub downloaded: u64,
+    /// Download speed in bytes per second
+    pub speed: u64,
+    pub error: Option<String>,
+    pub files: Vec<RemoteDownloadFile>,
+    pub created_at: Option<String>,
+}
+
+/// Download details in the summary props of a v4 download task
+#[derive(Debug, Default, Deserialize)]
+struct V4DownloadProps {
+    #[serde(default)]
+    src_str: String,
+    #[serde(default)]
+    dst: String,
+    #[serde(default)]
+    download: Option<V4DownloadStatus>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct V4DownloadStatus {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    total: u64,
+    #[serde(default)]
+    downloaded: u64,
+    #[serde(default)]
+    download_speed: u64,
+    #[serde(default)]
+    files: Vec<V4DownloadFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct V4DownloadFile {
+    #[serde(default)]
+    index: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    progress: f64,
+    #[serde(default)]
+    selected: bool,
+}
+
+/// Parse a number that aria2 reports as a string
+fn aria2_number(value: &str) -> u64 {
+    value.parse().unwrap_or(0)
+}
+
+fn files_from_v3(files: Vec<v3_models::Aria2File>) -> Vec<RemoteDownloadFile> {
+    files
+        .into_iter()
+        .map(|file| RemoteDownloadFile {
+            index: aria2_number(&file.index) as u32,
+            size: aria2_number(&file.length),
+            downloaded: aria2_number(&file.completed_length),
+            selected: file.selected == "true",
+            path: file.path,
+        })
+        .collect()
+}
+
+fn destination_from_v4(dst: &str) -> String {
+    uri_to_path(dst)
+        .map(str::to_string)
+        .unwrap_or_else(|_| dst.to_string())
+}
+
+impl RemoteDownload {
+    /// Build a download from an active v3 aria2 task
+    pub fn from_v3_downloading(task: v3_models::Aria2Downloading) -> Self {
+        Self {
+            id: task.info.gid,
+            name: task.name,
+            state: RemoteDownloadState::from_v3(task.status),
+            destination: task.dst,
+            total: task.total,
+            downloaded: task.downloaded,
+            speed: task.speed.max(0) as u64,
+            error: None,
+            files: files_from_v3(task.info.files),
+            created_at: None,
+        }
+    }
+
+    /// Build a download from a finished v3 aria2 task
+    pub fn from_v3_finished(task: v3_models::Aria2Finished) -> Self {
+        let state = RemoteDownloadState::from_v3_finished(task.status, task.task_status);
+        let error = [task.error, task.task_error]
+            .into_iter()
+            .find(|e| !e.is_empty());
+        Self {
+            id: task.gid,
+            name: task.name,
+            state,
+            destination: task.dst,
+            total: task.total,
+            downloaded: if state == RemoteDownloadState::Completed {
+                task.total
+            } else {
+                0
+            },
+            speed: 0,
+            error,
+            files: files_from_v3(task.files),
+            created_at: (!task.create.is_empty()).then_some(task.create),
+        }
+    }
+
+    /// Build a download from a v4 download task
+    pub fn from_v4(task: v4_models::TaskResponse) -> Self {
+        let phase = task.summary.as_ref().and_then(|s| s.phase.clone());
+        let props: V4DownloadProps = task
+            .summary
+            .map(|s| serde_json::from_value(s.props).unwrap_or_default())
+            .unwrap_or_default();
+        let download = props.download.unwrap_or_default();
+        let name = if download.name.is_empty() {
+            props.src_str
+        } else {
+            download.name
+        };
+        Self {
+            id: task.id,
+            name,
+            state: RemoteDownloadState::from_v4(
+                task.status.as_str(),
+                phase.as_deref(),
+                Some(&download.state),
+            ),
+            destination: destination_from_v4(&props.dst),
+            total: download.total,
+            downloaded: download.downloaded,
+            speed: download.download_speed,
+            error: task.error.filter(|e| !e.is_empty()),
+            files: download
+                .files
+                .into_iter()
+                .map(|file| RemoteDownloadFile {
+                    index: file.index,
+                    downloaded: (file.size as f64 * file.progress.clamp(0.0, 1.0)) as u64,
+                    size: file.size,
+                    selected: file.selected,
+                    path: file.name,
+                })
+                .collect(),
+            created_at: Some(task.created_at),
+        }
+    }
+}
+
+/// Remote download methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Start remote downloads into a folder
+    ///
+    /// Each URL or magnet link becomes one download. v4 returns the created
+    /// tasks; v3 does not report them, so the result is empty there and the
+    /// downloads show up in [`list_active_downloads`](Self::list_active_downloads).
+    pub async fn create_remote_download(
+        &self,
+        urls: &[&str],
+        dest_folder: &str,
+    ) -> Result<Vec<RemoteDownload>, Error> {
+        debug!(
+            "Creating remote download of {:?} into {}",
+            urls, dest_folder
+        );
+
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                let request = v3_models::Aria2CreateRequest {
+                    dst: normalize_path(dest_folder),
+                    url: urls.to_vec(),
+                };
+                client.create_download(&request).await?;
+                Ok(Vec::new())
+            }
+            UnifiedClient::V4(client) => {
+                let dst = path_to_uri(dest_folder);
+                let request = v4_models::CreateDownloadRequest {
+                    dst: &dst,
+                    src: urls.to_vec(),
+                    preferred_node_id: None,
+                };
+                let tasks = client.create_download(&request).await?;
+                Ok(tasks
+                    .into_iter()
+                    .map(|task| RemoteDownload {
+                        id: task.id,
+                        name: task.name.unwrap_or_default(),
+                        state: RemoteDownloadState::from_v4(&task.status, None, None),
+                        destination: normalize_path(dest_folder).to_string(),
+                        total: 0,
+                        downloaded: 0,
+                        speed: 0,
+                        error: None,
+                        files: Vec::new(),
+                        created_at: Some(task.created_at),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// List remote downloads that are still running
+    pub async fn list_active_downloads(&self) -> Result<Vec<RemoteDownload>, Error> {
+        debug!("Listing active remote downloads");
+
+        match self.client()? {
+            UnifiedClient::V3(client) => Ok(client
+                .list_downloading()
+                .await?
+                .into_iter()
+                .map(RemoteDownload::from_v3_downloading)
+                .collect()),
+            UnifiedClient::V4(_) => self.list_v4_downloads("downloading").await,
+        }
+    }
+
+    /// List remote downloads that have completed, failed or been canceled
+    pub async fn list_finished_downloads(&self) -> Result<Vec<RemoteDownload>, Error> {
+        debug!("Listing finished remote downloads");
+
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                let mut downloads = Vec::new();
+                let mut page = 1;
+                loop {
+                    let tasks = client.list_finished(page).await?;
+                    if tasks.is_empty() {
+                        break;
+                    }
+                    downloads.extend(tasks.into_iter().map(RemoteDownload::from_v3_finished));
+                    page += 1;
+                }
+                Ok(downloads)
+            }
+            UnifiedClient::V4(_) => self.list_v4_downloads("downloaded").await,
+        }
+    }
+
+    /// List all pages of a v4 download task category
+    async fn list_v4_downloads(&self, category: &str) -> Result<Vec<RemoteDownload>, Error> {
+        let UnifiedClient::V4(client) = self.client()? else {
+            return Err(Error::UnsupportedFeature(
+                "download task categories".to_string(),
+                "v3".to_string(),
+            ));
+        };
+        let mut downloads = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let list = client
+                .list_workflow_tasks_with_token(DOWNLOAD_PAGE_SIZE, category, next_token.as_deref())
+                .await?;
+            downloads.extend(list.tasks.into_iter().map(RemoteDownload::from_v4));
+            next_token = list.pagination.next_token.filter(|t| !t.is_empty());
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(downloads)
+    }
+
+    /// Cancel a remote download
+    pub async fn cancel_remote_download(&self, id: &str) -> Result<(), Error> {
+        debug!("Canceling remote download: {}", id);
+
+        match self.client()? {
+            UnifiedClient::V3(client) => client.delete_task(id).await,
+            UnifiedClient::V4(client) => client.cancel_download_task(id).await,
+        }
+    }
+
+    /// Choose which files of a torrent to download
+    ///
+    /// `indexes` are [`RemoteDownloadFile::index`] values; all other files
+    /// are skipped. The file list is only known once the torrent's metadata
+    /// has been fetched.
+    pub async fn select_download_files(&self, id: &str, indexes: &[u32]) -> Result<(), Error> {
+        debug!("Selecting files {:?} of remote download {}", indexes, id);
+
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                let request = v3_models::Aria2SelectRequest {
+                    indexes: indexes.iter().map(|i| *i as i32).collect(),
+                };
+                client.select_files(id, &request).await
+            }
+            UnifiedClient::V4(client) => {
+                // v4 expects a decision for every file of the torrent
+                let download = self
+                    .list_active_downloads()
+                    .await?
+                    .into_iter()
+                    .find(|d| d.id == id)
+                    .ok_or_else(|| {
+                        Error::InvalidResponse(format!("Active download not found: {}", id))
+                    })?;
+                if download.files.is_empty() {
+                    return Err(Error::InvalidResponse(format!(
+                        "File list of download {} is not available yet",
+                        id
+                    )));
+                }
+                let request = v4_models::SelectDownloadFilesRequest {
+                    selected_files: download
+                        .files
+                        .iter()
+                        .map(|file| v4_models::DownloadFileSelection {
+                            index: file.index as i32,
+                            download: indexes.contains(&file.index),
+                        })
+                        .collect(),
+                };
+                client.select_download_files(id, &request).await?;
+                Ok(())
+            }
+        }
+    }
+}
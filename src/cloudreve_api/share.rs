@@ -3,24 +3,224 @@
 use crate::Error;
 use crate::api::v3::models as v3_models;
 use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::uri_to_path;
 use crate::client::UnifiedClient;
+use crate::cloudreve_api::entry::parse_timestamp;
+use chrono::{Duration, Utc};
 use log::debug;
 
+/// Page size used when listing v4 share links without an explicit size
+const DEFAULT_SHARE_PAGE_SIZE: u32 = 50;
+
 /// Unified share item
 #[derive(Debug, Clone)]
 pub struct ShareItem {
+    /// Share ID on v4, share key on v3
     pub id: String,
     pub name: String,
     pub url: String,
     pub created_at: String,
     pub expired: bool,
+    pub is_folder: bool,
+    pub password: Option<String>,
+    pub views: u64,
+    pub downloads: u64,
+    /// Downloads left before the share expires; `None` if unlimited
+    pub remaining_downloads: Option<u64>,
+    /// Expiry time; `None` if the share does not expire
+    pub expires_at: Option<String>,
+    /// Files can be previewed in the browser
+    pub preview: bool,
+    /// The folder's README is shown (v4 only)
+    pub show_readme: bool,
+    /// Credit points needed to unlock the share (v4 only)
+    pub price: i64,
+    /// Path of the shared object, if the server reports it (v4 only)
+    pub source_path: Option<String>,
+}
+
+impl ShareItem {
+    /// Build a share item from a v3 share
+    ///
+    /// v3 reports `expire` and `remain_downloads` as -1 when unlimited, and
+    /// the remaining lifetime in seconds otherwise.
+    pub fn from_v3(share: v3_models::Share, base_url: &str) -> Self {
+        let expired = share.remain_downloads == 0 || v3_expired_by_time(&share);
+        Self {
+            url: format!("{}/s/{}", base_url.trim_end_matches('/'), share.key),
+            id: share.key,
+            name: share.source.map(|s| s.name).unwrap_or_default(),
+            created_at: share.created_at,
+            expired,
+            is_folder: share.is_dir,
+            password: (!share.password.is_empty()).then_some(share.password),
+            views: share.views.max(0) as u64,
+            downloads: share.downloads.max(0) as u64,
+            remaining_downloads: (share.remain_downloads >= 0)
+                .then_some(share.remain_downloads as u64),
+            expires_at: (share.expire > 0)
+                .then(|| (Utc::now() + Duration::seconds(share.expire)).to_rfc3339()),
+            preview: share.preview,
+            show_readme: false,
+            price: 0,
+            source_path: None,
+        }
+    }
+
+    /// Build a share item from a v4 share link
+    pub fn from_v4(link: v4_models::ShareLink) -> Self {
+        Self {
+            id: link.id,
+            name: link.name,
+            url: link.url,
+            created_at: link.created_at,
+            expired: link.expired,
+            is_folder: link.source_type == v4_models::ShareSourceType::Folder,
+            password: link.password.filter(|p| !p.is_empty()),
+            views: link.visited.max(0) as u64,
+            downloads: link.downloaded.max(0) as u64,
+            remaining_downloads: link.remain_downloads.filter(|d| *d >= 0).map(|d| d as u64),
+            expires_at: link.expires.or(link.expired_at),
+            preview: link.share_view.unwrap_or(false),
+            show_readme: link.show_readme.unwrap_or(false),
+            price: link.price,
+            source_path: link
+                .source_uri
+                .map(|uri| uri_to_path(&uri).map(str::to_string).unwrap_or(uri)),
+        }
+    }
+}
+
+/// One page of share links
+#[derive(Debug, Clone, Default)]
+pub struct SharePage {
+    pub items: Vec<ShareItem>,
+    /// Token for the next page; `None` on the last page
+    pub next_page: Option<String>,
+    /// Total number of shares, if the server reports it (v3 only)
+    pub total: Option<u64>,
+}
+
+/// Options for creating or editing a share link
+///
+/// When editing, options left as `None` keep their current value.
+#[derive(Debug, Clone, Default)]
+pub struct ShareOptions {
+    /// Password needed to open the share; an empty string removes it
+    pub password: Option<String>,
+    /// Seconds until the share expires
+    pub expires: Option<u32>,
+    /// Number of downloads before the share expires
+    pub download_limit: Option<u32>,
+    /// Allow previewing files in the browser
+    pub preview: Option<bool>,
+    /// Show the folder's README (v4 only)
+    pub show_readme: Option<bool>,
+    /// Credit points needed to unlock the share (v4 only)
+    pub price: Option<i32>,
+    /// Permissions granted through the share (v4 only); read-only if not set
+    pub permissions: Option<v4_models::PermissionSetting>,
+}
+
+impl ShareOptions {
+    /// Get the password to set, treating an empty password as none
+    fn password(&self) -> Option<&str> {
+        self.password.as_deref().filter(|p| !p.is_empty())
+    }
+
+    /// Name the first option v3 cannot apply
+    fn v3_unsupported(&self, editing: bool) -> Option<&'static str> {
+        if self.price.is_some() {
+            Some("price")
+        } else if self.permissions.is_some() {
+            Some("permissions")
+        } else if self.show_readme == Some(true) {
+            Some("show_readme")
+        } else if editing && self.expires.is_some() {
+            Some("expires")
+        } else if editing && self.download_limit.is_some() {
+            Some("download_limit")
+        } else {
+            None
+        }
+    }
+}
+
+/// Properties for updating a share
+pub type ShareUpdateProps = ShareOptions;
+
+/// Split a share URL into the share ID and the password embedded in it
+///
+/// Accepts `https://host/s/<id>`, `https://host/s/<id>/<password>` and a
+/// bare ID.
+pub(crate) fn split_share_url(url: &str) -> (String, Option<String>) {
+    let rest = url.split_once("/s/").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let mut parts = rest.trim_matches('/').splitn(2, '/');
+    let id = parts.next().unwrap_or("").to_string();
+    let password = parts.next().filter(|p| !p.is_empty()).map(str::to_string);
+    (id, password)
+}
+
+/// Check whether a share URL points to the server at `base_url`
+///
+/// Compares scheme-independent host and port. Bare share IDs and URLs that
+/// cannot be parsed are taken to belong to the server.
+pub(crate) fn is_same_server(share_url: &str, base_url: &str) -> bool {
+    let (Ok(share), Ok(base)) = (url::Url::parse(share_url), url::Url::parse(base_url)) else {
+        return true;
+    };
+    if share.host_str().is_none() {
+        return true;
+    }
+    share.host_str().map(str::to_lowercase) == base.host_str().map(str::to_lowercase)
+        && share.port_or_known_default() == base.port_or_known_default()
+}
+
+/// Check whether a v3 share has run past its expiry time
+///
+/// v3 reports the seconds left in `expire`, or -1 for shares that never
+/// expire.
+pub(crate) fn v3_expired_by_time(share: &v3_models::Share) -> bool {
+    share.expire != -1 && share.expire <= 0
+}
+
+/// Password to send when editing a v4 share link
+///
+/// Without a new password the current one is kept. A protected share whose
+/// password the server did not report fails with [`Error::InvalidValue`]
+/// rather than being turned public by the edit.
+fn edited_password(
+    props: &ShareOptions,
+    current: &v4_models::ShareLink,
+) -> Result<Option<String>, Error> {
+    if props.password.is_some() {
+        return Ok(props.password().map(str::to_string));
+    }
+    match current.password.clone().filter(|p| !p.is_empty()) {
+        Some(password) => Ok(Some(password)),
+        None if current.password_protected == Some(true) => Err(Error::InvalidValue(format!(
+            "Share {} is password protected but its password is unknown; pass it in ShareOptions::password",
+            current.id
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Seconds left until a v4 share link expires
+fn remaining_seconds(link: &v4_models::ShareLink) -> Option<u32> {
+    let expires = link.expires.as_deref().or(link.expired_at.as_deref())?;
+    let left = (parse_timestamp(expires).ok()? - Utc::now()).num_seconds();
+    (left > 0).then(|| u32::try_from(left).unwrap_or(u32::MAX))
 }
 
 /// Share methods for CloudreveAPI
 impl super::CloudreveAPI {
     /// Create a share link for a file or directory
     ///
-    /// Creates a share link with optional expiration and password.
+    /// Creates a share link with optional expiration and password. Returns
+    /// the share key on v3 and the share URL on v4; use
+    /// [`create_share_with`](Self::create_share_with) for all options.
     pub async fn create_share(
         &self,
         path: &str,
@@ -30,99 +230,256 @@ impl super::CloudreveAPI {
     ) -> Result<String, Error> {
         debug!("Creating share link for: {}", path);
 
-        match &self.inner {
+        let options = ShareOptions {
+            password: password.map(str::to_string),
+            expires: expires_in,
+            ..Default::default()
+        };
+        let (key, url) = self.submit_share(path, &options).await?;
+        Ok(match self.client()? {
+            UnifiedClient::V3(_) => key,
+            UnifiedClient::V4(_) => url,
+        })
+    }
+
+    /// Create a share link with all options
+    ///
+    /// v3 rejects `price`, `permissions` and `show_readme` with
+    /// [`Error::UnsupportedFeature`]. On v4 the created link is read back so
+    /// the returned item is complete.
+    pub async fn create_share_with(
+        &self,
+        path: &str,
+        options: &ShareOptions,
+    ) -> Result<ShareItem, Error> {
+        debug!("Creating share link for {} with {:?}", path, options);
+
+        let (id, url) = self.submit_share(path, options).await?;
+        match self.client()? {
             UnifiedClient::V3(client) => {
-                let request = v3_models::ShareRequest {
-                    id: path.to_string(),
-                    is_dir: path.ends_with('/'),
-                    password: password.unwrap_or("").to_string(),
+                let obj = self.path_cache.find(client, path).await?;
+                Ok(ShareItem {
+                    id,
+                    name: obj.name,
+                    url,
+                    created_at: Utc::now().to_rfc3339(),
+                    expired: false,
+                    is_folder: obj.object_type == "dir",
+                    password: options.password().map(str::to_string),
+                    views: 0,
                     downloads: 0,
-                    expire: expires_in.unwrap_or(0) as i32,
-                    preview: true,
+                    remaining_downloads: options.download_limit.map(u64::from),
+                    expires_at: options
+                        .expires
+                        .map(|secs| (Utc::now() + Duration::seconds(secs.into())).to_rfc3339()),
+                    preview: options.preview.unwrap_or(true),
+                    show_readme: false,
+                    price: 0,
+                    source_path: Some(path.to_string()),
+                })
+            }
+            UnifiedClient::V4(client) => {
+                let link = client
+                    .get_share_link_info(&id, options.password(), Some(false), Some(true))
+                    .await?;
+                Ok(ShareItem::from_v4(link))
+            }
+        }
+    }
+
+    /// Create a share link and return its ID (key on v3) and URL
+    async fn submit_share(
+        &self,
+        path: &str,
+        options: &ShareOptions,
+    ) -> Result<(String, String), Error> {
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                if let Some(option) = options.v3_unsupported(false) {
+                    return Err(Error::UnsupportedFeature(
+                        format!("share option {}", option),
+                        "v3".to_string(),
+                    ));
+                }
+                // V3 shares reference the object ID, with -1 meaning unlimited
+                let obj = self.path_cache.find(client, path).await?;
+                let request = v3_models::ShareRequest {
+                    id: obj.id,
+                    is_dir: obj.object_type == "dir",
+                    password: options.password().unwrap_or("").to_string(),
+                    downloads: options
+                        .download_limit
+                        .map_or(-1, |d| i32::try_from(d).unwrap_or(i32::MAX)),
+                    expire: options
+                        .expires
+                        .map_or(-1, |e| i32::try_from(e).unwrap_or(i32::MAX)),
+                    preview: options.preview.unwrap_or(true),
                 };
                 let share = client.create_share(&request).await?;
-                Ok(share.key)
+                let url = format!("{}/s/{}", self.base_url.trim_end_matches('/'), share.key);
+                Ok((share.key, url))
             }
             UnifiedClient::V4(client) => {
-                let permissions = v4_models::PermissionSetting {
-                    user_explicit: serde_json::json!({}),
-                    group_explicit: serde_json::json!({}),
-                    same_group: "read".to_string(),
-                    other: "read".to_string(),
-                    anonymous: "read".to_string(),
-                    everyone: "read".to_string(),
-                };
+                let password = options.password().map(str::to_string);
                 let request = v4_models::CreateShareLinkRequest {
-                    permissions,
+                    permissions: options
+                        .permissions
+                        .clone()
+                        .unwrap_or_else(v4_models::PermissionSetting::read_only),
                     uri: path.to_string(),
                     is_private: Some(password.is_some()),
-                    share_view: None,
-                    expire: expires_in,
-                    price: None,
-                    password: password.map(|p| p.to_string()),
-                    show_readme: None,
+                    share_view: options.preview,
+                    expire: options.expires,
+                    price: options.price,
+                    password,
+                    show_readme: options.show_readme,
+                    downloads: options.download_limit,
                 };
-                let share = client.create_share_link(&request).await?;
-                Ok(share)
+                let url = client.create_share_link(&request).await?;
+                let (id, _) = split_share_url(&url);
+                Ok((id, url))
             }
         }
     }
 
     /// List all shares
     ///
-    /// Returns a list of all share links for the current user.
+    /// Returns all share links of the current user, reading every page.
     pub async fn list_shares(&self) -> Result<Vec<ShareItem>, Error> {
         debug!("Listing shares");
 
-        match &self.inner {
-            UnifiedClient::V3(_client) => {
-                // V3 doesn't have a dedicated list shares endpoint
-                // Return empty for now or implement via workarounds
-                Ok(Vec::new())
+        let mut items = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let page = self.list_shares_page(None, token.as_deref()).await?;
+            items.extend(page.items);
+            match page.next_page {
+                Some(next) => token = Some(next),
+                None => break,
+            }
+        }
+        Ok(items)
+    }
+
+    /// List one page of shares
+    ///
+    /// Pass the `next_page` token of the previous page to continue. v3
+    /// servers use a fixed page size, so `page_size` only applies on v4.
+    pub async fn list_shares_page(
+        &self,
+        page_size: Option<u32>,
+        page_token: Option<&str>,
+    ) -> Result<SharePage, Error> {
+        debug!("Listing shares page: {:?}", page_token);
+
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                // The token carries the page number and the shares seen so far
+                let (page, seen) = match page_token {
+                    Some(token) => token
+                        .split_once(':')
+                        .and_then(|(p, s)| Some((p.parse::<u32>().ok()?, s.parse::<i64>().ok()?)))
+                        .ok_or_else(|| {
+                            Error::InvalidResponse(format!("Invalid share page token: {}", token))
+                        })?,
+                    None => (1, 0),
+                };
+                let list = client.list_shares(page).await?;
+                let seen = seen + list.items.len() as i64;
+                let next_page = (!list.items.is_empty() && seen < list.total)
+                    .then(|| format!("{}:{}", page + 1, seen));
+                Ok(SharePage {
+                    items: list
+                        .items
+                        .into_iter()
+                        .map(|s| ShareItem::from_v3(s, &self.base_url))
+                        .collect(),
+                    next_page,
+                    total: Some(list.total.max(0) as u64),
+                })
             }
             UnifiedClient::V4(client) => {
-                let shares = client.list_my_share_links().await?;
-                Ok(shares
-                    .into_iter()
-                    .map(|s| ShareItem {
-                        id: s.id,
-                        name: s.name,
-                        url: s.url,
-                        created_at: s.created_at,
-                        expired: s.expired,
-                    })
-                    .collect())
+                let (links, next_page) = client
+                    .list_my_share_links_with_params(
+                        page_size.unwrap_or(DEFAULT_SHARE_PAGE_SIZE),
+                        None,
+                        None,
+                        page_token,
+                    )
+                    .await?;
+                Ok(SharePage {
+                    items: links.into_iter().map(ShareItem::from_v4).collect(),
+                    next_page: next_page.filter(|t| !t.is_empty()),
+                    total: None,
+                })
             }
         }
     }
 
     /// Update a share link
     ///
-    /// Updates an existing share link with new settings.
-    pub async fn update_share(&self, id: &str, props: &ShareUpdateProps) -> Result<(), Error> {
+    /// Options left as `None` keep their current value. v3 can only change
+    /// the password and preview; other options fail with
+    /// [`Error::UnsupportedFeature`]. On v4, editing a protected share whose
+    /// password the server does not report needs the password passed again,
+    /// or fails with [`Error::InvalidValue`].
+    pub async fn update_share(&self, id: &str, props: &ShareOptions) -> Result<(), Error> {
         debug!("Updating share: {}", id);
 
-        match &self.inner {
-            UnifiedClient::V3(_client) => Err(Error::UnsupportedFeature(
-                "share update".to_string(),
-                "v3".to_string(),
-            )),
+        match self.client()? {
+            UnifiedClient::V3(client) => {
+                if let Some(option) = props.v3_unsupported(true) {
+                    return Err(Error::UnsupportedFeature(
+                        format!("share option {} update", option),
+                        "v3".to_string(),
+                    ));
+                }
+                if let Some(password) = &props.password {
+                    let request = v3_models::ShareUpdateRequest {
+                        prop: "password",
+                        value: password.clone(),
+                    };
+                    client.update_share(id, &request).await?;
+                }
+                if let Some(preview) = props.preview {
+                    let request = v3_models::ShareUpdateRequest {
+                        prop: "preview_enabled",
+                        value: preview.to_string(),
+                    };
+                    client.update_share(id, &request).await?;
+                }
+                Ok(())
+            }
             UnifiedClient::V4(client) => {
-                let permissions = v4_models::PermissionSetting {
-                    user_explicit: serde_json::json!({}),
-                    group_explicit: serde_json::json!({}),
-                    same_group: "read".to_string(),
-                    other: "read".to_string(),
-                    anonymous: "read".to_string(),
-                    everyone: "read".to_string(),
-                };
+                // Editing replaces every setting, so start from the current ones
+                let current = client
+                    .get_share_link_info(id, None, Some(false), Some(true))
+                    .await?;
+                let uri = current.source_uri.clone().ok_or_else(|| {
+                    Error::InvalidResponse(format!("Share {} has no source URI", id))
+                })?;
+                let password = edited_password(props, &current)?;
                 let request = v4_models::EditShareLinkRequest {
-                    permissions,
-                    uri: String::new(), // Will be filled by the API
-                    share_view: None,
-                    expire: props.expires,
-                    price: None,
-                    show_readme: None,
+                    permissions: props
+                        .permissions
+                        .clone()
+                        .or_else(|| current.permission_setting.clone())
+                        .unwrap_or_else(v4_models::PermissionSetting::read_only),
+                    uri,
+                    is_private: Some(password.is_some()),
+                    share_view: props.preview.or(current.share_view),
+                    expire: props.expires.or_else(|| remaining_seconds(&current)),
+                    price: props
+                        .price
+                        .or_else(|| (current.price > 0).then_some(current.price as i32)),
+                    password,
+                    show_readme: props.show_readme.or(current.show_readme),
+                    downloads: props.download_limit.or_else(|| {
+                        current
+                            .remain_downloads
+                            .filter(|d| *d > 0)
+                            .map(|d| u32::try_from(d).unwrap_or(u32::MAX))
+                    }),
                 };
                 client.edit_share_link(id, &request).await?;
                 Ok(())
@@ -136,11 +493,8 @@ impl super::CloudreveAPI {
     pub async fn delete_share(&self, id: &str) -> Result<(), Error> {
         debug!("Deleting share: {}", id);
 
-        match &self.inner {
-            UnifiedClient::V3(_client) => Err(Error::UnsupportedFeature(
-                "share deletion".to_string(),
-                "v3".to_string(),
-            )),
+        match self.client()? {
+            UnifiedClient::V3(client) => client.delete_share(id).await,
             UnifiedClient::V4(client) => {
                 client.delete_share_link(id).await?;
                 Ok(())
@@ -149,9 +503,70 @@ impl super::CloudreveAPI {
     }
 }
 
-/// Properties for updating a share
-#[derive(Debug, Clone, Default)]
-pub struct ShareUpdateProps {
-    pub password: Option<String>,
-    pub expires: Option<u32>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_share_url() {
+        assert_eq!(
+            split_share_url("https://example.com/s/abc123"),
+            ("abc123".to_string(), None)
+        );
+        assert_eq!(
+            split_share_url("https://example.com/s/abc123/secret?x=1"),
+            ("abc123".to_string(), Some("secret".to_string()))
+        );
+        assert_eq!(split_share_url("abc123"), ("abc123".to_string(), None));
+    }
+
+    #[test]
+    fn test_is_same_server() {
+        let base = "https://drive.example.com";
+        assert!(is_same_server("https://Drive.example.com/s/abc", base));
+        assert!(is_same_server("https://drive.example.com:443/s/abc", base));
+        assert!(is_same_server("abc123", base));
+        assert!(!is_same_server("https://other.example.com/s/abc", base));
+        assert!(!is_same_server(
+            "https://drive.example.com:8443/s/abc",
+            base
+        ));
+    }
+
+    #[test]
+    fn test_edit_keeps_password_protection() {
+        let link = |password: &str| -> v4_models::ShareLink {
+            serde_json::from_value(serde_json::json!({
+                "id": "Xy12",
+                "name": "report.pdf",
+                "visited": 0,
+                "downloaded": 0,
+                "price": 0,
+                "unlocked": true,
+                "source_type": 0,
+                "owner": {"id": "1", "created_at": "2024-01-01T00:00:00Z"},
+                "created_at": "2024-01-01T00:00:00Z",
+                "expired": false,
+                "url": "https://example.com/s/Xy12",
+                "password": password,
+                "password_protected": true
+            }))
+            .unwrap()
+        };
+        let keep = ShareOptions::default();
+        let clear = ShareOptions {
+            password: Some(String::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            edited_password(&keep, &link("pw")).unwrap().as_deref(),
+            Some("pw")
+        );
+        assert!(matches!(
+            edited_password(&keep, &link("")),
+            Err(Error::InvalidValue(_))
+        ));
+        assert_eq!(edited_password(&clear, &link("")).unwrap(), None);
+    }
 }
@@ -0,0 +1,533 @@
+//! Anonymous access to share links
+//!
+//! A [`ShareSession`] opens a share link, with its password if it has one,
+//! and lists and downloads what it contains. No login is needed. v4 servers
+//! address shared files as `cloudreve://<id>:<password>@share/<path>`, while
+//! v3 servers unlock the share for the session and serve it through their
+//! `/share` endpoints.
+//!
+//! [`CloudreveAPI::import_share`](super::CloudreveAPI::import_share) saves
+//! an opened share into the logged-in account.
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::ApiV4Client;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::entry::{Entry, collect_entries};
+use crate::cloudreve_api::path::{join_path, normalize_path, split_path};
+use crate::cloudreve_api::share::{ShareItem, is_same_server, split_share_url};
+use log::{debug, warn};
+use std::collections::HashMap;
+
+/// Page size used when listing shared folders on v4
+const SHARE_LIST_PAGE_SIZE: u32 = 100;
+
+/// An opened share link
+///
+/// Paths are relative to the shared folder, e.g. `/photos/a.jpg`. For a
+/// shared file, pass `/` or the file's own name.
+#[derive(Debug, Clone)]
+pub struct ShareSession {
+    client: UnifiedClient,
+    password: Option<String>,
+    /// Share ID on v4, share key on v3
+    pub id: String,
+    pub info: ShareItem,
+}
+
+/// Result of importing a share
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// The server copied the share itself and nothing was streamed
+    pub server_side: bool,
+    /// Destination paths of the imported files; for a server-side copy,
+    /// of the top-level files and folders
+    pub copied: Vec<String>,
+    pub directories_created: usize,
+    /// Bytes streamed through this client; 0 for a server-side copy
+    pub bytes_copied: u64,
+    pub errors: Vec<(String, String)>,
+}
+
+/// Get the path inside a share from a v4 share URI
+///
+/// e.g. `cloudreve://abc:pw@share/docs/a.txt` -> `/docs/a.txt`
+fn share_uri_to_path(uri: &str) -> String {
+    let rest = uri.strip_prefix("cloudreve://").unwrap_or(uri);
+    match rest.split_once('/') {
+        Some((_, path)) => format!("/{}", path.trim_end_matches('/')),
+        None => "/".to_string(),
+    }
+}
+
+/// Make a download URL absolute
+fn absolute_url(base_url: &str, url: String) -> String {
+    if url.starts_with('/') {
+        format!("{}{}", base_url.trim_end_matches('/'), url)
+    } else {
+        url
+    }
+}
+
+impl ShareSession {
+    /// Check whether the share is a folder
+    pub fn is_folder(&self) -> bool {
+        self.info.is_folder
+    }
+
+    /// Build the v4 URI of a path inside the share
+    fn v4_uri(&self, path: &str) -> String {
+        let owner = match &self.password {
+            Some(password) => format!("{}:{}", self.id, password),
+            None => self.id.clone(),
+        };
+        format!(
+            "cloudreve://{}@share/{}",
+            owner,
+            normalize_path(path).trim_start_matches('/')
+        )
+    }
+
+    /// List a folder inside the share
+    ///
+    /// Returns entries with paths relative to the share. All pages are read.
+    /// Objects that do not parse are skipped and logged.
+    pub async fn list(&self, path: &str) -> Result<Vec<Entry>, Error> {
+        debug!("Listing shared folder {} in share {}", path, self.id);
+
+        match &self.client {
+            UnifiedClient::V3(client) => {
+                let list = client
+                    .list_shared_directory(&self.id, normalize_path(path))
+                    .await?;
+                Ok(collect_entries(
+                    list.objects.into_iter().map(Entry::from_v3),
+                ))
+            }
+            UnifiedClient::V4(client) => {
+                let uri = self.v4_uri(path);
+                let mut files = Vec::new();
+                let mut next_token: Option<String> = None;
+                loop {
+                    let request = v4_models::ListFilesRequest {
+                        path: &uri,
+                        page: None,
+                        page_size: Some(SHARE_LIST_PAGE_SIZE),
+                        order_by: None,
+                        order_direction: None,
+                        next_page_token: next_token.as_deref(),
+                    };
+                    let response = client.list_files(&request).await?;
+                    files.extend(response.files);
+                    next_token = response.pagination.next_token.filter(|t| !t.is_empty());
+                    if next_token.is_none() {
+                        break;
+                    }
+                }
+                Ok(collect_entries(files.into_iter().map(|file| {
+                    let path = share_uri_to_path(&file.path);
+                    let mut entry = Entry::from_v4(file)?;
+                    entry.path = path;
+                    Ok(entry)
+                })))
+            }
+        }
+    }
+
+    /// Get a download URL for a file in the share
+    pub async fn download_url(&self, path: &str) -> Result<String, Error> {
+        debug!("Getting download URL for {} in share {}", path, self.id);
+
+        match &self.client {
+            UnifiedClient::V3(client) => {
+                // A shared file is downloaded without a path
+                let path = self.is_folder().then(|| normalize_path(path));
+                let url = client.get_shared_download_url(&self.id, path).await?;
+                Ok(absolute_url(&client.base_url, url.url))
+            }
+            UnifiedClient::V4(client) => {
+                let uri = self.v4_uri(path);
+                let request = v4_models::CreateDownloadUrlRequest {
+                    uris: vec![&uri],
+                    download: Some(true),
+                    redirect: None,
+                    entity: None,
+                    use_primary_site_url: None,
+                    skip_error: None,
+                    archive: None,
+                    no_cache: None,
+                };
+                let response = client.create_download_url(&request).await?;
+                response
+                    .urls
+                    .into_iter()
+                    .next()
+                    .map(|item| item.url)
+                    .ok_or_else(|| Error::InvalidResponse("No download URL returned".to_string()))
+            }
+        }
+    }
+
+    /// Get a download URL for a zip archive of files and folders in the share
+    pub async fn archive_url(&self, paths: &[&str]) -> Result<String, Error> {
+        debug!("Getting archive URL for {:?} in share {}", paths, self.id);
+
+        match &self.client {
+            UnifiedClient::V3(client) => {
+                // v3 archives reference object IDs, found in the parent listings
+                let mut listings: HashMap<&str, v3_models::DirectoryList> = HashMap::new();
+                let mut objects = Vec::with_capacity(paths.len());
+                for path in paths {
+                    let (parent, name) = split_path(path);
+                    if !listings.contains_key(parent) {
+                        let list = client.list_shared_directory(&self.id, parent).await?;
+                        listings.insert(parent, list);
+                    }
+                    let obj = listings[parent]
+                        .objects
+                        .iter()
+                        .find(|obj| obj.name == name)
+                        .ok_or_else(|| {
+                            Error::InvalidResponse(format!("File not found in share: {}", path))
+                        })?;
+                    objects.push(obj.clone());
+                }
+                let mut items = v3_models::SourceItems {
+                    items: Vec::new(),
+                    dirs: Vec::new(),
+                };
+                for obj in &objects {
+                    if obj.object_type == "dir" {
+                        items.dirs.push(obj.id.as_str());
+                    } else {
+                        items.items.push(obj.id.as_str());
+                    }
+                }
+                let url = client.create_shared_archive(&self.id, &items).await?;
+                Ok(absolute_url(&client.base_url, url.url))
+            }
+            UnifiedClient::V4(client) => {
+                let uris: Vec<String> = paths.iter().map(|path| self.v4_uri(path)).collect();
+                let request = v4_models::CreateDownloadUrlRequest {
+                    uris: uris.iter().map(String::as_str).collect(),
+                    download: Some(true),
+                    redirect: None,
+                    entity: None,
+                    use_primary_site_url: None,
+                    skip_error: None,
+                    archive: Some(true),
+                    no_cache: None,
+                };
+                let response = client.create_download_url(&request).await?;
+                response
+                    .urls
+                    .into_iter()
+                    .next()
+                    .map(|item| item.url)
+                    .ok_or_else(|| Error::InvalidResponse("No archive URL returned".to_string()))
+            }
+        }
+    }
+
+    /// Download a file in the share into memory
+    pub async fn download(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let url = self.download_url(path).await?;
+        self.fetch(&url).await
+    }
+
+    /// Download a zip archive of files and folders in the share into memory
+    pub async fn download_archive(&self, paths: &[&str]) -> Result<Vec<u8>, Error> {
+        let url = self.archive_url(paths).await?;
+        self.fetch(&url).await
+    }
+
+    /// Open a download of a file in the share
+    async fn open_download(&self, path: &str) -> Result<reqwest::Response, Error> {
+        let url = self.download_url(path).await?;
+        Ok(self
+            .client
+            .http_client()
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, Error> {
+        let response = self
+            .client
+            .http_client()
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Share access methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Open a share link
+    ///
+    /// `share` is a share URL such as `https://host/s/<id>` or a bare share
+    /// ID. A password embedded in the URL is used unless `password` is
+    /// given. Works without login; a wrong or missing password fails with
+    /// [`Error::Auth`]. Links to another server fail with
+    /// [`Error::InvalidValue`]; open those with a client for that server.
+    pub async fn open_share(
+        &self,
+        share: &str,
+        password: Option<&str>,
+    ) -> Result<ShareSession, Error> {
+        if !is_same_server(share, &self.base_url) {
+            return Err(Error::InvalidValue(format!(
+                "Share link {} belongs to another server than {}",
+                share, self.base_url
+            )));
+        }
+        let (id, url_password) = split_share_url(share);
+        if id.is_empty() {
+            return Err(Error::InvalidResponse(format!(
+                "Invalid share link: {}",
+                share
+            )));
+        }
+        let password = password.map(str::to_string).or(url_password);
+        debug!("Opening share: {}", id);
+
+        let mut client = self.client()?.clone();
+        let info = match &mut client {
+            UnifiedClient::V3(client) => {
+                let share = client.get_share_info(&id, password.as_deref()).await?;
+                if share.locked {
+                    return Err(Error::Auth(format!("Share {} needs a valid password", id)));
+                }
+                let base_url = client.base_url.clone();
+                ShareItem::from_v3(share, &base_url)
+            }
+            UnifiedClient::V4(client) => {
+                let link = client
+                    .get_share_link_info(&id, password.as_deref(), None, None)
+                    .await?;
+                if link.password_protected == Some(true) && !link.unlocked {
+                    return Err(Error::Auth(format!("Share {} needs a valid password", id)));
+                }
+                ShareItem::from_v4(link)
+            }
+        };
+
+        Ok(ShareSession {
+            client,
+            password,
+            id,
+            info,
+        })
+    }
+
+    /// Save the contents of a share into this account
+    ///
+    /// A shared folder is imported as a folder of the same name inside
+    /// `dest_folder`; a shared file is put directly into it. When this client
+    /// and the share are on the same v4 server and none of the shared
+    /// entries exist in the destination yet, the server copies the files.
+    /// Otherwise, or if the server-side copy fails, every file is downloaded
+    /// from the share and uploaded again, and failures on individual files
+    /// are collected in [`ImportReport::errors`] while the import continues.
+    /// Existing files are never replaced.
+    pub async fn import_share(
+        &self,
+        share: &ShareSession,
+        dest_folder: &str,
+    ) -> Result<ImportReport, Error> {
+        debug!("Importing share {} into {}", share.id, dest_folder);
+
+        let dest_root = if share.is_folder() {
+            join_path(dest_folder, &share.info.name)
+        } else {
+            normalize_path(dest_folder).to_string()
+        };
+        let mut report = ImportReport::default();
+        if self.ensure_import_dir(&dest_root).await? {
+            report.directories_created += 1;
+        }
+
+        if let (Ok(UnifiedClient::V4(client)), UnifiedClient::V4(_)) =
+            (self.client(), &share.client)
+            && share.client.base_url().trim_end_matches('/') == self.base_url.trim_end_matches('/')
+        {
+            match self
+                .copy_share_on_server(client, share, &dest_root, &mut report)
+                .await
+            {
+                Ok(true) => return Ok(report),
+                Ok(false) => debug!(
+                    "{} already has entries of share {}, streaming instead",
+                    dest_root, share.id
+                ),
+                Err(e) => warn!(
+                    "Server-side copy of share {} failed, streaming instead: {}",
+                    share.id, e
+                ),
+            }
+        }
+
+        if !share.is_folder() {
+            // v3 cannot list a shared file, so use what the share info says
+            let source = format!("/{}", share.info.name);
+            let dest_path = join_path(&dest_root, &share.info.name);
+            self.import_shared_file(share, &source, &dest_path, None, &mut report)
+                .await;
+            return Ok(report);
+        }
+
+        let mut pending = vec!["/".to_string()];
+        while let Some(dir) = pending.pop() {
+            let entries = match share.list(&dir).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    report.errors.push((dir, e.to_string()));
+                    continue;
+                }
+            };
+            for entry in entries {
+                let source = join_path(&dir, &entry.name);
+                let dest_path = join_path(&dest_root, source.trim_start_matches('/'));
+                if entry.is_folder() {
+                    match self.ensure_import_dir(&dest_path).await {
+                        Ok(created) => {
+                            report.directories_created += usize::from(created);
+                            pending.push(source);
+                        }
+                        Err(e) => report.errors.push((source, e.to_string())),
+                    }
+                    continue;
+                }
+                self.import_shared_file(share, &source, &dest_path, Some(&entry), &mut report)
+                    .await;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Let a v4 server copy a share into `dest_root` by itself
+    ///
+    /// Returns `false` without copying anything if one of the shared
+    /// entries already exists in `dest_root`, since the server would
+    /// replace or merge it. Copied entries are looked up afterwards so that
+    /// only what actually arrived is reported.
+    async fn copy_share_on_server(
+        &self,
+        client: &ApiV4Client,
+        share: &ShareSession,
+        dest_root: &str,
+        report: &mut ImportReport,
+    ) -> Result<bool, Error> {
+        let entries = share.list("/").await?;
+        for entry in &entries {
+            if self
+                .lookup(&join_path(dest_root, &entry.name))
+                .await?
+                .is_some()
+            {
+                return Ok(false);
+            }
+        }
+
+        let uris: Vec<String> = entries.iter().map(|e| share.v4_uri(&e.path)).collect();
+        let dst = path_to_uri(dest_root);
+        let request = v4_models::CopyFileRequest {
+            uris: uris.iter().map(String::as_str).collect(),
+            dst: &dst,
+        };
+        client.copy_file(&request).await?;
+        self.invalidate_cached(dest_root);
+
+        report.server_side = true;
+        for entry in entries {
+            let dest_path = join_path(dest_root, &entry.name);
+            match self.lookup(&dest_path).await {
+                Ok(Some(_)) => report.copied.push(dest_path),
+                Ok(None) => report
+                    .errors
+                    .push((entry.path, "Missing after server-side copy".to_string())),
+                Err(e) => report.errors.push((entry.path, e.to_string())),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Make sure a directory exists; returns whether it was created
+    async fn ensure_import_dir(&self, dir: &str) -> Result<bool, Error> {
+        if normalize_path(dir) == "/" {
+            return Ok(false);
+        }
+        match self.lookup(dir).await? {
+            Some(info) if info.is_folder() => Ok(false),
+            Some(_) => Err(Error::InvalidResponse(format!(
+                "Destination is not a directory: {}",
+                dir
+            ))),
+            None => {
+                self.create_directory(dir).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Stream one shared file into this account, recording the outcome
+    ///
+    /// Without a listing entry the size is taken from the download.
+    async fn import_shared_file(
+        &self,
+        share: &ShareSession,
+        source: &str,
+        dest_path: &str,
+        entry: Option<&Entry>,
+        report: &mut ImportReport,
+    ) {
+        let result = async {
+            if self.lookup(dest_path).await?.is_some() {
+                return Err(Error::AlreadyExists(dest_path.to_string()));
+            }
+            let response = share.open_download(source).await?;
+            let size = entry
+                .map(|e| e.size)
+                .or(response.content_length())
+                .ok_or_else(|| {
+                    Error::InvalidResponse(format!("Unknown size of shared file {}", source))
+                })?;
+            let modified = entry.and_then(|e| u64::try_from(e.updated_at.timestamp_millis()).ok());
+            self.upload_response(response, source, dest_path, size, modified)
+                .await
+        }
+        .await;
+
+        match result {
+            Ok(bytes) => {
+                report.bytes_copied += bytes;
+                report.copied.push(dest_path.to_string());
+            }
+            Err(e) => {
+                warn!("Failed to import {}: {}", source, e);
+                report.errors.push((source.to_string(), e.to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_uri_to_path() {
+        assert_eq!(
+            share_uri_to_path("cloudreve://abc:pw@share/docs/a.txt"),
+            "/docs/a.txt"
+        );
+        assert_eq!(share_uri_to_path("cloudreve://abc@share"), "/");
+        assert_eq!(share_uri_to_path("cloudreve://abc@share/"), "/");
+    }
+}
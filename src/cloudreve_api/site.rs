@@ -22,7 +22,7 @@ impl super::CloudreveAPI {
     pub async fn get_site_config(&self, section: Option<&str>) -> Result<SiteConfigValue, Error> {
         debug!("Getting site config: section={:?}", section);
 
-        match &self.inner {
+        match self.client()? {
             UnifiedClient::V4(client) => {
                 let section_enum = section
                     .and_then(|s| match s {
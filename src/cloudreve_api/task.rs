@@ -0,0 +1,379 @@
+//! Background task tracking for CloudreveAPI
+//!
+//! Archive creation and extraction, storage policy relocation, imports and
+//! remote downloads run as workflow tasks on v4 servers. A [`TaskHandle`]
+//! follows one of them: it looks up the current status, streams progress
+//! and waits for a final [`TaskOutcome`].
+
+use crate::Error;
+use crate::api::v4::models::{Progress, TaskResponse, TaskStatus, TaskType};
+use crate::client::UnifiedClient;
+use futures::stream::{self, Stream};
+use log::debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Page size used when searching workflow task lists
+const TASK_PAGE_SIZE: i32 = 50;
+
+/// Options for [`TaskHandle::wait`]
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    /// Give up with [`Error::Timeout`] after this long; `None` waits forever
+    pub timeout: Option<Duration>,
+    /// Delay before the second status check
+    pub initial_interval: Duration,
+    /// Upper bound for the delay between status checks
+    pub max_interval: Duration,
+    /// Factor the delay grows by after every check
+    pub backoff: f64,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            backoff: 2.0,
+        }
+    }
+}
+
+impl WaitOptions {
+    /// Delay to use after `current`, grown by the backoff factor and capped
+    pub fn next_interval(&self, current: Duration) -> Duration {
+        current
+            .mul_f64(self.backoff.max(1.0))
+            .min(self.max_interval)
+    }
+}
+
+/// Final state of a workflow task
+#[derive(Debug, Clone)]
+pub enum TaskOutcome {
+    Completed(TaskResponse),
+    Error {
+        error: Option<String>,
+        /// Errors of earlier attempts, oldest first
+        error_history: Vec<String>,
+        task: TaskResponse,
+    },
+    Canceled(TaskResponse),
+}
+
+impl TaskOutcome {
+    /// Build the outcome of a task, or `None` while it is still running
+    pub fn from_task(task: TaskResponse) -> Option<Self> {
+        match task.status {
+            TaskStatus::Completed => Some(Self::Completed(task)),
+            TaskStatus::Canceled => Some(Self::Canceled(task)),
+            TaskStatus::Error => Some(Self::Error {
+                error: task.error.clone(),
+                error_history: task.error_history.clone().unwrap_or_default(),
+                task,
+            }),
+            TaskStatus::Queued | TaskStatus::Processing | TaskStatus::Suspending => None,
+        }
+    }
+
+    /// Get the task as last reported by the server
+    pub fn task(&self) -> &TaskResponse {
+        match self {
+            Self::Completed(task) | Self::Canceled(task) | Self::Error { task, .. } => task,
+        }
+    }
+
+    /// Check whether the task completed successfully
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Completed(_))
+    }
+}
+
+/// Workflow task categories that may contain a task of the given type
+fn categories_for(kind: Option<&TaskType>) -> &'static [&'static str] {
+    match kind {
+        Some(TaskType::RemoteDownload) => &["downloading", "downloaded"],
+        Some(_) => &["general"],
+        None => &["general", "downloading", "downloaded"],
+    }
+}
+
+/// Check whether a task status is final
+fn is_final(status: &TaskStatus) -> bool {
+    matches!(
+        status,
+        TaskStatus::Completed | TaskStatus::Error | TaskStatus::Canceled
+    )
+}
+
+/// Poll `status` and report `progress` every `interval`
+///
+/// Ends when the task is final, or after the first error of either call.
+fn progress_stream<S, SF, P, PF>(
+    interval: Duration,
+    status: S,
+    progress: P,
+) -> impl Stream<Item = Result<Progress, Error>>
+where
+    S: Fn() -> SF,
+    SF: Future<Output = Result<TaskResponse, Error>>,
+    P: Fn() -> PF,
+    PF: Future<Output = Result<Progress, Error>>,
+{
+    stream::unfold(Some(true), move |state| {
+        // Futures are lazy, so neither request is sent unless awaited
+        let status = status();
+        let progress = progress();
+        async move {
+            let first = state?;
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            match status.await {
+                Ok(task) if is_final(&task.status) => None,
+                Ok(_) => match progress.await {
+                    Ok(p) => Some((Ok(p), Some(false))),
+                    Err(e) => Some((Err(e), None)),
+                },
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    })
+}
+
+/// Where a task was last found in the workflow task lists
+#[derive(Debug, Clone)]
+struct TaskLocation {
+    category: &'static str,
+    /// Token of the page the task was on; `None` for the first page
+    page_token: Option<String>,
+}
+
+/// Handle to a workflow task running on the server
+#[derive(Clone)]
+pub struct TaskHandle<'a> {
+    api: &'a super::CloudreveAPI,
+    id: String,
+    kind: Option<TaskType>,
+    /// Shared by clones, so any of them can skip the search
+    location: Arc<Mutex<Option<TaskLocation>>>,
+}
+
+impl<'a> TaskHandle<'a> {
+    /// Get the task ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the task type, if known
+    pub fn kind(&self) -> Option<&TaskType> {
+        self.kind.as_ref()
+    }
+
+    fn location(&self) -> MutexGuard<'_, Option<TaskLocation>> {
+        self.location.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fetch the current task information
+    ///
+    /// The server has no endpoint for a single task, so the task lists of
+    /// the categories matching the task type are searched. The page the
+    /// task was found on is remembered and checked first on the next call;
+    /// only if the task has moved is the search repeated.
+    pub async fn status(&self) -> Result<TaskResponse, Error> {
+        debug!("Fetching status of task {}", self.id);
+
+        let client = match self.api.client()? {
+            UnifiedClient::V3(_) => {
+                return Err(Error::UnsupportedFeature(
+                    "task tracking".to_string(),
+                    "v3".to_string(),
+                ));
+            }
+            UnifiedClient::V4(client) => client,
+        };
+
+        let hint = self.location().clone();
+        if let Some(location) = hint
+            && let Ok(list) = client
+                .list_workflow_tasks_with_token(
+                    TASK_PAGE_SIZE,
+                    location.category,
+                    location.page_token.as_deref(),
+                )
+                .await
+            && let Some(task) = list.tasks.into_iter().find(|t| t.id == self.id)
+        {
+            return Ok(task);
+        }
+
+        for &category in categories_for(self.kind.as_ref()) {
+            let mut page_token: Option<String> = None;
+            loop {
+                let list = client
+                    .list_workflow_tasks_with_token(TASK_PAGE_SIZE, category, page_token.as_deref())
+                    .await?;
+                if let Some(task) = list.tasks.into_iter().find(|t| t.id == self.id) {
+                    *self.location() = Some(TaskLocation {
+                        category,
+                        page_token,
+                    });
+                    return Ok(task);
+                }
+                page_token = list.pagination.next_token.filter(|t| !t.is_empty());
+                if page_token.is_none() {
+                    break;
+                }
+            }
+        }
+
+        *self.location() = None;
+        Err(Error::InvalidResponse(format!(
+            "Task not found: {}",
+            self.id
+        )))
+    }
+
+    /// Fetch the current progress of the task
+    pub async fn current_progress(&self) -> Result<Progress, Error> {
+        debug!("Fetching progress of task {}", self.id);
+
+        match self.api.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "task tracking".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(client) => client.get_task_progress(&self.id).await,
+        }
+    }
+
+    /// Stream the task's progress every `interval` until it finishes
+    ///
+    /// The stream ends once the task has reached a final state, or after
+    /// yielding the first error.
+    pub fn progress(&self, interval: Duration) -> impl Stream<Item = Result<Progress, Error>> + '_ {
+        progress_stream(interval, || self.status(), || self.current_progress())
+    }
+
+    /// Wait until the task reaches a final state
+    ///
+    /// The status is checked with exponentially growing delays as set in
+    /// `options`. Returns [`Error::Timeout`] if the task is still running
+    /// when the timeout expires.
+    pub async fn wait(&self, options: &WaitOptions) -> Result<TaskOutcome, Error> {
+        debug!("Waiting for task {}", self.id);
+
+        let started = Instant::now();
+        let mut interval = options.initial_interval;
+        loop {
+            if let Some(outcome) = TaskOutcome::from_task(self.status().await?) {
+                return Ok(outcome);
+            }
+
+            let delay = match options.timeout {
+                Some(timeout) => {
+                    let elapsed = started.elapsed();
+                    if elapsed >= timeout {
+                        return Err(Error::Timeout(format!(
+                            "task {} still running after {:?}",
+                            self.id, timeout
+                        )));
+                    }
+                    interval.min(timeout - elapsed)
+                }
+                None => interval,
+            };
+            tokio::time::sleep(delay).await;
+            interval = options.next_interval(interval);
+        }
+    }
+}
+
+impl super::CloudreveAPI {
+    /// Get a handle to a workflow task by ID
+    ///
+    /// Remote download IDs from [`super::RemoteDownload::id`] work here too.
+    pub fn task(&self, id: &str) -> TaskHandle<'_> {
+        TaskHandle {
+            api: self,
+            id: id.to_string(),
+            kind: None,
+            location: Arc::default(),
+        }
+    }
+
+    /// Get a handle to a task returned by a workflow call
+    pub fn track(&self, task: &TaskResponse) -> TaskHandle<'_> {
+        TaskHandle {
+            api: self,
+            id: task.id.clone(),
+            kind: Some(task.r#type.clone()),
+            location: Arc::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn task(status: &str) -> TaskResponse {
+        serde_json::from_value(serde_json::json!({
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:05:00Z",
+            "id": "task-1",
+            "status": status,
+            "type": "extract_archive"
+        }))
+        .unwrap()
+    }
+
+    fn progress(current: i64) -> Progress {
+        Progress {
+            total: Some(10),
+            current: Some(current),
+            identifier: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_ends_when_task_finishes() {
+        let checks = AtomicUsize::new(0);
+        let items: Vec<_> = progress_stream(
+            Duration::ZERO,
+            || {
+                let n = checks.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(task(if n < 2 { "processing" } else { "completed" })) }
+            },
+            || async { Ok(progress(5)) },
+        )
+        .collect()
+        .await;
+
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_progress_ends_after_progress_error() {
+        let checks = AtomicUsize::new(0);
+        let items: Vec<_> = progress_stream(
+            Duration::ZERO,
+            || async {
+                checks.fetch_add(1, Ordering::SeqCst);
+                Ok(task("processing"))
+            },
+            || async { Err(Error::InvalidResponse("broken".to_string())) },
+        )
+        .collect()
+        .await;
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+        assert_eq!(checks.load(Ordering::SeqCst), 1);
+    }
+}
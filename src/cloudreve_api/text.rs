@@ -0,0 +1,309 @@
+//! Text file reading and editing for CloudreveAPI
+//!
+//! [`CloudreveAPI::read_text`] decodes a file and remembers the version it
+//! was read at; [`CloudreveAPI::write_text`] refuses to overwrite the file
+//! with [`Error::Conflict`] if it changed since. On v4 the version is the
+//! file's primary entity, which the server checks as well; v3 has no
+//! versions, so the modification date and size stand in for it.
+
+use crate::Error;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::file::FileInfo;
+use encoding_rs::{Encoding, GB18030, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use log::debug;
+
+/// A decoded text file
+#[derive(Debug, Clone)]
+pub struct TextFile {
+    pub content: String,
+    /// Encoding the content was detected in
+    pub encoding: &'static Encoding,
+    /// Whether the file started with a byte order mark
+    pub bom: bool,
+    /// Version the content was read at, for [`WriteTextOptions::expected_version`]
+    pub version: String,
+}
+
+impl TextFile {
+    /// Options to write edited content back in the same encoding,
+    /// guarded by the version it was read at
+    pub fn write_options(&self) -> WriteTextOptions {
+        WriteTextOptions {
+            expected_version: Some(self.version.clone()),
+            encoding: self.encoding,
+            bom: self.bom,
+        }
+    }
+}
+
+/// Options for [`CloudreveAPI::write_text`](super::CloudreveAPI::write_text)
+#[derive(Debug, Clone)]
+pub struct WriteTextOptions {
+    /// Fail with [`Error::Conflict`] unless the file is at this version;
+    /// `None` overwrites unconditionally
+    pub expected_version: Option<String>,
+    /// Encoding to write in; UTF-8 by default
+    pub encoding: &'static Encoding,
+    /// Start the file with a byte order mark; only written for UTF-8 and
+    /// UTF-16
+    pub bom: bool,
+}
+
+impl Default for WriteTextOptions {
+    fn default() -> Self {
+        Self {
+            expected_version: None,
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+/// Detect the encoding of raw bytes and decode them
+///
+/// A byte order mark wins; otherwise valid UTF-8 is taken as UTF-8, then
+/// GB18030 is tried, and windows-1252 is the last resort as it decodes any
+/// input.
+pub(crate) fn decode_text(bytes: &[u8]) -> (String, &'static Encoding, bool) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (content, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (content.into_owned(), encoding, true);
+    }
+    for encoding in [UTF_8, GB18030] {
+        if let Some(content) = encoding.decode_without_bom_handling_and_without_replacement(bytes) {
+            return (content.into_owned(), encoding, false);
+        }
+    }
+    let (content, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+    (content.into_owned(), WINDOWS_1252, false)
+}
+
+/// Encode text for writing as set in `options`
+///
+/// encoding_rs only decodes UTF-16, so it is encoded here.
+pub(crate) fn encode_text(content: &str, options: &WriteTextOptions) -> Vec<u8> {
+    if options.encoding == UTF_16LE || options.encoding == UTF_16BE {
+        let little_endian = options.encoding == UTF_16LE;
+        let mut bytes = Vec::with_capacity(content.len() * 2 + 2);
+        let units = options.bom.then_some(0xFEFF).into_iter();
+        for unit in units.chain(content.encode_utf16()) {
+            if little_endian {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return bytes;
+    }
+
+    let (encoded, encoding, _) = options.encoding.encode(content);
+    let mut bytes = Vec::with_capacity(encoded.len() + 3);
+    if options.bom && encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    bytes.extend_from_slice(&encoded);
+    bytes
+}
+
+/// Check whether a v4 server rejected a write because the file moved on
+/// from the `previous` version
+fn is_stale_version(error: &Error) -> bool {
+    match error {
+        Error::Api { code, message } => {
+            matches!(code, 409 | 40076) || message.to_lowercase().contains("stale")
+        }
+        _ => false,
+    }
+}
+
+/// Version of a file as compared by the guard
+fn file_version(info: &FileInfo) -> String {
+    match info {
+        FileInfo::V3(obj) => format!("{}:{}", obj.date, obj.size),
+        FileInfo::V4(file) => file.primary_entity.clone().unwrap_or_default(),
+    }
+}
+
+impl super::CloudreveAPI {
+    /// Fetch file information from the server, bypassing the caches
+    async fn fresh_file_info(&self, path: &str) -> Result<FileInfo, Error> {
+        self.invalidate_cached(path);
+        self.backend().get_file_info(path).await
+    }
+
+    /// Read a text file, detecting its encoding
+    ///
+    /// The returned [`TextFile::version`] guards a later
+    /// [`Self::write_text`] through [`TextFile::write_options`].
+    pub async fn read_text(&self, path: &str) -> Result<TextFile, Error> {
+        debug!("Reading text file: {}", path);
+
+        let info = self.fresh_file_info(path).await?;
+        let bytes = match (self.client()?, &info) {
+            (UnifiedClient::V3(client), FileInfo::V3(obj)) => {
+                client.get_file_content(&obj.id).await?.bytes().await?
+            }
+            (UnifiedClient::V4(client), FileInfo::V4(_)) => {
+                let url = self.download_file(path).await?;
+                client
+                    .http_client
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await?
+            }
+            (client, _) => {
+                return Err(Error::UnsupportedFeature(
+                    "reading text with file info of another API version".to_string(),
+                    client.api_version().to_string(),
+                ));
+            }
+        };
+
+        let (content, encoding, bom) = decode_text(&bytes);
+        Ok(TextFile {
+            content,
+            encoding,
+            bom,
+            version: file_version(&info),
+        })
+    }
+
+    /// Replace the content of a text file
+    ///
+    /// Fails with [`Error::Conflict`] if
+    /// [`WriteTextOptions::expected_version`] is set and the file is at
+    /// another version, including when a v4 server notices the change
+    /// itself. Returns the new version.
+    pub async fn write_text(
+        &self,
+        path: &str,
+        content: &str,
+        options: &WriteTextOptions,
+    ) -> Result<String, Error> {
+        debug!("Writing text file: {}", path);
+
+        let info = self.fresh_file_info(path).await?;
+        let current = file_version(&info);
+        if let Some(expected) = &options.expected_version
+            && *expected != current
+        {
+            return Err(Error::Conflict(format!(
+                "{} is at version {}, expected {}",
+                path, current, expected
+            )));
+        }
+
+        let bytes = encode_text(content, options);
+        let version = match (self.client()?, &info) {
+            (UnifiedClient::V3(client), FileInfo::V3(obj)) => {
+                client.update_file_content(&obj.id, bytes).await?;
+                file_version(&self.fresh_file_info(path).await?)
+            }
+            (UnifiedClient::V4(client), FileInfo::V4(_)) => {
+                let previous = options
+                    .expected_version
+                    .as_deref()
+                    .filter(|version| !version.is_empty());
+                let file = match client.put_file_content(path, bytes, previous).await {
+                    Ok(file) => file,
+                    Err(e) if previous.is_some() && is_stale_version(&e) => {
+                        self.invalidate_cached(path);
+                        return Err(Error::Conflict(format!(
+                            "{} changed since version {}: {}",
+                            path,
+                            previous.unwrap_or_default(),
+                            e
+                        )));
+                    }
+                    Err(e) => return Err(e),
+                };
+                self.invalidate_cached(path);
+                file.primary_entity.unwrap_or_default()
+            }
+            (client, _) => {
+                return Err(Error::UnsupportedFeature(
+                    "writing text with file info of another API version".to_string(),
+                    client.api_version().to_string(),
+                ));
+            }
+        };
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_detects_encodings() {
+        assert_eq!(
+            decode_text("héllo".as_bytes()),
+            ("héllo".to_string(), UTF_8, false)
+        );
+
+        let (content, encoding, bom) = decode_text(b"\xEF\xBB\xBFhi");
+        assert_eq!((content.as_str(), encoding, bom), ("hi", UTF_8, true));
+
+        let (content, encoding, bom) = decode_text(b"\xFF\xFEh\x00i\x00");
+        assert_eq!(
+            (content.as_str(), encoding, bom),
+            ("hi", encoding_rs::UTF_16LE, true)
+        );
+
+        let (gbk, _, _) = GB18030.encode("中文");
+        let (content, encoding, _) = decode_text(&gbk);
+        assert_eq!((content.as_str(), encoding), ("中文", GB18030));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let options = WriteTextOptions {
+            encoding: GB18030,
+            ..Default::default()
+        };
+        let bytes = encode_text("中文", &options);
+        assert_eq!(decode_text(&bytes).0, "中文");
+
+        let with_bom = WriteTextOptions {
+            bom: true,
+            ..Default::default()
+        };
+        assert_eq!(encode_text("hi", &with_bom), b"\xEF\xBB\xBFhi");
+    }
+
+    #[test]
+    fn test_encode_utf16() {
+        let options = WriteTextOptions {
+            encoding: UTF_16LE,
+            bom: true,
+            ..Default::default()
+        };
+        let bytes = encode_text("hi", &options);
+        assert_eq!(bytes, b"\xFF\xFEh\x00i\x00");
+        assert_eq!(decode_text(&bytes), ("hi".to_string(), UTF_16LE, true));
+
+        let options = WriteTextOptions {
+            encoding: UTF_16BE,
+            ..Default::default()
+        };
+        assert_eq!(encode_text("hi", &options), b"\x00h\x00i");
+    }
+
+    #[test]
+    fn test_stale_version_errors() {
+        let stale = Error::Api {
+            code: 40076,
+            message: "File is updated during your edit".to_string(),
+        };
+        assert!(is_stale_version(&stale));
+        let other = Error::Api {
+            code: 40016,
+            message: "Object not exist".to_string(),
+        };
+        assert!(!is_stale_version(&other));
+    }
+}
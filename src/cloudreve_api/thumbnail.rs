@@ -0,0 +1,306 @@
+//! Thumbnails and previews for CloudreveAPI
+//!
+//! Both return the image or file bytes together with their content type.
+//! v3 servers serve thumbnails and previews by file ID, v4 servers hand out
+//! a signed thumbnail URL and preview through the download URL. Thumbnails
+//! are only requested for extensions listed in the site's `thumb_exts` and
+//! can be kept in a local cache directory. The cache is never cleaned up on
+//! its own; call [`ThumbnailOptions::prune_cache`] now and then.
+
+use crate::Error;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::capabilities::Capabilities;
+use crate::cloudreve_api::entry::{Entry, EntryKind, RawEntry, guess_mime};
+use log::debug;
+use reqwest::header::CONTENT_TYPE;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Content type assumed for thumbnails served without one
+const DEFAULT_THUMB_TYPE: &str = "image/jpeg";
+
+/// Content type assumed for previews of unknown file types
+const DEFAULT_PREVIEW_TYPE: &str = "application/octet-stream";
+
+/// Bytes of an image or file with their content type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaContent {
+    pub data: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Options for [`CloudreveAPI::get_thumbnail`](super::CloudreveAPI::get_thumbnail)
+#[derive(Debug, Clone, Default)]
+pub struct ThumbnailOptions {
+    /// Requested width in pixels; v4 only, v3 uses the site's size
+    pub width: Option<u32>,
+    /// Requested height in pixels; v4 only, v3 uses the site's size
+    pub height: Option<u32>,
+    /// Directory to cache thumbnails in; no caching when `None`
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl ThumbnailOptions {
+    /// Delete cached thumbnails written more than `max_age` ago
+    ///
+    /// Only `*.thumb` files in [`Self::cache_dir`] are touched. Returns the
+    /// number of files deleted; does nothing without a cache directory.
+    pub fn prune_cache(&self, max_age: Duration) -> Result<usize, Error> {
+        let Some(dir) = &self.cache_dir else {
+            return Ok(0);
+        };
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "thumb") {
+                continue;
+            }
+            let age = std::fs::metadata(&path)?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age >= max_age {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is the same in every
+/// Rust release
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Name of the cache file for a thumbnail
+///
+/// The modification time is part of the key, so a changed file gets a new
+/// thumbnail.
+fn cache_file_name(base_url: &str, entry: &Entry, options: &ThumbnailOptions) -> String {
+    let key = format!(
+        "{}\n{}\n{}\n{:?}\n{:?}",
+        base_url,
+        entry.path,
+        entry.updated_at.timestamp_millis(),
+        options.width,
+        options.height
+    );
+    format!("{:016x}.thumb", fnv1a(key.as_bytes()))
+}
+
+/// Read a cached thumbnail: the content type on the first line, then the data
+fn read_cached(file: &Path) -> Option<MediaContent> {
+    let raw = std::fs::read(file).ok()?;
+    let split = raw.iter().position(|b| *b == b'\n')?;
+    let content_type = String::from_utf8(raw[..split].to_vec()).ok()?;
+    Some(MediaContent {
+        data: raw[split + 1..].to_vec(),
+        content_type,
+    })
+}
+
+/// Write a thumbnail in the format [`read_cached`] expects
+fn write_cached(file: &Path, content: &MediaContent) -> Result<(), Error> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut raw = Vec::with_capacity(content.content_type.len() + 1 + content.data.len());
+    raw.extend_from_slice(content.content_type.as_bytes());
+    raw.push(b'\n');
+    raw.extend_from_slice(&content.data);
+    std::fs::write(file, raw)?;
+    Ok(())
+}
+
+/// Read a response body along with its content type
+async fn read_content(
+    response: reqwest::Response,
+    fallback_type: &str,
+) -> Result<MediaContent, Error> {
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| fallback_type.to_string());
+    Ok(MediaContent {
+        data: response.bytes().await?.to_vec(),
+        content_type,
+    })
+}
+
+impl super::CloudreveAPI {
+    /// Get the thumbnail image of a file
+    ///
+    /// Fails with [`Error::UnsupportedFeature`] if the file has no thumbnail
+    /// on v3, or the site does not generate thumbnails for its extension on
+    /// v4. With [`ThumbnailOptions::cache_dir`] set, thumbnails are read from
+    /// and stored in that directory, which is not pruned automatically.
+    pub async fn get_thumbnail(
+        &self,
+        path: &str,
+        options: &ThumbnailOptions,
+    ) -> Result<MediaContent, Error> {
+        debug!("Getting thumbnail for file: {}", path);
+
+        let client = self.client()?;
+        let entry = self.get_file_info(path).await?.to_entry()?;
+        if entry.is_folder() {
+            return Err(Error::InvalidResponse(format!(
+                "Not a file: {}",
+                entry.path
+            )));
+        }
+
+        let cache_file = options
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join(cache_file_name(&self.base_url, &entry, options)));
+        if let Some(cached) = cache_file.as_deref().and_then(read_cached) {
+            debug!("Thumbnail cache hit: {}", path);
+            return Ok(cached);
+        }
+
+        let content = match client {
+            UnifiedClient::V3(client) => {
+                let RawEntry::V3(obj) = &entry.raw else {
+                    return Err(Error::UnsupportedFeature(
+                        "thumbnails of v4 files".to_string(),
+                        "v3".to_string(),
+                    ));
+                };
+                if !obj.thumb {
+                    return Err(Error::UnsupportedFeature(
+                        format!("thumbnail for {}", entry.name),
+                        "v3".to_string(),
+                    ));
+                }
+                let response = client.get_thumbnail(&obj.id).await?;
+                read_content(response, DEFAULT_THUMB_TYPE).await?
+            }
+            UnifiedClient::V4(client) => {
+                let config = self.get_site_config(Some("thumb")).await?;
+                let caps = Capabilities::new(self.api_version(), None, Some(&config));
+                if !caps.can_thumbnail(&entry.name) {
+                    return Err(Error::UnsupportedFeature(
+                        format!("thumbnail for {}", entry.name),
+                        "v4".to_string(),
+                    ));
+                }
+
+                let url = client
+                    .get_thumbnail_url(&entry.path, options.width, options.height)
+                    .await?;
+                let url = if url.starts_with('/') {
+                    format!("{}{}", self.base_url.trim_end_matches('/'), url)
+                } else {
+                    url
+                };
+                let response = client
+                    .http_client
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                read_content(response, DEFAULT_THUMB_TYPE).await?
+            }
+        };
+
+        if let Some(file) = &cache_file
+            && let Err(e) = write_cached(file, &content)
+        {
+            debug!("Could not cache thumbnail {}: {}", file.display(), e);
+        }
+        Ok(content)
+    }
+
+    /// Get the preview content of a file
+    ///
+    /// Returns the file as the server serves it for in-browser preview.
+    pub async fn preview_file(&self, path: &str) -> Result<MediaContent, Error> {
+        debug!("Previewing file: {}", path);
+
+        let client = self.client()?;
+        let entry = self.get_file_info(path).await?.to_entry()?;
+        let fallback_type = guess_mime(EntryKind::File, &entry.name)
+            .unwrap_or_else(|| DEFAULT_PREVIEW_TYPE.to_string());
+
+        let response = match client {
+            UnifiedClient::V3(client) => {
+                let RawEntry::V3(obj) = &entry.raw else {
+                    return Err(Error::UnsupportedFeature(
+                        "previews of v4 files".to_string(),
+                        "v3".to_string(),
+                    ));
+                };
+                client.preview_file(&obj.id).await?
+            }
+            UnifiedClient::V4(client) => {
+                let url = self.download_file(&entry.path).await?;
+                client
+                    .http_client
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+            }
+        };
+        read_content(response, &fallback_type).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("cloudreve-thumbs-{}", std::process::id()));
+        let file = dir.join("a.thumb");
+        let content = MediaContent {
+            data: vec![0xff, 0xd8, b'\n', 0x00],
+            content_type: "image/jpeg".to_string(),
+        };
+
+        write_cached(&file, &content).unwrap();
+        assert_eq!(read_cached(&file), Some(content));
+        assert_eq!(read_cached(&dir.join("missing.thumb")), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_key_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_prune_cache() {
+        let dir = std::env::temp_dir().join(format!("cloudreve-prune-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.thumb"), b"image/jpeg\n").unwrap();
+        std::fs::write(dir.join("keep.txt"), b"").unwrap();
+        let options = ThumbnailOptions {
+            cache_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+
+        assert_eq!(options.prune_cache(Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(options.prune_cache(Duration::ZERO).unwrap(), 1);
+        assert!(!dir.join("a.thumb").exists());
+        assert!(dir.join("keep.txt").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,562 @@
+//! Cross-instance transfer between two CloudreveAPI clients
+//!
+//! Files are streamed from the source download URL straight into a chunked
+//! upload session on the destination, so nothing is written to local disk.
+//! The source and destination may speak different API versions, which makes
+//! this suitable for migrating data from a v3 server to a v4 server.
+//!
+//! Progress can be persisted to a checkpoint file so that an interrupted
+//! transfer resumes where it stopped instead of starting over.
+
+use crate::Error;
+use crate::api::v3::models as v3_models;
+use crate::api::v4::models as v4_models;
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::CloudreveAPI;
+use crate::cloudreve_api::file::{FileItem, FileListAll};
+use crate::cloudreve_api::move_copy::{Destination, staging_name};
+use crate::cloudreve_api::path::{join_path, normalize_path, split_path};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Options controlling a tree transfer
+#[derive(Debug, Clone, Default)]
+pub struct TransferOptions {
+    /// File used to record completed files so an interrupted transfer can resume
+    pub checkpoint: Option<PathBuf>,
+    /// Replace destination files whose size differs from the source
+    pub overwrite: bool,
+}
+
+/// Persisted progress of a tree transfer
+///
+/// Completed files are stored as paths relative to the source root.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransferCheckpoint {
+    pub src_root: String,
+    pub dest_root: String,
+    pub completed: BTreeSet<String>,
+}
+
+impl TransferCheckpoint {
+    /// Create an empty checkpoint for a transfer between two roots
+    pub fn new(src_root: &str, dest_root: &str) -> Self {
+        Self {
+            src_root: normalize_path(src_root).to_string(),
+            dest_root: normalize_path(dest_root).to_string(),
+            completed: BTreeSet::new(),
+        }
+    }
+
+    /// Load a checkpoint from disk
+    ///
+    /// Returns `None` when the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        match std::fs::read(path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the checkpoint to disk
+    ///
+    /// The file is replaced atomically so an interruption never leaves a
+    /// truncated checkpoint behind.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Check whether this checkpoint belongs to a transfer between these roots
+    pub fn matches(&self, src_root: &str, dest_root: &str) -> bool {
+        self.src_root == normalize_path(src_root) && self.dest_root == normalize_path(dest_root)
+    }
+}
+
+/// Result of a tree transfer
+#[derive(Debug, Default)]
+pub struct TransferReport {
+    pub files_transferred: usize,
+    pub files_skipped: usize,
+    pub directories_created: usize,
+    pub bytes_transferred: u64,
+    pub errors: Vec<(String, String)>,
+}
+
+/// A source object collected while walking the tree
+#[derive(Debug, Clone)]
+struct RemoteEntry {
+    name: String,
+    is_folder: bool,
+    size: i64,
+    /// Last modification time in Unix milliseconds, if the server reported one
+    modified: Option<u64>,
+}
+
+/// Parse an RFC 3339 timestamp into Unix milliseconds
+fn parse_modified(value: &str) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .and_then(|time| u64::try_from(time.timestamp_millis()).ok())
+}
+
+/// Collect the entries of a listing together with their modification times
+fn remote_entries(list: &FileListAll) -> Vec<RemoteEntry> {
+    match list {
+        FileListAll::V3(d) => d
+            .objects
+            .iter()
+            .map(|obj| RemoteEntry {
+                name: obj.name.clone(),
+                is_folder: obj.object_type == "dir",
+                size: obj.size,
+                modified: parse_modified(&obj.date),
+            })
+            .collect(),
+        FileListAll::V4(r) => r
+            .files
+            .iter()
+            .map(|file| RemoteEntry {
+                name: file.name.clone(),
+                is_folder: matches!(file.r#type, v4_models::FileType::Folder),
+                size: file.size,
+                modified: parse_modified(&file.updated_at),
+            })
+            .collect(),
+    }
+}
+
+/// Join a path relative to the transfer root onto a root directory
+fn join_relative(root: &str, relative: &str) -> String {
+    if relative.is_empty() {
+        normalize_path(root).to_string()
+    } else {
+        join_path(root, relative)
+    }
+}
+
+/// Transfer methods for CloudreveAPI
+impl super::CloudreveAPI {
+    /// Copy a directory tree from this instance to another one
+    ///
+    /// Every file below `src_path` is streamed from this client into
+    /// `dest_path` on `dest`, recreating the folder structure. File
+    /// modification times are preserved; Cloudreve offers no API to set
+    /// folder times, so folders get the time they were created.
+    ///
+    /// Files already present at the destination with the same size are
+    /// skipped. A file with a different size is only replaced when
+    /// `options.overwrite` is set; otherwise an [`Error::AlreadyExists`] is
+    /// recorded for it. When `options.checkpoint` is set, completed files are
+    /// recorded after each upload and skipped on the next run.
+    ///
+    /// Failures on individual files or directories are collected in
+    /// [`TransferReport::errors`] and the transfer continues.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cloudreve_api::{CloudreveAPI, TransferOptions};
+    /// # async fn example(old: &CloudreveAPI, new: &CloudreveAPI) -> cloudreve_api::Result<()> {
+    /// let options = TransferOptions {
+    ///     checkpoint: Some("migration.json".into()),
+    ///     ..Default::default()
+    /// };
+    /// let report = old.transfer_tree("/photos", new, "/photos", &options).await?;
+    /// println!("{} files copied", report.files_transferred);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn transfer_tree(
+        &self,
+        src_path: &str,
+        dest: &CloudreveAPI,
+        dest_path: &str,
+        options: &TransferOptions,
+    ) -> Result<TransferReport, Error> {
+        debug!(
+            "Transferring tree {} from {} to {} on {}",
+            src_path, self.base_url, dest_path, dest.base_url
+        );
+
+        let src_root = normalize_path(src_path);
+        let dest_root = normalize_path(dest_path);
+        if !self.is_existing_dir(src_root).await? {
+            return Err(Error::InvalidResponse(format!(
+                "Source is not a directory: {}",
+                src_root
+            )));
+        }
+
+        let mut checkpoint = match &options.checkpoint {
+            Some(file) => match TransferCheckpoint::load(file)? {
+                Some(saved) if saved.matches(src_root, dest_root) => saved,
+                Some(saved) => {
+                    return Err(Error::InvalidResponse(format!(
+                        "Checkpoint {} belongs to transfer {} -> {}",
+                        file.display(),
+                        saved.src_root,
+                        saved.dest_root
+                    )));
+                }
+                None => TransferCheckpoint::new(src_root, dest_root),
+            },
+            None => TransferCheckpoint::new(src_root, dest_root),
+        };
+
+        let mut report = TransferReport::default();
+        let mut pending = vec![String::new()];
+
+        while let Some(relative_dir) = pending.pop() {
+            let src_dir = join_relative(src_root, &relative_dir);
+            let dest_dir = join_relative(dest_root, &relative_dir);
+
+            let existing = match dest.prepare_transfer_dir(&dest_dir, &mut report).await {
+                Ok(existing) => existing,
+                Err(e) => {
+                    report.errors.push((dest_dir, e.to_string()));
+                    continue;
+                }
+            };
+            let entries = match self.list_files_all(&src_dir, None).await {
+                Ok(list) => remote_entries(&list),
+                Err(e) => {
+                    report.errors.push((src_dir, e.to_string()));
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let relative = if relative_dir.is_empty() {
+                    entry.name.clone()
+                } else {
+                    format!("{}/{}", relative_dir, entry.name)
+                };
+
+                if entry.is_folder {
+                    pending.push(relative);
+                    continue;
+                }
+
+                let src_file = join_path(&src_dir, &entry.name);
+                let dest_file = join_path(&dest_dir, &entry.name);
+
+                if checkpoint.completed.contains(&relative) {
+                    report.files_skipped += 1;
+                    continue;
+                }
+
+                let replace = match existing.get(&entry.name) {
+                    None => false,
+                    Some(item) if item.is_folder => {
+                        report
+                            .errors
+                            .push((src_file, format!("Destination is a folder: {}", dest_file)));
+                        continue;
+                    }
+                    Some(item) if item.size == entry.size => {
+                        debug!("Skipping {}: already present at destination", src_file);
+                        report.files_skipped += 1;
+                        checkpoint.completed.insert(relative);
+                        continue;
+                    }
+                    Some(_) if !options.overwrite => {
+                        report
+                            .errors
+                            .push((src_file, Error::AlreadyExists(dest_file).to_string()));
+                        continue;
+                    }
+                    Some(_) => true,
+                };
+
+                match self
+                    .transfer_file(dest, &src_file, &dest_file, &entry, replace)
+                    .await
+                {
+                    Ok(bytes) => {
+                        report.files_transferred += 1;
+                        report.bytes_transferred += bytes;
+                        checkpoint.completed.insert(relative);
+                        if let Some(file) = &options.checkpoint {
+                            checkpoint.save(file)?;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to transfer {}: {}", src_file, e);
+                        report.errors.push((src_file, e.to_string()));
+                    }
+                }
+            }
+        }
+
+        if let Some(file) = &options.checkpoint {
+            checkpoint.save(file)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Make sure a destination directory exists and list what it contains
+    async fn prepare_transfer_dir(
+        &self,
+        dir: &str,
+        report: &mut TransferReport,
+    ) -> Result<HashMap<String, FileItem>, Error> {
+        if normalize_path(dir) != "/" {
+            match self.lookup(dir).await? {
+                Some(info) if info.is_folder() => {}
+                Some(_) => {
+                    return Err(Error::InvalidResponse(format!(
+                        "Destination is not a directory: {}",
+                        dir
+                    )));
+                }
+                None => {
+                    self.create_directory(dir).await?;
+                    report.directories_created += 1;
+                    return Ok(HashMap::new());
+                }
+            }
+        }
+
+        Ok(self
+            .list_files_all(dir, None)
+            .await?
+            .items()
+            .into_iter()
+            .map(|item| (item.name.clone(), item))
+            .collect())
+    }
+
+    /// Stream a single file into an upload session on `dest`
+    ///
+    /// When `replace` is set, the file is uploaded under a staging name and
+    /// moved over the existing file once complete. Returns the number of
+    /// bytes transferred.
+    async fn transfer_file(
+        &self,
+        dest: &CloudreveAPI,
+        src_file: &str,
+        dest_file: &str,
+        entry: &RemoteEntry,
+        replace: bool,
+    ) -> Result<u64, Error> {
+        debug!("Transferring file {} to {}", src_file, dest_file);
+
+        let upload_path = if replace {
+            join_path(split_path(dest_file).0, &staging_name("transfer"))
+        } else {
+            dest_file.to_string()
+        };
+
+        let url = self.download_file(src_file).await?;
+        let response = self
+            .client()?
+            .http_client()
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let size = u64::try_from(entry.size).unwrap_or_default();
+        let sent = dest
+            .upload_response(response, src_file, &upload_path, size, entry.modified)
+            .await?;
+
+        if replace {
+            dest.move_to(&upload_path, Destination::Path(dest_file), true)
+                .await?;
+        }
+
+        Ok(sent)
+    }
+}
+
+/// Streaming uploads shared by transfers and share imports
+impl super::CloudreveAPI {
+    /// Stream a download response into a new file at `path`
+    ///
+    /// `source` names the origin in errors. Fails if the response does not
+    /// contain exactly `size` bytes; the upload session is discarded on any
+    /// failure. Returns the number of bytes uploaded.
+    pub(super) async fn upload_response(
+        &self,
+        mut response: reqwest::Response,
+        source: &str,
+        path: &str,
+        size: u64,
+        modified: Option<u64>,
+    ) -> Result<u64, Error> {
+        let upload = ChunkedUpload::begin(self, path, size, modified).await?;
+
+        let streamed = async {
+            let mut buffer = Vec::with_capacity(upload.chunk_size.min(size as usize));
+            let mut index = 0;
+            let mut sent = 0;
+            while let Some(bytes) = response.chunk().await? {
+                buffer.extend_from_slice(&bytes);
+                while buffer.len() >= upload.chunk_size {
+                    let rest = buffer.split_off(upload.chunk_size);
+                    let chunk = std::mem::replace(&mut buffer, rest);
+                    sent += chunk.len() as u64;
+                    upload.send(index, chunk).await?;
+                    index += 1;
+                }
+            }
+            // Empty files still need a single empty chunk to complete the session
+            if !buffer.is_empty() || index == 0 {
+                sent += buffer.len() as u64;
+                upload.send(index, buffer).await?;
+            }
+            if sent != size {
+                return Err(Error::InvalidResponse(format!(
+                    "Source {} changed during transfer: expected {} bytes, got {}",
+                    source, size, sent
+                )));
+            }
+            upload.finish().await?;
+            Ok(sent)
+        }
+        .await;
+
+        match streamed {
+            Ok(sent) => Ok(sent),
+            Err(e) => {
+                upload.abort().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// An upload session on the destination of a transfer
+struct ChunkedUpload<'a> {
+    api: &'a CloudreveAPI,
+    path: String,
+    session_id: String,
+    chunk_size: usize,
+}
+
+impl<'a> ChunkedUpload<'a> {
+    /// Open an upload session for a file of `size` bytes
+    async fn begin(
+        api: &'a CloudreveAPI,
+        path: &str,
+        size: u64,
+        modified: Option<u64>,
+    ) -> Result<Self, Error> {
+        let (dir, name) = split_path(path);
+        let policy_id = api.default_policy_id(dir).await?;
+
+        let (session_id, chunk_size) = match api.client()? {
+            UnifiedClient::V3(client) => {
+                let request = v3_models::UploadFileRequest {
+                    path: dir,
+                    name,
+                    policy_id: &policy_id,
+                    size: size as i64,
+                    last_modified: modified.unwrap_or_default() as i64,
+                    mime_type: "",
+                };
+                let session = client.upload_file(&request).await?;
+                (session.session_id, session.chunk_size.max(0) as u64)
+            }
+            UnifiedClient::V4(client) => {
+                let request = v4_models::CreateUploadSessionRequest {
+                    uri: &path_to_uri(path),
+                    size,
+                    policy_id: &policy_id,
+                    last_modified: modified,
+                    mime_type: None,
+                    metadata: None,
+                    entity_type: None,
+                };
+                let session = client.create_upload_session(&request).await?;
+                if session
+                    .upload_urls
+                    .as_ref()
+                    .is_some_and(|urls| !urls.is_empty())
+                {
+                    let _ = client
+                        .delete_upload_session(path, &session.session_id)
+                        .await;
+                    return Err(Error::UnsupportedFeature(
+                        "transfer to storage policies with direct upload URLs".to_string(),
+                        "v4".to_string(),
+                    ));
+                }
+                (session.session_id, session.chunk_size)
+            }
+        };
+
+        // A chunk size of 0 means the policy expects the whole file at once
+        let chunk_size = if chunk_size == 0 {
+            size.max(1)
+        } else {
+            chunk_size
+        };
+
+        Ok(Self {
+            api,
+            path: path.to_string(),
+            session_id,
+            chunk_size: usize::try_from(chunk_size).unwrap_or(usize::MAX),
+        })
+    }
+
+    /// Upload one chunk of the file
+    async fn send(&self, index: u32, data: Vec<u8>) -> Result<(), Error> {
+        match self.api.client()? {
+            UnifiedClient::V3(client) => client.upload_chunk(&self.session_id, index, data).await,
+            UnifiedClient::V4(client) => {
+                client
+                    .upload_file_chunk(&self.session_id, index, &data)
+                    .await
+            }
+        }
+    }
+
+    /// Complete the upload session
+    async fn finish(&self) -> Result<(), Error> {
+        self.api.invalidate_cached(&self.path);
+
+        match self.api.client()? {
+            UnifiedClient::V3(client) => {
+                // Only some storage policies need an explicit completion call
+                if let Err(e) = client.complete_upload(&self.session_id).await {
+                    debug!("complete_upload not needed or already completed: {}", e);
+                }
+                Ok(())
+            }
+            UnifiedClient::V4(_) => Ok(()),
+        }
+    }
+
+    /// Discard the upload session after a failure
+    async fn abort(&self) {
+        // Sessions are only opened through a built-in client
+        let Ok(client) = self.api.client() else {
+            return;
+        };
+        let result = match client {
+            UnifiedClient::V3(client) => client.delete_upload_session(&self.session_id).await,
+            UnifiedClient::V4(client) => {
+                client
+                    .delete_upload_session(&self.path, &self.session_id)
+                    .await
+            }
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to discard upload session {}: {}",
+                self.session_id, e
+            );
+        }
+    }
+}
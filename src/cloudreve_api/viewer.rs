@@ -0,0 +1,340 @@
+//! File viewer sessions for CloudreveAPI
+//!
+//! v4 sites configure viewers per extension in the site config's
+//! `file_viewers`: built-in viewers of the web UI, WOPI editors such as
+//! Collabora or OnlyOffice, and custom viewers with a URL template.
+//! [`CloudreveAPI::open_in_viewer`] picks the viewer for a file, creates a
+//! viewer session and builds a URL that can be opened or embedded.
+
+use crate::Error;
+use crate::api::v4::models::{CreateViewerSessionRequest, FileViewer, SiteConfig};
+use crate::api::v4::uri::path_to_uri;
+use crate::client::UnifiedClient;
+use crate::cloudreve_api::entry::{extension, parse_timestamp};
+use crate::cloudreve_api::site::SiteConfigValue;
+use log::debug;
+
+/// What the file is opened for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViewerAction {
+    View,
+    Edit,
+    /// Read-only view for embedding in another page
+    EmbedView,
+}
+
+impl ViewerAction {
+    /// Get the action as the API and WOPI discovery spell it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViewerAction::View => "view",
+            ViewerAction::Edit => "edit",
+            ViewerAction::EmbedView => "embedview",
+        }
+    }
+}
+
+/// How a viewer is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViewerKind {
+    /// Runs inside the Cloudreve web UI
+    Builtin,
+    /// WOPI client; the URL must be opened with a form POST of
+    /// [`ViewerLaunch::form_fields`]
+    Wopi,
+    /// Third-party page built from a URL template
+    Custom,
+}
+
+impl ViewerKind {
+    fn of(viewer: &FileViewer) -> Self {
+        match viewer.viewer_type.as_str() {
+            "wopi" => ViewerKind::Wopi,
+            "custom" => ViewerKind::Custom,
+            "builtin" => ViewerKind::Builtin,
+            _ if viewer.wopi_actions.is_some() => ViewerKind::Wopi,
+            _ if viewer.url.is_some() => ViewerKind::Custom,
+            _ => ViewerKind::Builtin,
+        }
+    }
+}
+
+/// A viewer ready to open a file
+#[derive(Debug, Clone)]
+pub struct ViewerLaunch {
+    pub viewer: FileViewer,
+    pub kind: ViewerKind,
+    pub url: String,
+    pub session_id: String,
+    pub access_token: Option<String>,
+    /// Expiry of the access token in milliseconds since the epoch
+    pub access_token_ttl: Option<i64>,
+}
+
+impl ViewerLaunch {
+    /// Form fields to POST to [`ViewerLaunch::url`] for WOPI viewers
+    ///
+    /// Empty for other viewer kinds, which are opened with a plain GET.
+    pub fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if self.kind != ViewerKind::Wopi {
+            return fields;
+        }
+        if let Some(token) = &self.access_token {
+            fields.push(("access_token", token.clone()));
+        }
+        if let Some(ttl) = self.access_token_ttl {
+            fields.push(("access_token_ttl", ttl.to_string()));
+        }
+        fields
+    }
+}
+
+/// Pick the viewer for a file
+///
+/// Viewer groups are searched as well. Disabled viewers and viewers whose
+/// size limit the file exceeds are skipped. Of the rest, WOPI and custom
+/// viewers come before built-in ones, which only run inside the web UI;
+/// then the one with the highest priority wins, the first one listed on
+/// ties.
+pub fn pick_viewer<'a>(
+    viewers: &'a [FileViewer],
+    file_name: &str,
+    size: u64,
+) -> Option<&'a FileViewer> {
+    let ext = extension(file_name)?;
+    let mut best: Option<&FileViewer> = None;
+    for viewer in viewers {
+        let candidate = if viewer.viewers.is_empty() {
+            let accepts = !viewer.disabled
+                && (viewer.max_size == 0 || size <= viewer.max_size)
+                && viewer
+                    .extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(&ext));
+            accepts.then_some(viewer)
+        } else {
+            pick_viewer(&viewer.viewers, file_name, size)
+        };
+        if let Some(candidate) = candidate
+            && best.is_none_or(|b| launch_rank(candidate) > launch_rank(b))
+        {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+/// Ranking of a viewer: opens outside the web UI first, then priority
+fn launch_rank(viewer: &FileViewer) -> (bool, i32) {
+    (
+        ViewerKind::of(viewer) != ViewerKind::Builtin,
+        viewer.priority,
+    )
+}
+
+/// Build the WOPI client URL for an action
+///
+/// `embedview` falls back to `view` for clients without an embedded view.
+pub(crate) fn wopi_url(
+    viewer: &FileViewer,
+    ext: &str,
+    action: ViewerAction,
+    wopi_src: &str,
+) -> Option<String> {
+    let actions = viewer.wopi_actions.as_ref()?.get(ext)?;
+    let template = actions.get(action.as_str()).or_else(|| match action {
+        ViewerAction::EmbedView => actions.get(ViewerAction::View.as_str()),
+        _ => None,
+    })?;
+
+    let src = urlencoding::encode(wopi_src);
+    if template.contains("{WOPISrc}") {
+        return Some(template.replace("{WOPISrc}", &src));
+    }
+    let separator = if template.contains('?') { '&' } else { '?' };
+    Some(format!("{}{}WOPISrc={}", template, separator, src))
+}
+
+/// Fill the placeholders of a custom viewer URL template
+pub(crate) fn custom_url(template: &str, download_url: &str, file_name: &str) -> String {
+    template
+        .replace("{$src_raw}", download_url)
+        .replace("{$src}", &urlencoding::encode(download_url))
+        .replace("{$name}", &urlencoding::encode(file_name))
+}
+
+impl super::CloudreveAPI {
+    /// Get the viewers configured on the site
+    pub async fn file_viewers(&self) -> Result<Vec<FileViewer>, Error> {
+        debug!("Getting file viewers");
+
+        match self.client()? {
+            UnifiedClient::V3(_) => Err(Error::UnsupportedFeature(
+                "file viewers".to_string(),
+                "v3".to_string(),
+            )),
+            UnifiedClient::V4(_) => match self.get_site_config(Some("explorer")).await? {
+                SiteConfigValue::V4(config) => {
+                    let SiteConfig { file_viewers, .. } = *config;
+                    Ok(file_viewers.unwrap_or_default())
+                }
+                SiteConfigValue::V3(_) => Ok(Vec::new()),
+            },
+        }
+    }
+
+    /// Open a file in the viewer configured for its extension
+    ///
+    /// Creates a viewer session and returns the URL to open. WOPI viewers
+    /// additionally need [`ViewerLaunch::form_fields`] posted to the URL.
+    /// Built-in viewers only run inside the web UI, so they are only picked
+    /// when no WOPI or custom viewer handles the file, and then fail with
+    /// [`Error::UnsupportedFeature`], as does a file no viewer handles.
+    pub async fn open_in_viewer(
+        &self,
+        path: &str,
+        action: ViewerAction,
+    ) -> Result<ViewerLaunch, Error> {
+        debug!("Opening {} in a viewer for {}", path, action.as_str());
+
+        let viewers = self.file_viewers().await?;
+        let UnifiedClient::V4(client) = self.client()? else {
+            return Err(Error::UnsupportedFeature(
+                "file viewers".to_string(),
+                "v3".to_string(),
+            ));
+        };
+
+        let entry = self.get_file_info(path).await?.to_entry()?;
+        let viewer = pick_viewer(&viewers, &entry.name, entry.size)
+            .cloned()
+            .ok_or_else(|| {
+                Error::UnsupportedFeature(format!("viewer for {}", entry.name), "v4".to_string())
+            })?;
+        let kind = ViewerKind::of(&viewer);
+
+        let template = match kind {
+            ViewerKind::Builtin => {
+                return Err(Error::UnsupportedFeature(
+                    format!("opening {} outside the web UI", viewer.name),
+                    "v4".to_string(),
+                ));
+            }
+            ViewerKind::Custom => Some(
+                viewer
+                    .url
+                    .clone()
+                    .filter(|url| !url.is_empty())
+                    .ok_or_else(|| {
+                        Error::InvalidResponse(format!(
+                            "Custom viewer {} has no URL template",
+                            viewer.name
+                        ))
+                    })?,
+            ),
+            ViewerKind::Wopi => None,
+        };
+
+        let uri = path_to_uri(&entry.path);
+        let request = CreateViewerSessionRequest {
+            uri: &uri,
+            viewer_id: (!viewer.id.is_empty()).then_some(viewer.id.as_str()),
+            preferred_action: Some(action.as_str()),
+            version: None,
+        };
+        let session = client.create_viewer_session(&request).await?;
+
+        let url = if let Some(template) = template {
+            custom_url(
+                &template,
+                &self.download_file(&entry.path).await?,
+                &entry.name,
+            )
+        } else {
+            let wopi_src = session.wopi_src.as_deref().ok_or_else(|| {
+                Error::InvalidResponse("Viewer session without WOPI source".to_string())
+            })?;
+            let ext = extension(&entry.name).unwrap_or_default();
+            wopi_url(&viewer, &ext, action, wopi_src).ok_or_else(|| {
+                Error::UnsupportedFeature(
+                    format!("{} with {}", action.as_str(), viewer.name),
+                    "v4".to_string(),
+                )
+            })?
+        };
+
+        let (access_token, access_token_ttl) = match &session.session {
+            Some(s) => (
+                Some(s.access_token.clone()).filter(|t| !t.is_empty()),
+                s.expires
+                    .as_deref()
+                    .and_then(|e| parse_timestamp(e).ok())
+                    .map(|e| e.timestamp_millis()),
+            ),
+            None => (None, None),
+        };
+        Ok(ViewerLaunch {
+            session_id: session.id().to_string(),
+            viewer,
+            kind,
+            url,
+            access_token,
+            access_token_ttl,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn wopi_viewer(template: &str) -> FileViewer {
+        let actions = HashMap::from([(
+            "docx".to_string(),
+            HashMap::from([("view".to_string(), template.to_string())]),
+        )]);
+        FileViewer {
+            viewer_type: "wopi".to_string(),
+            wopi_actions: Some(actions),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_wopi_url() {
+        let src = "https://drive.example.com/api/v4/file/wopi/abc";
+        let encoded = "https%3A%2F%2Fdrive.example.com%2Fapi%2Fv4%2Ffile%2Fwopi%2Fabc";
+
+        let placeholder = wopi_viewer("https://office.example.com/view?WOPISrc={WOPISrc}");
+        assert_eq!(
+            wopi_url(&placeholder, "docx", ViewerAction::View, src).unwrap(),
+            format!("https://office.example.com/view?WOPISrc={}", encoded)
+        );
+
+        let plain = wopi_viewer("https://office.example.com/view?lang=en");
+        assert_eq!(
+            wopi_url(&plain, "docx", ViewerAction::EmbedView, src).unwrap(),
+            format!(
+                "https://office.example.com/view?lang=en&WOPISrc={}",
+                encoded
+            )
+        );
+        assert!(wopi_url(&plain, "docx", ViewerAction::Edit, src).is_none());
+        assert!(wopi_url(&plain, "xlsx", ViewerAction::View, src).is_none());
+    }
+
+    #[test]
+    fn test_custom_url() {
+        let url = custom_url(
+            "https://viewer.example.com/?file={$src}&title={$name}",
+            "https://drive.example.com/dl?a=1&b=2",
+            "my doc.pdf",
+        );
+        assert_eq!(
+            url,
+            "https://viewer.example.com/?file=https%3A%2F%2Fdrive.example.com%2Fdl%3Fa%3D1%26b%3D2&title=my%20doc.pdf"
+        );
+    }
+}
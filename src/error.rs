@@ -35,7 +35,23 @@ pub enum Error {
     #[error("Invalid timestamp: {0}")]
     InvalidTimestamp(String),
 
+    /// Destination object already exists
+    #[error("Destination already exists: {0}")]
+    AlreadyExists(String),
+
     /// Feature not supported in API version
     #[error("Feature '{0}' not supported in API {1}")]
     UnsupportedFeature(String, String),
+
+    /// Value rejected before sending it to the server
+    #[error("Invalid value: {0}")]
+    InvalidValue(String),
+
+    /// Object changed since the version the caller expected
+    #[error("Version conflict: {0}")]
+    Conflict(String),
+
+    /// Operation did not finish in time
+    #[error("Timed out: {0}")]
+    Timeout(String),
 }
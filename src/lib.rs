@@ -42,8 +42,17 @@ pub use api::v4::models::*;
 
 // Main Cloudreve API client
 pub use cloudreve_api::{
-    CloudreveAPI, DeleteResult, DeleteTarget, FileInfo, FileItem, FileList, FileListAll,
-    LoginResponse, SiteConfigValue, TokenInfo, UserInfo, V3LoginResponse, V4LoginResponse,
+    AudioTags, BackendFuture, BatchResult, Capabilities, CloudreveAPI, CloudreveBackend,
+    DavAccountMapping, DeleteResult, DeleteTarget, Destination, DirectLinkItem, Entry, EntryKind,
+    Feature, FileInfo, FileItem, FileList, FileListAll, FileMetadata, GpsLocation, ImageMetadata,
+    ImportReport, ListingCacheConfig, ListingCacheStats, LoginResponse, MediaContent,
+    MediaMetadata, MetadataChange, MetadataKey, MigrationOptions, MigrationPlan, MigrationReport,
+    RawEntry, RelocationPlan, RelocationReport, RemoteDownload, RemoteDownloadFile,
+    RemoteDownloadState, ShareItem, ShareMapping, ShareOptions, SharePage, ShareSession,
+    ShareUpdateProps, SiteConfigValue, StorageQuota, TaskHandle, TaskOutcome, TextFile,
+    ThumbnailOptions, TokenInfo, TransferCheckpoint, TransferOptions, TransferReport, UserInfo,
+    V3Inventory, V3LoginResponse, V4LoginResponse, VideoInfo, ViewerAction, ViewerKind,
+    ViewerLaunch, WaitOptions, WriteTextOptions,
 };
 
 // Legacy exports for backward compatibility
@@ -0,0 +1,81 @@
+mod common;
+
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::{CloudreveAPI, Error, ListingCacheConfig};
+use common::MemoryBackend;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod backend_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_custom_backend_file_operations() {
+        let api = CloudreveAPI::with_backend(Arc::new(MemoryBackend::default()));
+        assert!(api.has_custom_backend());
+        assert_eq!(api.api_version(), ApiVersion::V3);
+        assert_eq!(api.base_url(), "memory://test");
+
+        api.create_directory("/docs").await.unwrap();
+        api.upload_file("/docs/a.txt", b"hello".to_vec(), None)
+            .await
+            .unwrap();
+        api.rename("/docs/a.txt", "b.txt").await.unwrap();
+
+        let list = api.list_files("/docs", None, None).await.unwrap();
+        let names: Vec<String> = list.items().into_iter().map(|item| item.name).collect();
+        assert_eq!(names, vec!["b.txt".to_string()]);
+
+        let info = api.get_file_info("/docs/b.txt").await.unwrap();
+        assert_eq!(info.size(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_custom_backend_move_to_uses_backend() {
+        let api = CloudreveAPI::with_backend(Arc::new(MemoryBackend::default()));
+        api.create_directory("/archive").await.unwrap();
+        api.upload_file("/report.pdf", b"pdf".to_vec(), None)
+            .await
+            .unwrap();
+
+        api.move_file("/report.pdf", "/archive").await.unwrap();
+
+        assert!(api.get_file_info("/archive/report.pdf").await.is_ok());
+        assert!(api.get_file_info("/report.pdf").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listing_cache_applies_to_custom_backend() {
+        let backend = Arc::new(MemoryBackend::default());
+        let api = CloudreveAPI::with_backend(backend.clone());
+        api.enable_listing_cache(ListingCacheConfig::default());
+
+        api.list_files("/", None, None).await.unwrap();
+        api.list_files("/", None, None).await.unwrap();
+        assert_eq!(*backend.list_calls.lock().unwrap(), 1);
+
+        api.create_directory("/new").await.unwrap();
+        api.list_files("/", None, None).await.unwrap();
+        assert_eq!(*backend.list_calls.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_custom_backend_rejects_version_specific_operations() {
+        let mut api = CloudreveAPI::with_backend(Arc::new(MemoryBackend::default()));
+
+        assert!(api.try_inner().is_none());
+        let result = api.login("user@example.com", "password").await;
+        assert!(matches!(result, Err(Error::UnsupportedFeature(_, _))));
+    }
+
+    #[tokio::test]
+    async fn test_api_can_wrap_another_api() {
+        let inner = CloudreveAPI::with_backend(Arc::new(MemoryBackend::default()));
+        inner.create_directory("/shared").await.unwrap();
+
+        let outer = CloudreveAPI::with_backend(Arc::new(inner));
+        let info = outer.get_file_info("/shared").await.unwrap();
+        assert!(info.is_folder());
+        assert_eq!(outer.get_server_version().await.unwrap(), "memory");
+    }
+}
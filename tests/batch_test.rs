@@ -0,0 +1,57 @@
+use cloudreve_api::BatchResult;
+use cloudreve_api::cloudreve_api::batch::plan_batches;
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_batches_respects_max_size() {
+        let paths = ["/a/1", "/a/2", "/a/3", "/a/4", "/a/5"];
+        let batches = plan_batches(&paths, 2, true);
+        assert_eq!(
+            batches,
+            vec![vec!["/a/1", "/a/2"], vec!["/a/3", "/a/4"], vec!["/a/5"]]
+        );
+    }
+
+    #[test]
+    fn test_plan_batches_groups_siblings() {
+        let paths = ["/a/1", "/b/1", "/a/2", "/3", "/b/2/"];
+        let batches = plan_batches(&paths, 10, true);
+        assert_eq!(
+            batches,
+            vec![vec!["/a/1", "/a/2"], vec!["/b/1", "/b/2/"], vec!["/3"]]
+        );
+    }
+
+    #[test]
+    fn test_plan_batches_without_grouping() {
+        let paths = ["/a/1", "/b/1", "/a/2"];
+        let batches = plan_batches(&paths, 2, false);
+        assert_eq!(batches, vec![vec!["/a/1", "/b/1"], vec!["/a/2"]]);
+    }
+
+    #[test]
+    fn test_plan_batches_zero_size_treated_as_one() {
+        let batches = plan_batches(&["/a", "/b"], 0, false);
+        assert_eq!(batches, vec![vec!["/a"], vec!["/b"]]);
+    }
+
+    #[test]
+    fn test_batch_result_merge() {
+        let mut total = BatchResult {
+            succeeded: 2,
+            failed: 1,
+            errors: vec![("/a".to_string(), "not found".to_string())],
+        };
+        total.merge(BatchResult {
+            succeeded: 3,
+            failed: 1,
+            errors: vec![("/b".to_string(), "denied".to_string())],
+        });
+        assert_eq!(total.succeeded, 5);
+        assert_eq!(total.failed, 2);
+        assert_eq!(total.errors.len(), 2);
+    }
+}
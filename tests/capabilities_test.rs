@@ -0,0 +1,75 @@
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::api::v3::models::SiteConfig as V3SiteConfig;
+use cloudreve_api::api::v4::models::SiteConfig as V4SiteConfig;
+use cloudreve_api::{Capabilities, Feature, SiteConfigValue};
+
+#[cfg(test)]
+mod capabilities_tests {
+    use super::*;
+
+    fn v4_config(json: &str) -> SiteConfigValue {
+        let config: V4SiteConfig = serde_json::from_str(json).unwrap();
+        SiteConfigValue::V4(Box::new(config))
+    }
+
+    #[test]
+    fn test_version_features_without_site_config() {
+        let v3 = Capabilities::new(ApiVersion::V3, None, None);
+        assert!(v3.supports(Feature::Preview));
+        assert!(!v3.supports(Feature::RestoreFromTrash));
+        assert!(!v3.supports(Feature::ShareUpdate));
+        assert!(v3.supports(Feature::ShareDelete));
+
+        let v4 = Capabilities::new(ApiVersion::V4, Some("4.1.0".to_string()), None);
+        assert!(v4.supports(Feature::RestoreFromTrash));
+        assert!(v4.supports(Feature::DavAccountManagement));
+        assert!(v4.supports(Feature::Preview));
+        assert_eq!(v4.server_version.as_deref(), Some("4.1.0"));
+    }
+
+    #[test]
+    fn test_v4_site_flags() {
+        let config = v4_config(
+            r#"{
+                "authn": true,
+                "register_enabled": false,
+                "point_enabled": true,
+                "thumb_exts": ["jpg", "png"],
+                "file_viewers": [{"extensions": ["pdf"], "handler": "pdf", "name": "PDF"}]
+            }"#,
+        );
+        let caps = Capabilities::new(ApiVersion::V4, None, Some(&config));
+
+        assert!(caps.supports(Feature::Passkeys));
+        assert!(caps.supports(Feature::Points));
+        assert!(caps.supports(Feature::FileViewers));
+        assert!(!caps.supports(Feature::Registration));
+        assert_eq!(caps.file_viewers.len(), 1);
+    }
+
+    #[test]
+    fn test_v3_site_flags() {
+        let config: V3SiteConfig =
+            serde_json::from_str(r#"{"authn": false, "register_enabled": true}"#).unwrap();
+        let caps = Capabilities::new(ApiVersion::V3, None, Some(&SiteConfigValue::V3(config)));
+
+        assert!(caps.supports(Feature::Registration));
+        assert!(!caps.supports(Feature::Passkeys));
+        assert!(!caps.supports(Feature::Points));
+    }
+
+    #[test]
+    fn test_can_thumbnail_respects_extensions() {
+        let v3 = Capabilities::new(ApiVersion::V3, None, None);
+        assert!(v3.can_thumbnail("photo.heic"));
+
+        let mut limited = Capabilities::new(ApiVersion::V3, None, None);
+        limited.thumb_exts = Some(vec!["jpg".to_string()]);
+        assert!(limited.can_thumbnail("photo.JPG"));
+        assert!(!limited.can_thumbnail("notes.txt"));
+        assert!(!limited.can_thumbnail("README"));
+
+        let v4 = Capabilities::new(ApiVersion::V4, None, None);
+        assert!(v4.can_thumbnail("photo.jpg"));
+    }
+}
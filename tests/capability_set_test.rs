@@ -0,0 +1,64 @@
+use cloudreve_api::FileList;
+use cloudreve_api::api::v4::models::*;
+
+#[cfg(test)]
+mod capability_set_tests {
+    use super::*;
+
+    fn file(name: &str, capability: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "type": 0, "id": name, "name": name,
+            "created_at": "2023-01-01T00:00:00Z", "updated_at": "2023-01-01T00:00:00Z",
+            "size": 1, "path": format!("cloudreve://my/{}", name),
+            "capability": capability, "owned": true
+        })
+    }
+
+    #[test]
+    fn test_decode_capabilities() {
+        let set = CapabilitySet::from_boolset("gkA=").unwrap();
+        assert!(set.contains(FileCapability::Rename));
+        assert!(set.contains(FileCapability::Download));
+        assert!(set.contains(FileCapability::Delete));
+        assert!(!set.contains(FileCapability::Share));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![
+                FileCapability::Rename,
+                FileCapability::Download,
+                FileCapability::Delete
+            ]
+        );
+        assert_eq!(set.to_boolset(), "gkA=");
+        assert_eq!(set.iter().collect::<CapabilitySet>(), set);
+
+        assert!(CapabilitySet::from_boolset("").is_none());
+        assert!(CapabilitySet::from_boolset("not base64!").is_none());
+    }
+
+    #[test]
+    fn test_file_items_use_capabilities() {
+        let own: CapabilitySet = [FileCapability::Download].into_iter().collect();
+        let listing: ListResponse = serde_json::from_value(serde_json::json!({
+            "files": [file("own.txt", Some(&own.to_boolset())), file("inherited.txt", None)],
+            "parent": file("", None),
+            "pagination": {"page": 0, "page_size": 50, "is_cursor": false},
+            "props": {
+                "capability": "gkA=",
+                "max_page_size": 2000,
+                "order_by_options": [],
+                "order_direction_options": []
+            },
+            "context_hint": "",
+            "mixed_type": false,
+            "view": null
+        }))
+        .unwrap();
+
+        let items = FileList::V4(Box::new(listing)).items();
+        assert!(items[0].can(FileCapability::Download));
+        assert!(!items[0].can(FileCapability::Rename));
+        assert!(items[1].can(FileCapability::Rename));
+        assert!(!items[1].can(FileCapability::Share));
+    }
+}
@@ -0,0 +1,208 @@
+//! In-memory backend shared by the custom backend tests
+
+#![allow(dead_code)]
+
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::api::v3::models::{DirectoryList, Object, Policy};
+use cloudreve_api::{
+    BackendFuture, CloudreveBackend, Error, FileInfo, FileList, FileListAll, StorageQuota, UserInfo,
+};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// In-memory backend storing file contents by path
+#[derive(Default)]
+pub struct MemoryBackend {
+    pub objects: Mutex<BTreeMap<String, Option<Vec<u8>>>>,
+    pub list_calls: Mutex<usize>,
+    /// Make moves and copies into directories fail
+    pub fail_transfers: AtomicBool,
+}
+
+fn parent_and_name(path: &str) -> (String, String) {
+    let (parent, name) = path.rsplit_once('/').unwrap();
+    let parent = if parent.is_empty() { "/" } else { parent };
+    (parent.to_string(), name.to_string())
+}
+
+fn object(path: &str, content: &Option<Vec<u8>>) -> Object {
+    let (_, name) = parent_and_name(path);
+    Object {
+        id: path.to_string(),
+        name,
+        path: path.to_string(),
+        thumb: false,
+        size: content.as_ref().map_or(0, |c| c.len() as i64),
+        object_type: if content.is_some() { "file" } else { "dir" }.to_string(),
+        date: String::new(),
+        create_date: String::new(),
+        source_enabled: false,
+    }
+}
+
+impl MemoryBackend {
+    /// Paths of all stored objects
+    pub fn paths(&self) -> Vec<String> {
+        self.objects.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Content of a stored file
+    pub fn content(&self, path: &str) -> Option<Vec<u8>> {
+        self.objects.lock().unwrap().get(path).cloned().flatten()
+    }
+
+    fn directory(&self, path: &str) -> DirectoryList {
+        *self.list_calls.lock().unwrap() += 1;
+        let objects = self.objects.lock().unwrap();
+        DirectoryList {
+            parent: path.to_string(),
+            objects: objects
+                .iter()
+                .filter(|(p, _)| parent_and_name(p).0 == path)
+                .map(|(p, c)| object(p, c))
+                .collect(),
+            policy: Policy::default(),
+        }
+    }
+
+    fn find(&self, path: &str) -> Option<FileInfo> {
+        let objects = self.objects.lock().unwrap();
+        objects.get(path).map(|c| FileInfo::V3(object(path, c)))
+    }
+
+    fn relocate(&self, src: &str, dir: &str, keep_source: bool) -> Result<(), Error> {
+        if self.fail_transfers.load(Ordering::SeqCst) {
+            return Err(Error::InvalidResponse(format!("Transfer failed: {}", src)));
+        }
+        let mut objects = self.objects.lock().unwrap();
+        let content = objects
+            .get(src)
+            .cloned()
+            .ok_or_else(|| Error::InvalidResponse(format!("File not found: {}", src)))?;
+        if !keep_source {
+            objects.remove(src);
+        }
+        let (_, name) = parent_and_name(src);
+        objects.insert(format!("{}/{}", dir.trim_end_matches('/'), name), content);
+        Ok(())
+    }
+}
+
+impl CloudreveBackend for MemoryBackend {
+    fn api_version(&self) -> ApiVersion {
+        ApiVersion::V3
+    }
+
+    fn base_url(&self) -> &str {
+        "memory://test"
+    }
+
+    fn ping(&self) -> BackendFuture<'_, String> {
+        Box::pin(async { Ok("memory".to_string()) })
+    }
+
+    fn list_files<'a>(
+        &'a self,
+        path: &'a str,
+        _page: Option<u32>,
+        _page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileList> {
+        Box::pin(async move { Ok(FileList::V3(self.directory(path))) })
+    }
+
+    fn list_files_all<'a>(
+        &'a self,
+        path: &'a str,
+        _page_size: Option<u32>,
+    ) -> BackendFuture<'a, FileListAll> {
+        Box::pin(async move { Ok(FileListAll::V3(self.directory(path))) })
+    }
+
+    fn get_file_info<'a>(&'a self, path: &'a str) -> BackendFuture<'a, FileInfo> {
+        Box::pin(async move {
+            self.find(path)
+                .ok_or_else(|| Error::InvalidResponse(format!("File not found: {}", path)))
+        })
+    }
+
+    fn lookup<'a>(&'a self, path: &'a str) -> BackendFuture<'a, Option<FileInfo>> {
+        Box::pin(async move { Ok(self.find(path)) })
+    }
+
+    fn create_directory<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.objects.lock().unwrap().insert(path.to_string(), None);
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.objects.lock().unwrap().remove(path);
+            Ok(())
+        })
+    }
+
+    fn rename<'a>(&'a self, path: &'a str, new_name: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let mut objects = self.objects.lock().unwrap();
+            let content = objects.remove(path).unwrap_or_default();
+            let (parent, _) = parent_and_name(path);
+            objects.insert(
+                format!("{}/{}", parent.trim_end_matches('/'), new_name),
+                content,
+            );
+            Ok(())
+        })
+    }
+
+    fn move_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move { self.relocate(src, dir, false) })
+    }
+
+    fn copy_into_dir<'a>(&'a self, src: &'a str, dir: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move { self.relocate(src, dir, true) })
+    }
+
+    fn upload_file<'a>(
+        &'a self,
+        path: &'a str,
+        content: Vec<u8>,
+        _policy_id: Option<&'a str>,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), Some(content));
+            Ok(())
+        })
+    }
+
+    fn download_url<'a>(&'a self, path: &'a str) -> BackendFuture<'a, String> {
+        Box::pin(async move { Ok(format!("memory://test{}", path)) })
+    }
+
+    fn user_info(&self) -> BackendFuture<'_, UserInfo> {
+        Box::pin(async {
+            Ok(UserInfo {
+                id: "1".to_string(),
+                email: "test@example.com".to_string(),
+                nickname: "test".to_string(),
+                group: None,
+                status: None,
+            })
+        })
+    }
+
+    fn storage_quota(&self) -> BackendFuture<'_, StorageQuota> {
+        Box::pin(async {
+            Ok(StorageQuota {
+                used: 0,
+                total: 0,
+                free: 0,
+            })
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::api::v4::models::*;
+use cloudreve_api::{CloudreveAPI, Error};
+
+#[cfg(test)]
+mod direct_link_tests {
+    use super::*;
+
+    #[test]
+    fn test_create_direct_link_request() {
+        let request = CreateDirectLinkRequest {
+            uris: vec!["cloudreve://my/wiki/diagram.png".to_string()],
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"uris": ["cloudreve://my/wiki/diagram.png"]})
+        );
+    }
+
+    #[test]
+    fn test_direct_link_responses() {
+        let created: Vec<DirectLinkResponse> = serde_json::from_value(serde_json::json!([
+            {"link": "https://drive.example.com/f/aB3x/diagram.png", "file_url": "cloudreve://my/wiki/diagram.png"},
+            {"link": "https://drive.example.com/f/Zk9q/logo.svg"}
+        ]))
+        .unwrap();
+        assert_eq!(created[0].file_url, "cloudreve://my/wiki/diagram.png");
+        assert!(created[1].file_url.is_empty());
+
+        let info: ExtendedInfo = serde_json::from_value(serde_json::json!({
+            "storage_policy_inherited": true,
+            "storage_used": 1024,
+            "permissions": null,
+            "direct_links": [
+                {"id": "aB3x", "url": "https://drive.example.com/f/aB3x/diagram.png", "downloaded": 12, "created_at": "2024-01-01T00:00:00Z"}
+            ]
+        }))
+        .unwrap();
+        let links = info.direct_links.unwrap();
+        assert_eq!(links[0].id, "aB3x");
+        assert_eq!(links[0].downloaded, 12.0);
+    }
+
+    #[tokio::test]
+    async fn test_v3_cannot_delete_direct_links() {
+        let api = CloudreveAPI::with_version("http://127.0.0.1:9", ApiVersion::V3).unwrap();
+        let result = api.delete_direct_link("aB3x").await;
+        assert!(matches!(result, Err(Error::UnsupportedFeature(_, _))));
+    }
+}
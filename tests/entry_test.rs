@@ -0,0 +1,89 @@
+use cloudreve_api::api::v3::models::{DirectoryList, Object};
+use cloudreve_api::api::v4::models::File;
+use cloudreve_api::cloudreve_api::entry::parse_timestamp;
+use cloudreve_api::{Entry, EntryKind, Error, FileInfo, FileList, RawEntry};
+
+#[cfg(test)]
+mod entry_tests {
+    use super::*;
+
+    fn v3_object(name: &str, object_type: &str, date: &str) -> Object {
+        Object {
+            id: "obj-1".to_string(),
+            name: name.to_string(),
+            path: "/docs".to_string(),
+            thumb: false,
+            size: 2048,
+            object_type: object_type.to_string(),
+            date: date.to_string(),
+            create_date: "2024-05-01T11:19:12.1733916+08:00".to_string(),
+            source_enabled: false,
+        }
+    }
+
+    fn v4_file(json: &str) -> File {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_entry_from_v3_object() {
+        let entry = Entry::from_v3(v3_object("report.PDF", "file", "2024-05-02 08:00:00")).unwrap();
+
+        assert_eq!(entry.path, "/docs/report.PDF");
+        assert_eq!(entry.kind, EntryKind::File);
+        assert_eq!(entry.size, 2048);
+        assert_eq!(entry.mime.as_deref(), Some("application/pdf"));
+        assert_eq!(entry.extension().as_deref(), Some("pdf"));
+        assert_eq!(
+            entry.created_at.to_rfc3339(),
+            "2024-05-01T03:19:12.173391600+00:00"
+        );
+        assert_eq!(entry.updated_at.to_rfc3339(), "2024-05-02T08:00:00+00:00");
+        assert!(matches!(entry.raw, RawEntry::V3(ref obj) if obj.id == "obj-1"));
+    }
+
+    #[test]
+    fn test_entry_from_v4_folder() {
+        let file = v4_file(
+            r#"{"type": 1, "id": "2", "name": "photos", "created_at": "2023-01-01T00:00:00Z",
+                "updated_at": "2023-01-02T10:30:00+02:00", "size": 0, "path": "cloudreve://my/media/photos",
+                "owned": true}"#,
+        );
+        let entry = FileInfo::V4(file).to_entry().unwrap();
+
+        assert_eq!(entry.path, "/media/photos");
+        assert!(entry.is_folder());
+        assert!(entry.mime.is_none());
+        assert_eq!(entry.updated_at.to_rfc3339(), "2023-01-02T08:30:00+00:00");
+    }
+
+    #[test]
+    fn test_invalid_timestamp_is_reported() {
+        let result = Entry::from_v3(v3_object("a.txt", "file", "yesterday"));
+        assert!(matches!(result, Err(Error::InvalidTimestamp(value)) if value == "yesterday"));
+    }
+
+    #[test]
+    fn test_listing_skips_bad_entries() {
+        let listing = FileList::V3(DirectoryList {
+            parent: "parent".to_string(),
+            objects: vec![
+                v3_object("bad.txt", "file", "yesterday"),
+                v3_object("photo.tiff", "file", "2024-05-02 08:00:00"),
+            ],
+            policy: Default::default(),
+        });
+        let entries = listing.entries();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "photo.tiff");
+        assert_eq!(entries[0].mime.as_deref(), Some("image/tiff"));
+    }
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        assert!(parse_timestamp("2024-05-01T11:19:12Z").is_ok());
+        assert!(parse_timestamp("2024-05-01 11:19:12.5").is_ok());
+        assert!(parse_timestamp("").is_err());
+    }
+}
@@ -94,6 +94,7 @@ mod file_tests {
             capability: Some("read".to_string()),
             owned: true,
             primary_entity: Some("primary".to_string()),
+            extended_info: None,
             permission: Some("read".to_string()),
         };
 
@@ -0,0 +1,93 @@
+use chrono::{TimeZone, Utc};
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::{CloudreveAPI, Error, FileMetadata, MediaMetadata};
+
+#[cfg(test)]
+mod media_tests {
+    use super::*;
+
+    fn media(value: serde_json::Value) -> MediaMetadata {
+        MediaMetadata::from_metadata(&FileMetadata::from_value(Some(&value)))
+    }
+
+    #[test]
+    fn test_image_metadata() {
+        let parsed = media(serde_json::json!({
+            "exif:camera_make": "Canon",
+            "exif:camera_model": "EOS R5",
+            "exif:taken_at": "2024:05:01 08:30:00",
+            "exif:latitude": "48.8584",
+            "exif:longitude": "2.2945",
+            "exif:iso": 400,
+            "exif:f": "2.8",
+            "exif:exposure_time": "1/250",
+            "exif:x": "8192",
+            "exif:y": "5464",
+            "tag:holiday": ""
+        }));
+        let image = parsed.image.unwrap();
+        assert_eq!(image.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(image.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(
+            image.taken_at,
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 30, 0).unwrap())
+        );
+        let gps = image.gps.unwrap();
+        assert_eq!(
+            (gps.latitude, gps.longitude, gps.altitude),
+            (48.8584, 2.2945, None)
+        );
+        assert_eq!(image.iso, Some(400));
+        assert_eq!(image.f_number, Some(2.8));
+        assert_eq!(image.exposure_time.as_deref(), Some("1/250"));
+        assert_eq!((image.width, image.height), (Some(8192), Some(5464)));
+        assert!(parsed.audio.is_none() && parsed.video.is_none());
+    }
+
+    #[test]
+    fn test_audio_and_video() {
+        let parsed = media(serde_json::json!({
+            "music:title": "Song",
+            "music:artist": "Band",
+            "music:year": "1999",
+            "music:track": "3/12",
+            "stream:duration": "215.4",
+            "stream:bit_rate": "320000",
+            "stream:format": "mp3"
+        }));
+        let audio = parsed.audio.unwrap();
+        assert_eq!(audio.title.as_deref(), Some("Song"));
+        assert_eq!(audio.artist.as_deref(), Some("Band"));
+        assert_eq!(
+            (audio.year, audio.track, audio.disc),
+            (Some(1999), Some(3), None)
+        );
+
+        let video = parsed.video.unwrap();
+        assert_eq!(video.duration, Some(215.4));
+        assert_eq!(video.bit_rate, Some(320000));
+        assert_eq!(video.format.as_deref(), Some("mp3"));
+        assert!(parsed.image.is_none());
+    }
+
+    #[test]
+    fn test_empty_and_rfc3339() {
+        assert!(media(serde_json::json!({"tag:a": ""})).is_empty());
+
+        let parsed = media(serde_json::json!({"exif:taken_at": "2024-05-01T08:30:00+02:00"}));
+        assert_eq!(
+            parsed.taken_at(),
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 6, 30, 0).unwrap())
+        );
+        assert!(parsed.image.unwrap().gps.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_photos_taken_between_fails_on_v3() {
+        let api = CloudreveAPI::with_version("http://127.0.0.1:9", ApiVersion::V3).unwrap();
+        let result = api
+            .photos_taken_between("/", Utc::now(), Utc::now(), true)
+            .await;
+        assert!(matches!(result, Err(Error::UnsupportedFeature(_, _))));
+    }
+}
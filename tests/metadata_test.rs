@@ -0,0 +1,120 @@
+use cloudreve_api::api::v4::models::{CustomProps, MetadataPatch, PatchMetadataRequest};
+use cloudreve_api::cloudreve_api::metadata::validate_prop;
+use cloudreve_api::{Error, FileMetadata, MetadataChange, MetadataKey};
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+
+    fn prop(json: &str) -> CustomProps {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_key_namespaces() {
+        assert_eq!(
+            MetadataKey::parse("tag:work"),
+            MetadataKey::Tag("work".to_string())
+        );
+        assert_eq!(
+            MetadataKey::parse("props:owner"),
+            MetadataKey::Prop("owner".to_string())
+        );
+        let system = MetadataKey::parse("exif:camera_make");
+        assert_eq!(system.to_raw(), "exif:camera_make");
+        assert!(!system.is_writable());
+        assert_eq!(MetadataKey::Tag("work".to_string()).to_raw(), "tag:work");
+    }
+
+    #[test]
+    fn test_file_metadata_groups() {
+        let value = serde_json::json!({
+            "tag:work": "#ff0000",
+            "props:rating": "4",
+            "exif:iso": 200
+        });
+        let metadata = FileMetadata::from_value(Some(&value));
+        assert_eq!(
+            metadata.tags.get("work").map(String::as_str),
+            Some("#ff0000")
+        );
+        assert_eq!(
+            metadata.get(&MetadataKey::Prop("rating".to_string())),
+            Some("4")
+        );
+        assert_eq!(
+            metadata.system.get("exif:iso").map(String::as_str),
+            Some("200")
+        );
+        assert_eq!(FileMetadata::from_value(None), FileMetadata::default());
+    }
+
+    #[test]
+    fn test_validate_prop_values() {
+        let number = prop(r#"{"id": "size", "type": "number", "min": 0, "max": 10}"#);
+        assert!(validate_prop(&number, "3.5").is_ok());
+        assert!(matches!(
+            validate_prop(&number, "11"),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(validate_prop(&number, "abc").is_err());
+
+        let select = prop(r#"{"key": "stage", "type": "select", "options": ["draft", "final"]}"#);
+        assert!(validate_prop(&select, "final").is_ok());
+        assert!(validate_prop(&select, "other").is_err());
+
+        let multi = prop(r#"{"key": "labels", "type": "multi_select", "options": ["a", "b"]}"#);
+        assert!(validate_prop(&multi, r#"["a", "b"]"#).is_ok());
+        assert!(validate_prop(&multi, r#"["c"]"#).is_err());
+        assert!(validate_prop(&multi, "a").is_err());
+
+        let text = prop(r#"{"key": "note", "type": "text", "max": 3}"#);
+        assert!(validate_prop(&text, "abc").is_ok());
+        assert!(validate_prop(&text, "abcd").is_err());
+
+        let rating = prop(r#"{"key": "stars", "type": "rating"}"#);
+        assert!(validate_prop(&rating, "5").is_ok());
+        assert!(validate_prop(&rating, "6").is_err());
+
+        let boolean = prop(r#"{"key": "done", "type": "boolean"}"#);
+        assert!(validate_prop(&boolean, "true").is_ok());
+        assert!(validate_prop(&boolean, "yes").is_err());
+
+        let link = prop(r#"{"key": "source", "type": "link"}"#);
+        assert!(validate_prop(&link, "https://example.com").is_ok());
+        assert!(validate_prop(&link, "example").is_err());
+        assert_eq!(link.prop_key(), "source");
+    }
+
+    #[test]
+    fn test_patch_request_shape() {
+        let change = MetadataChange::remove(MetadataKey::Tag("old".to_string()));
+        assert_eq!(change.value, None);
+
+        let request = PatchMetadataRequest {
+            uris: vec!["cloudreve://my/a.txt".to_string()],
+            patches: vec![
+                MetadataPatch {
+                    key: "tag:new".to_string(),
+                    value: "#00ff00".to_string(),
+                    remove: false,
+                },
+                MetadataPatch {
+                    key: "tag:old".to_string(),
+                    value: String::new(),
+                    remove: true,
+                },
+            ],
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "uris": ["cloudreve://my/a.txt"],
+                "patches": [
+                    {"key": "tag:new", "value": "#00ff00"},
+                    {"key": "tag:old", "value": "", "remove": true}
+                ]
+            })
+        );
+    }
+}
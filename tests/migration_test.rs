@@ -0,0 +1,194 @@
+use cloudreve_api::Share;
+use cloudreve_api::WebdavAccount;
+use cloudreve_api::api::v3::models::ShareSource;
+use cloudreve_api::cloudreve_api::migration::{InventoryShare, remap_path};
+use cloudreve_api::{MigrationOptions, MigrationPlan, V3Inventory};
+
+#[cfg(test)]
+mod migration_tests {
+    use super::*;
+
+    fn share(key: &str, password: &str, expire: i64) -> Share {
+        Share {
+            key: key.to_string(),
+            password: password.to_string(),
+            expire,
+            downloads: -1,
+            remain_downloads: -1,
+            source: Some(ShareSource {
+                name: "report.pdf".to_string(),
+                size: 1024,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn inventory() -> V3Inventory {
+        V3Inventory {
+            base_url: "https://old.example.com/".to_string(),
+            shares: vec![
+                InventoryShare {
+                    share: share("abc", "secret", 3600),
+                    source_path: Some("/docs/report.pdf".to_string()),
+                },
+                InventoryShare {
+                    share: share("missing", "", -1),
+                    source_path: None,
+                },
+            ],
+            dav_accounts: vec![WebdavAccount {
+                id: 7,
+                name: "laptop".to_string(),
+                uri: "/docs".to_string(),
+                password: "old".to_string(),
+                created_at: "2023-01-01T00:00:00Z".to_string(),
+            }],
+            nickname: Some("alice".to_string()),
+            preferred_theme: None,
+        }
+    }
+
+    #[test]
+    fn test_remap_path() {
+        assert_eq!(remap_path("/a/b", "/"), "/a/b");
+        assert_eq!(remap_path("/a/b", "/v3/"), "/v3/a/b");
+        assert_eq!(remap_path("/", "/"), "/");
+        assert_eq!(remap_path("/", "/v3"), "/v3");
+    }
+
+    #[test]
+    fn test_plan_maps_shares() {
+        let options = MigrationOptions {
+            dest_root: "/from-v3".to_string(),
+            ..Default::default()
+        };
+        let plan = MigrationPlan::new(&inventory(), &options);
+
+        assert_eq!(plan.shares.len(), 1);
+        let planned = &plan.shares[0];
+        assert_eq!(planned.old_key, "abc");
+        assert_eq!(planned.old_url, "https://old.example.com/s/abc");
+        assert_eq!(planned.request.uri, "/from-v3/docs/report.pdf");
+        assert_eq!(planned.request.password.as_deref(), Some("secret"));
+        assert_eq!(planned.request.is_private, Some(true));
+        assert_eq!(planned.request.expire, Some(3600));
+
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].0, "share missing");
+    }
+
+    #[test]
+    fn test_plan_maps_dav_accounts_and_settings() {
+        let plan = MigrationPlan::new(&inventory(), &MigrationOptions::default());
+
+        assert_eq!(plan.dav_accounts.len(), 1);
+        assert_eq!(plan.dav_accounts[0].old_id, 7);
+        assert_eq!(plan.dav_accounts[0].request.uri, "/docs");
+        assert_eq!(plan.dav_accounts[0].request.name, "laptop");
+
+        let settings = plan.settings.expect("settings should be planned");
+        assert_eq!(settings.nickname.as_deref(), Some("alice"));
+        assert_eq!(settings.preferred_theme, None);
+    }
+
+    #[test]
+    fn test_plan_respects_options() {
+        let options = MigrationOptions {
+            shares: false,
+            dav_accounts: false,
+            settings: false,
+            ..Default::default()
+        };
+        let plan = MigrationPlan::new(&inventory(), &options);
+
+        assert!(plan.shares.is_empty());
+        assert!(plan.dav_accounts.is_empty());
+        assert!(plan.settings.is_none());
+        assert!(plan.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_plan_skips_exhausted_and_keeps_limits() {
+        let mut exhausted = share("used", "", -1);
+        exhausted.downloads = 5;
+        exhausted.remain_downloads = 0;
+        let mut limited = share("limited", "", -1);
+        limited.downloads = 5;
+        limited.remain_downloads = 2;
+
+        let inventory = V3Inventory {
+            shares: vec![
+                InventoryShare {
+                    share: exhausted,
+                    source_path: Some("/a".to_string()),
+                },
+                InventoryShare {
+                    share: limited,
+                    source_path: Some("/b".to_string()),
+                },
+            ],
+            ..Default::default()
+        };
+        let plan = MigrationPlan::new(&inventory, &MigrationOptions::default());
+
+        assert_eq!(plan.shares.len(), 1);
+        assert_eq!(plan.shares[0].old_key, "limited");
+        assert_eq!(plan.shares[0].request.expire, None);
+        assert_eq!(plan.shares[0].request.is_private, Some(false));
+        assert_eq!(plan.shares[0].request.downloads, Some(2));
+        assert_eq!(plan.skipped.len(), 1);
+        assert!(plan.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_plan_skips_expired_shares() {
+        let mut previewed = share("live", "", 60);
+        previewed.preview = true;
+        let inventory = V3Inventory {
+            shares: vec![
+                InventoryShare {
+                    share: share("expired", "", 0),
+                    source_path: Some("/a".to_string()),
+                },
+                InventoryShare {
+                    share: previewed,
+                    source_path: Some("/b".to_string()),
+                },
+            ],
+            ..Default::default()
+        };
+        let plan = MigrationPlan::new(&inventory, &MigrationOptions::default());
+
+        assert_eq!(plan.shares.len(), 1);
+        assert_eq!(plan.shares[0].old_key, "live");
+        assert_eq!(plan.shares[0].request.share_view, Some(true));
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].0, "share expired");
+    }
+
+    #[test]
+    fn test_plan_warns_about_reduced_fidelity() {
+        let mut inventory = inventory();
+        inventory.shares[0].share.views = 12;
+        let plan = MigrationPlan::new(&inventory, &MigrationOptions::default());
+
+        assert_eq!(
+            plan.warnings
+                .iter()
+                .map(|(label, _)| label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["share abc", "WebDAV account laptop"]
+        );
+        assert!(plan.warnings[0].1.contains("12"));
+    }
+
+    #[test]
+    fn test_share_list_deserializes_create_date() {
+        let json = r#"{"key":"k1","create_date":"2023-05-01","views":3,
+            "source":{"name":"a.txt","size":10},"expire":-1}"#;
+        let share: Share = serde_json::from_str(json).unwrap();
+        assert_eq!(share.created_at, "2023-05-01");
+        assert_eq!(share.views, 3);
+        assert_eq!(share.source.unwrap().name, "a.txt");
+    }
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use cloudreve_api::{CloudreveAPI, Destination, Error};
+use common::MemoryBackend;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+/// API over a memory backend with `/docs/a.txt`, `/docs/b.txt` and an
+/// empty `/archive`
+async fn setup() -> (CloudreveAPI, Arc<MemoryBackend>) {
+    let backend = Arc::new(MemoryBackend::default());
+    let api = CloudreveAPI::with_backend(backend.clone());
+    api.create_directory("/docs").await.unwrap();
+    api.create_directory("/archive").await.unwrap();
+    api.upload_file("/docs/a.txt", b"a".to_vec(), None)
+        .await
+        .unwrap();
+    api.upload_file("/docs/b.txt", b"b".to_vec(), None)
+        .await
+        .unwrap();
+    (api, backend)
+}
+
+#[cfg(test)]
+mod move_copy_tests {
+    use super::*;
+
+    #[test]
+    fn test_destination_into_dir_keeps_name() {
+        let dest = Destination::IntoDir("/archive/");
+        assert_eq!(dest.resolve("report.pdf"), ("/archive", "report.pdf"));
+    }
+
+    #[test]
+    fn test_destination_path_renames() {
+        let dest = Destination::Path("/docs/report-v2.pdf");
+        assert_eq!(dest.resolve("report.pdf"), ("/docs", "report-v2.pdf"));
+    }
+
+    #[test]
+    fn test_destination_path_in_root() {
+        let dest = Destination::Path("/copy.txt");
+        assert_eq!(dest.resolve("original.txt"), ("/", "copy.txt"));
+    }
+
+    #[test]
+    fn test_already_exists_error_display() {
+        let error = Error::AlreadyExists("/docs/report.pdf".to_string());
+        let message = format!("{}", error);
+        assert!(message.contains("already exists"));
+        assert!(message.contains("/docs/report.pdf"));
+    }
+
+    #[tokio::test]
+    async fn test_existing_target_is_kept_without_overwrite() {
+        let (api, backend) = setup().await;
+
+        let moved = api
+            .move_to("/docs/a.txt", Destination::Path("/docs/b.txt"), false)
+            .await;
+        assert!(matches!(moved, Err(Error::AlreadyExists(path)) if path == "/docs/b.txt"));
+        let copied = api
+            .copy_to("/docs/a.txt", Destination::Path("/docs/b.txt"), false)
+            .await;
+        assert!(matches!(copied, Err(Error::AlreadyExists(_))));
+
+        assert_eq!(backend.content("/docs/a.txt").unwrap(), b"a");
+        assert_eq!(backend.content("/docs/b.txt").unwrap(), b"b");
+    }
+
+    #[tokio::test]
+    async fn test_folder_cannot_be_placed_inside_itself() {
+        let (api, backend) = setup().await;
+        api.create_directory("/docs/sub").await.unwrap();
+        let before = backend.paths();
+
+        let moved = api
+            .move_to("/docs", Destination::IntoDir("/docs/sub"), false)
+            .await;
+        assert!(matches!(moved, Err(Error::InvalidResponse(_))));
+        let copied = api
+            .copy_to("/docs", Destination::IntoDir("/docs"), false)
+            .await;
+        assert!(matches!(copied, Err(Error::InvalidResponse(_))));
+
+        assert_eq!(backend.paths(), before);
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_missing_folder_fails() {
+        let (api, backend) = setup().await;
+        let before = backend.paths();
+
+        let copied = api
+            .copy_to("/docs/a.txt", Destination::IntoDir("/missing"), false)
+            .await;
+        assert!(matches!(copied, Err(Error::InvalidResponse(_))));
+        assert_eq!(backend.paths(), before);
+    }
+
+    #[tokio::test]
+    async fn test_copy_and_move_with_rename() {
+        let (api, backend) = setup().await;
+
+        let copy = api
+            .copy_to("/docs/a.txt", Destination::Path("/archive/c.txt"), false)
+            .await
+            .unwrap();
+        assert_eq!(copy, "/archive/c.txt");
+        assert_eq!(backend.content("/archive/c.txt").unwrap(), b"a");
+        assert!(backend.content("/docs/a.txt").is_some());
+
+        let moved = api
+            .move_to("/docs/b.txt", Destination::Path("/archive/d.txt"), false)
+            .await
+            .unwrap();
+        assert_eq!(moved, "/archive/d.txt");
+        assert_eq!(backend.content("/archive/d.txt").unwrap(), b"b");
+        assert!(backend.content("/docs/b.txt").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_overwrite_restores_original() {
+        let (api, backend) = setup().await;
+        api.upload_file("/archive/b.txt", b"old".to_vec(), None)
+            .await
+            .unwrap();
+        backend.fail_transfers.store(true, Ordering::SeqCst);
+
+        let moved = api
+            .move_to("/docs/a.txt", Destination::Path("/archive/b.txt"), true)
+            .await;
+        assert!(moved.is_err());
+        let copied = api
+            .copy_to("/docs/b.txt", Destination::IntoDir("/archive"), true)
+            .await;
+        assert!(copied.is_err());
+
+        assert_eq!(backend.content("/archive/b.txt").unwrap(), b"old");
+        assert_eq!(backend.content("/docs/a.txt").unwrap(), b"a");
+        assert!(!backend.paths().iter().any(|p| p.contains(".cloudreve-")));
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_removes_parked_original() {
+        let (api, backend) = setup().await;
+        api.upload_file("/archive/a.txt", b"old".to_vec(), None)
+            .await
+            .unwrap();
+
+        api.copy_to("/docs/a.txt", Destination::IntoDir("/archive"), true)
+            .await
+            .unwrap();
+
+        assert_eq!(backend.content("/archive/a.txt").unwrap(), b"a");
+        assert!(!backend.paths().iter().any(|p| p.contains(".cloudreve-")));
+    }
+}
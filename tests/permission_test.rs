@@ -0,0 +1,119 @@
+use cloudreve_api::api::v4::models::{
+    Permission, PermissionSet, PermissionSetting, PermissionSubject, SetFilePermissionRequest,
+};
+
+#[cfg(test)]
+mod permission_tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_set_flags() {
+        let set = PermissionSet::read_only().with(Permission::Create);
+        assert!(set.is_readable() && set.can_create());
+        assert!(!set.is_writable() && !set.can_delete());
+        assert!(!set.without(Permission::Read).is_readable());
+        assert!(PermissionSet::empty().is_empty());
+        assert_eq!(
+            PermissionSet::read_only().union(PermissionSet::read_write()),
+            PermissionSet::read_write()
+        );
+    }
+
+    #[test]
+    fn test_boolset_encoding() {
+        assert_eq!(PermissionSet::read_only().to_boolset(), "AQ==");
+        assert_eq!(PermissionSet::read_write().to_boolset(), "Dw==");
+        assert_eq!(PermissionSet::empty().to_boolset(), "");
+        assert_eq!(
+            PermissionSet::from_boolset("BQ==").unwrap(),
+            PermissionSet::read_only().with(Permission::Create)
+        );
+        assert_eq!(
+            PermissionSet::from_boolset("").unwrap(),
+            PermissionSet::empty()
+        );
+        assert!(PermissionSet::from_boolset("not base64!").is_err());
+    }
+
+    #[test]
+    fn test_setting_deserialization() {
+        let setting: PermissionSetting = serde_json::from_value(serde_json::json!({
+            "user_explicit": {"42": "Dw=="},
+            "group_explicit": null,
+            "same_group": "read",
+            "other": "none",
+            "anonymous": "AQ=="
+        }))
+        .unwrap();
+        assert_eq!(
+            setting.user_explicit.get("42"),
+            Some(&PermissionSet::read_write())
+        );
+        assert!(setting.group_explicit.is_empty());
+        assert_eq!(setting.same_group, Some(PermissionSet::read_only()));
+        assert_eq!(setting.other, Some(PermissionSet::empty()));
+        assert_eq!(setting.anonymous, Some(PermissionSet::read_only()));
+        assert_eq!(setting.everyone, None);
+    }
+
+    #[test]
+    fn test_effective_permissions() {
+        let setting = PermissionSetting::default()
+            .grant_user("7", PermissionSet::empty())
+            .grant_group("3", PermissionSet::read_write())
+            .with_same_group(PermissionSet::read_only().with(Permission::Update))
+            .with_other(PermissionSet::empty())
+            .with_everyone(PermissionSet::read_only());
+
+        let visitor = PermissionSubject::default();
+        assert!(setting.effective(&visitor).is_empty());
+
+        let banned = PermissionSubject {
+            user_id: Some("7"),
+            group_id: Some("3"),
+            same_group: true,
+        };
+        assert!(setting.effective(&banned).is_empty());
+
+        let editor = PermissionSubject {
+            user_id: Some("8"),
+            group_id: Some("3"),
+            same_group: false,
+        };
+        assert_eq!(setting.effective(&editor), PermissionSet::read_write());
+
+        let colleague = PermissionSubject {
+            user_id: Some("9"),
+            group_id: Some("1"),
+            same_group: true,
+        };
+        assert!(setting.effective(&colleague).is_writable());
+
+        let stranger = PermissionSubject {
+            user_id: Some("10"),
+            group_id: Some("2"),
+            same_group: false,
+        };
+        assert_eq!(setting.effective(&stranger), PermissionSet::read_only());
+    }
+
+    #[test]
+    fn test_set_permission_request_shape() {
+        let setting = PermissionSetting::default()
+            .grant_user("42", PermissionSet::read_write())
+            .with_anonymous(PermissionSet::empty());
+        let request = SetFilePermissionRequest {
+            uri: "cloudreve://my/docs",
+            permissions: &setting,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "uri": "cloudreve://my/docs",
+                "user_explicit": {"42": "Dw=="},
+                "group_explicit": {},
+                "anonymous": ""
+            })
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use cloudreve_api::api::v4::models::{File, TaskResponse};
+use cloudreve_api::{RelocationReport, TaskOutcome};
+
+#[cfg(test)]
+mod relocate_tests {
+    use super::*;
+
+    fn outcome(status: &str) -> TaskOutcome {
+        let json = format!(
+            r#"{{
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:05:00Z",
+                "id": "task-1",
+                "status": "{}",
+                "type": "relocate"
+            }}"#,
+            status
+        );
+        let task: TaskResponse = serde_json::from_str(&json).unwrap();
+        TaskOutcome::from_task(task).unwrap()
+    }
+
+    fn report(status: &str) -> RelocationReport {
+        RelocationReport {
+            outcome: outcome(status),
+            files: 2,
+            total_bytes: 2048,
+            misplaced: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_report_verification() {
+        assert!(report("completed").is_verified());
+        assert!(!report("canceled").is_verified());
+
+        let mut misplaced = report("completed");
+        misplaced
+            .misplaced
+            .push(("/cold/a.bin".to_string(), Some("hot".to_string())));
+        assert!(!misplaced.is_verified());
+
+        let mut unchecked = report("completed");
+        unchecked
+            .errors
+            .push(("/cold/b.bin".to_string(), "not found".to_string()));
+        assert!(!unchecked.is_verified());
+    }
+
+    #[test]
+    fn test_file_extended_storage_policy() {
+        let json = r#"{
+            "type": 0,
+            "id": "f1",
+            "name": "a.bin",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "size": 1024,
+            "path": "cloudreve://my/cold/a.bin",
+            "owned": true,
+            "extended_info": {
+                "storage_policy": {"id": "cold", "name": "Archive", "type": "s3", "max_size": 0},
+                "storage_policy_inherited": false,
+                "storage_used": 1024
+            }
+        }"#;
+        let file: File = serde_json::from_str(json).unwrap();
+        let policy = file.extended_info.unwrap().storage_policy.unwrap();
+        assert_eq!(policy.id, "cold");
+    }
+}
@@ -0,0 +1,114 @@
+use cloudreve_api::api::v3::models::{Aria2Downloading, Aria2Finished};
+use cloudreve_api::api::v4::models::TaskResponse;
+use cloudreve_api::{RemoteDownload, RemoteDownloadState};
+
+#[cfg(test)]
+mod remote_download_tests {
+    use super::*;
+
+    #[test]
+    fn test_v3_active_download() {
+        let json = r#"{
+            "name": "ubuntu.iso",
+            "status": 1,
+            "dst": "/downloads",
+            "total": 1000,
+            "downloaded": 250,
+            "speed": 50,
+            "info": {
+                "gid": "2089b05ecca3d829",
+                "files": [
+                    {"index": "1", "path": "/tmp/ubuntu.iso", "length": "1000",
+                     "completedLength": "250", "selected": "true"}
+                ]
+            }
+        }"#;
+        let task: Aria2Downloading = serde_json::from_str(json).unwrap();
+
+        let download = RemoteDownload::from_v3_downloading(task);
+        assert_eq!(download.id, "2089b05ecca3d829");
+        assert_eq!(download.state, RemoteDownloadState::Downloading);
+        assert_eq!((download.total, download.downloaded), (1000, 250));
+        assert_eq!(download.files.len(), 1);
+        assert_eq!(download.files[0].index, 1);
+        assert!(download.files[0].selected);
+    }
+
+    #[test]
+    fn test_v3_finished_download_states() {
+        let finished = |status, task_status, task_error: &str| {
+            RemoteDownload::from_v3_finished(Aria2Finished {
+                gid: "g".to_string(),
+                status,
+                task_status,
+                task_error: task_error.to_string(),
+                total: 10,
+                ..Default::default()
+            })
+        };
+
+        let done = finished(4, 4, "");
+        assert_eq!(done.state, RemoteDownloadState::Completed);
+        assert_eq!(done.downloaded, 10);
+        assert_eq!(finished(4, 1, "").state, RemoteDownloadState::Transferring);
+
+        let failed = finished(4, 2, "disk full");
+        assert_eq!(failed.state, RemoteDownloadState::Error);
+        assert_eq!(failed.error.as_deref(), Some("disk full"));
+        assert_eq!(finished(5, 0, "").state, RemoteDownloadState::Canceled);
+    }
+
+    #[test]
+    fn test_v4_download_task() {
+        let json = r#"{
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:01:00Z",
+            "id": "task1",
+            "status": "processing",
+            "type": "remote_download",
+            "summary": {
+                "phase": "monitor",
+                "props": {
+                    "src_str": "magnet:?xt=urn:btih:abc",
+                    "dst": "cloudreve://my/downloads",
+                    "download": {
+                        "name": "show",
+                        "state": "downloading",
+                        "total": 200,
+                        "downloaded": 50,
+                        "download_speed": 10,
+                        "files": [
+                            {"index": 0, "name": "a.mkv", "size": 100, "progress": 0.5, "selected": true},
+                            {"index": 1, "name": "b.nfo", "size": 100, "progress": 0, "selected": false}
+                        ]
+                    }
+                }
+            }
+        }"#;
+        let task: TaskResponse = serde_json::from_str(json).unwrap();
+
+        let download = RemoteDownload::from_v4(task);
+        assert_eq!(download.id, "task1");
+        assert_eq!(download.name, "show");
+        assert_eq!(download.state, RemoteDownloadState::Downloading);
+        assert_eq!(download.destination, "/downloads");
+        assert_eq!(download.files[0].downloaded, 50);
+        assert!(!download.files[1].selected);
+        assert!(!download.state.is_finished());
+    }
+
+    #[test]
+    fn test_v4_transfer_phase() {
+        let json = r#"{
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:01:00Z",
+            "id": "task2",
+            "status": "suspending",
+            "type": "remote_download",
+            "summary": {"phase": "transfer", "props": {}}
+        }"#;
+        let task: TaskResponse = serde_json::from_str(json).unwrap();
+        let download = RemoteDownload::from_v4(task);
+        assert_eq!(download.state, RemoteDownloadState::Transferring);
+    }
+}
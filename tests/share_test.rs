@@ -1,5 +1,7 @@
-use cloudreve_api::Result;
+use cloudreve_api::api::ApiVersion;
+use cloudreve_api::api::v3::models::{Share, ShareSource};
 use cloudreve_api::api::v4::models::*;
+use cloudreve_api::{CloudreveAPI, Error, Result, ShareItem, ShareOptions};
 
 #[cfg(test)]
 mod share_tests {
@@ -8,14 +10,11 @@ mod share_tests {
     #[tokio::test]
     async fn test_create_share_request_struct() -> Result<()> {
         let _create_request = CreateShareLinkRequest {
-            permissions: PermissionSetting {
-                user_explicit: serde_json::json!({}),
-                group_explicit: serde_json::json!({}),
-                same_group: "read".to_string(),
-                other: "none".to_string(),
-                anonymous: "none".to_string(),
-                everyone: "read".to_string(),
-            },
+            permissions: PermissionSetting::default()
+                .with_same_group(PermissionSet::read_only())
+                .with_other(PermissionSet::empty())
+                .with_anonymous(PermissionSet::empty())
+                .with_everyone(PermissionSet::read_only()),
             uri: "/path/file.txt".to_string(),
             is_private: Some(false),
             share_view: Some(true),
@@ -23,6 +22,7 @@ mod share_tests {
             price: Some(0),
             password: Some("password".to_string()),
             show_readme: Some(true),
+            downloads: Some(10),
         };
         Ok(())
     }
@@ -30,19 +30,19 @@ mod share_tests {
     #[tokio::test]
     async fn test_edit_share_request_struct() -> Result<()> {
         let _edit_request = EditShareLinkRequest {
-            permissions: PermissionSetting {
-                user_explicit: serde_json::json!({}),
-                group_explicit: serde_json::json!({}),
-                same_group: "read".to_string(),
-                other: "none".to_string(),
-                anonymous: "none".to_string(),
-                everyone: "read".to_string(),
-            },
+            permissions: PermissionSetting::default()
+                .with_same_group(PermissionSet::read_only())
+                .with_other(PermissionSet::empty())
+                .with_anonymous(PermissionSet::empty())
+                .with_everyone(PermissionSet::read_only()),
             uri: "/path/file.txt".to_string(),
+            is_private: Some(true),
             share_view: Some(true),
             expire: Some(7200),
             price: Some(0),
+            password: Some("secret".to_string()),
             show_readme: Some(false),
+            downloads: None,
         };
         Ok(())
     }
@@ -55,14 +55,11 @@ mod share_tests {
 
     #[test]
     fn test_share_structs() {
-        let _permission_setting = PermissionSetting {
-            user_explicit: serde_json::json!({}),
-            group_explicit: serde_json::json!({}),
-            same_group: "read".to_string(),
-            other: "none".to_string(),
-            anonymous: "none".to_string(),
-            everyone: "read".to_string(),
-        };
+        let _permission_setting = PermissionSetting::default()
+            .with_same_group(PermissionSet::read_only())
+            .with_other(PermissionSet::empty())
+            .with_anonymous(PermissionSet::empty())
+            .with_everyone(PermissionSet::read_only());
 
         let _share = ShareLink {
             id: "1".to_string(),
@@ -106,6 +103,107 @@ mod share_tests {
             expires: Some("2024-01-01T00:00:00Z".to_string()),
             expired_at: Some("2024-01-01T00:00:00Z".to_string()),
             download_count: 5,
+            remain_downloads: Some(3),
+        };
+    }
+
+    #[test]
+    fn test_share_item_from_v3() {
+        let share = Share {
+            key: "abc123".to_string(),
+            password: "pw".to_string(),
+            is_dir: true,
+            downloads: 4,
+            remain_downloads: 6,
+            expire: -1,
+            preview: true,
+            views: 12,
+            source: Some(ShareSource {
+                name: "photos".to_string(),
+                size: 0,
+            }),
+            ..Default::default()
         };
+
+        let item = ShareItem::from_v3(share, "https://example.com/");
+        assert_eq!(item.id, "abc123");
+        assert_eq!(item.url, "https://example.com/s/abc123");
+        assert_eq!(item.name, "photos");
+        assert!(item.is_folder);
+        assert_eq!(item.password.as_deref(), Some("pw"));
+        assert_eq!((item.views, item.downloads), (12, 4));
+        assert_eq!(item.remaining_downloads, Some(6));
+        assert!(item.expires_at.is_none());
+        assert!(!item.expired);
+    }
+
+    #[test]
+    fn test_share_item_from_v3_exhausted() {
+        let share = Share {
+            key: "used".to_string(),
+            downloads: 3,
+            remain_downloads: 0,
+            expire: -1,
+            ..Default::default()
+        };
+        assert!(ShareItem::from_v3(share, "https://example.com").expired);
+    }
+
+    #[test]
+    fn test_share_item_from_v4() {
+        let json = r#"{
+            "id": "Xy12",
+            "name": "report.pdf",
+            "visited": 8,
+            "downloaded": 2,
+            "price": 5,
+            "unlocked": true,
+            "source_type": 0,
+            "owner": {"id": "1", "created_at": "2024-01-01T00:00:00Z"},
+            "created_at": "2024-01-01T00:00:00Z",
+            "expired": false,
+            "url": "https://example.com/s/Xy12",
+            "password": "",
+            "source_uri": "cloudreve://my/docs/report.pdf",
+            "share_view": true,
+            "show_readme": false,
+            "expires": "2030-01-01T00:00:00Z",
+            "remain_downloads": -1
+        }"#;
+        let link: ShareLink = serde_json::from_str(json).unwrap();
+
+        let item = ShareItem::from_v4(link);
+        assert_eq!(item.id, "Xy12");
+        assert!(!item.is_folder);
+        assert!(item.password.is_none());
+        assert_eq!((item.views, item.downloads), (8, 2));
+        assert_eq!(item.remaining_downloads, None);
+        assert_eq!(item.expires_at.as_deref(), Some("2030-01-01T00:00:00Z"));
+        assert!(item.preview);
+        assert_eq!(item.price, 5);
+        assert_eq!(item.source_path.as_deref(), Some("/docs/report.pdf"));
+    }
+
+    #[test]
+    fn test_share_options_default_keeps_settings() {
+        let options = ShareOptions::default();
+        assert!(options.password.is_none());
+        assert!(options.expires.is_none());
+        assert!(options.download_limit.is_none());
+        assert!(options.permissions.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_open_share_rejects_link_without_id() {
+        let api = CloudreveAPI::with_version("http://127.0.0.1:9", ApiVersion::V4).unwrap();
+        let result = api.open_share("http://127.0.0.1:9/s/", None).await;
+        assert!(matches!(result, Err(Error::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_open_share_rejects_other_server() {
+        let api = CloudreveAPI::with_version("http://127.0.0.1:9", ApiVersion::V4).unwrap();
+        let result = api.open_share("https://example.com/s/abc123", None).await;
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
     }
 }
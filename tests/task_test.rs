@@ -0,0 +1,79 @@
+use cloudreve_api::api::v4::models::TaskResponse;
+use cloudreve_api::{TaskOutcome, WaitOptions};
+use std::time::Duration;
+
+#[cfg(test)]
+mod task_tests {
+    use super::*;
+
+    fn task(status: &str, extra: &str) -> TaskResponse {
+        let json = format!(
+            r#"{{
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:05:00Z",
+                "id": "task-1",
+                "status": "{}",
+                "type": "extract_archive"{}
+            }}"#,
+            status, extra
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_outcome_of_running_task() {
+        assert!(TaskOutcome::from_task(task("queued", "")).is_none());
+        assert!(TaskOutcome::from_task(task("processing", "")).is_none());
+        assert!(TaskOutcome::from_task(task("suspending", "")).is_none());
+    }
+
+    #[test]
+    fn test_outcome_of_finished_task() {
+        let completed = TaskOutcome::from_task(task("completed", "")).unwrap();
+        assert!(completed.is_completed());
+        assert_eq!(completed.task().id, "task-1");
+
+        let canceled = TaskOutcome::from_task(task("canceled", "")).unwrap();
+        assert!(matches!(canceled, TaskOutcome::Canceled(_)));
+
+        let failed = TaskOutcome::from_task(task(
+            "error",
+            r#", "error": "disk full", "error_history": ["timeout", "disk full"]"#,
+        ))
+        .unwrap();
+        match failed {
+            TaskOutcome::Error {
+                error,
+                error_history,
+                ..
+            } => {
+                assert_eq!(error.as_deref(), Some("disk full"));
+                assert_eq!(error_history, vec!["timeout", "disk full"]);
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_wait_backoff_is_capped() {
+        let options = WaitOptions::default();
+        assert_eq!(options.timeout, None);
+        assert_eq!(
+            options.next_interval(Duration::from_secs(1)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            options.next_interval(Duration::from_secs(20)),
+            Duration::from_secs(30)
+        );
+
+        let flat = WaitOptions {
+            backoff: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            flat.next_interval(Duration::from_secs(3)),
+            Duration::from_secs(3)
+        );
+    }
+}
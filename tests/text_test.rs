@@ -0,0 +1,34 @@
+use cloudreve_api::{Error, TextFile, WriteTextOptions};
+use encoding_rs::{GB18030, UTF_8};
+
+#[cfg(test)]
+mod text_tests {
+    use super::*;
+
+    #[test]
+    fn test_write_options_keep_encoding_and_version() {
+        let text = TextFile {
+            content: "中文".to_string(),
+            encoding: GB18030,
+            bom: false,
+            version: "entity-1".to_string(),
+        };
+        let options = text.write_options();
+        assert_eq!(options.expected_version.as_deref(), Some("entity-1"));
+        assert_eq!(options.encoding, GB18030);
+        assert!(!options.bom);
+
+        let defaults = WriteTextOptions::default();
+        assert_eq!(defaults.expected_version, None);
+        assert_eq!(defaults.encoding, UTF_8);
+    }
+
+    #[test]
+    fn test_conflict_error_message() {
+        let error = Error::Conflict("/notes.txt is at version b, expected a".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Version conflict: /notes.txt is at version b, expected a"
+        );
+    }
+}
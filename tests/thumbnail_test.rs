@@ -0,0 +1,27 @@
+use cloudreve_api::ThumbnailOptions;
+use cloudreve_api::api::v4::models::ThumbnailResponse;
+
+#[cfg(test)]
+mod thumbnail_tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_response_forms() {
+        let signed: ThumbnailResponse = serde_json::from_str(
+            r#"{"url": "https://cdn.example.com/t.jpg?sign=x", "expires": "2024-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(signed.url(), "https://cdn.example.com/t.jpg?sign=x");
+
+        let plain: ThumbnailResponse = serde_json::from_str(r#""/api/v4/file/thumb/abc""#).unwrap();
+        assert_eq!(plain.url(), "/api/v4/file/thumb/abc");
+    }
+
+    #[test]
+    fn test_thumbnail_options_default() {
+        let options = ThumbnailOptions::default();
+        assert_eq!(options.width, None);
+        assert_eq!(options.height, None);
+        assert!(options.cache_dir.is_none());
+    }
+}
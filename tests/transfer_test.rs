@@ -0,0 +1,55 @@
+use cloudreve_api::{TransferCheckpoint, TransferOptions, TransferReport};
+
+#[cfg(test)]
+mod transfer_tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_options_default() {
+        let options = TransferOptions::default();
+        assert!(options.checkpoint.is_none());
+        assert!(!options.overwrite);
+    }
+
+    #[test]
+    fn test_transfer_report_default() {
+        let report = TransferReport::default();
+        assert_eq!(report.files_transferred, 0);
+        assert_eq!(report.files_skipped, 0);
+        assert_eq!(report.directories_created, 0);
+        assert_eq!(report.bytes_transferred, 0);
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_normalizes_roots() {
+        let checkpoint = TransferCheckpoint::new("/photos/", "/backup/photos/");
+        assert_eq!(checkpoint.src_root, "/photos");
+        assert_eq!(checkpoint.dest_root, "/backup/photos");
+        assert!(checkpoint.matches("/photos", "/backup/photos/"));
+        assert!(!checkpoint.matches("/photos", "/other"));
+    }
+
+    #[test]
+    fn test_checkpoint_save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "cloudreve-transfer-test-{}.json",
+            std::process::id()
+        ));
+        let mut checkpoint = TransferCheckpoint::new("/", "/migrated");
+        checkpoint.completed.insert("a.txt".to_string());
+        checkpoint.completed.insert("folder/b.txt".to_string());
+
+        checkpoint.save(&path).unwrap();
+        let loaded = TransferCheckpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, Some(checkpoint));
+    }
+
+    #[test]
+    fn test_checkpoint_load_missing_file() {
+        let path = std::env::temp_dir().join("cloudreve-transfer-test-missing.json");
+        assert_eq!(TransferCheckpoint::load(&path).unwrap(), None);
+    }
+}
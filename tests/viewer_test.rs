@@ -0,0 +1,81 @@
+use cloudreve_api::api::v4::models::{FileViewer, ViewerSessionResponse};
+use cloudreve_api::cloudreve_api::viewer::pick_viewer;
+use cloudreve_api::{ViewerKind, ViewerLaunch};
+
+#[cfg(test)]
+mod viewer_tests {
+    use super::*;
+
+    fn viewers() -> Vec<FileViewer> {
+        serde_json::from_str(
+            r#"[
+                {"viewers": [
+                    {"id": "pdf", "type": "builtin", "display_name": "PDF", "exts": ["pdf"]},
+                    {"id": "office", "type": "wopi", "display_name": "Office",
+                     "exts": ["docx", "pdf"], "priority": 5, "max_size": 1000}
+                ]},
+                {"id": "old", "name": "Old", "extensions": ["docx"], "disabled": true, "priority": 9}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pick_viewer_by_extension_and_priority() {
+        let viewers = viewers();
+        assert_eq!(pick_viewer(&viewers, "a.PDF", 10).unwrap().id, "office");
+        assert_eq!(pick_viewer(&viewers, "a.pdf", 5000).unwrap().id, "pdf");
+        assert_eq!(pick_viewer(&viewers, "a.docx", 10).unwrap().name, "Office");
+        assert!(pick_viewer(&viewers, "a.docx", 5000).is_none());
+        assert!(pick_viewer(&viewers, "README", 10).is_none());
+    }
+
+    #[test]
+    fn test_pick_viewer_prefers_launchable_viewers() {
+        let viewers: Vec<FileViewer> = serde_json::from_str(
+            r#"[
+                {"id": "md", "type": "builtin", "exts": ["md"], "priority": 9},
+                {"id": "editor", "type": "custom", "exts": ["md"], "priority": 1,
+                 "url": "https://editor.example.com/?src={$src}"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(pick_viewer(&viewers, "a.md", 10).unwrap().id, "editor");
+    }
+
+    #[test]
+    fn test_viewer_session_response_forms() {
+        let nested: ViewerSessionResponse = serde_json::from_str(
+            r#"{"session": {"id": "s1", "access_token": "tok", "expires": "2024-01-01T00:00:00Z"},
+                "wopi_src": "https://drive.example.com/wopi/files/s1"}"#,
+        )
+        .unwrap();
+        assert_eq!(nested.id(), "s1");
+        assert_eq!(nested.session.unwrap().access_token, "tok");
+
+        let flat: ViewerSessionResponse = serde_json::from_str(r#"{"session_id": "s2"}"#).unwrap();
+        assert_eq!(flat.id(), "s2");
+    }
+
+    #[test]
+    fn test_form_fields_only_for_wopi() {
+        let mut launch = ViewerLaunch {
+            viewer: FileViewer::default(),
+            kind: ViewerKind::Wopi,
+            url: "https://office.example.com/view".to_string(),
+            session_id: "s1".to_string(),
+            access_token: Some("tok".to_string()),
+            access_token_ttl: Some(1704067200000),
+        };
+        assert_eq!(
+            launch.form_fields(),
+            vec![
+                ("access_token", "tok".to_string()),
+                ("access_token_ttl", "1704067200000".to_string())
+            ]
+        );
+
+        launch.kind = ViewerKind::Custom;
+        assert!(launch.form_fields().is_empty());
+    }
+}
@@ -40,6 +40,9 @@ mod workflow_tests {
         let _extract_request = ExtractArchiveRequest {
             src: vec!["/path/archive.zip"],
             dst: "/extracted",
+            encoding: None,
+            password: None,
+            file_mask: None,
         };
         Ok(())
     }
@@ -89,4 +92,31 @@ mod workflow_tests {
             tasks: vec![], // Using empty vector since TaskResponse is different from DetailedTask
         };
     }
+
+    #[test]
+    fn test_extract_archive_request_options() {
+        let plain = ExtractArchiveRequest {
+            src: vec!["cloudreve://my/a.zip"],
+            dst: "cloudreve://my/out",
+            encoding: None,
+            password: None,
+            file_mask: None,
+        };
+        let json = serde_json::to_value(&plain).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"src": ["cloudreve://my/a.zip"], "dst": "cloudreve://my/out"})
+        );
+
+        let selective = ExtractArchiveRequest {
+            encoding: Some("gbk"),
+            password: Some("secret"),
+            file_mask: Some(vec!["docs/readme.txt"]),
+            ..plain
+        };
+        let json = serde_json::to_value(&selective).unwrap();
+        assert_eq!(json["encoding"], "gbk");
+        assert_eq!(json["password"], "secret");
+        assert_eq!(json["file_mask"], serde_json::json!(["docs/readme.txt"]));
+    }
 }